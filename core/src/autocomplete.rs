@@ -0,0 +1,54 @@
+//! Completing the phrase being typed from phrases used before
+
+/// The number of characters typed before completions are offered
+pub const MIN_CHARS: usize = 3;
+
+/// The rest of a phrase that the text just before the cursor starts
+///
+/// The typed phrase can start at any word in the current part of the prompt,
+/// where parts are split by commas, lines, and permutation braces, and longer
+/// phrases are tried first. Candidates are tried in order, ignoring case.
+/// Nothing is offered unless the cursor is at the end of a line, so that the
+/// completion doesn't cover the text after it.
+pub fn completion<'a>(
+    text: &str,
+    cursor: usize,
+    candidates: &'a [impl AsRef<str>],
+) -> Option<&'a str> {
+    let at_line_end = (text.get(cursor..))
+        .is_some_and(|after| after.is_empty() || after.starts_with(['\n', '\r']));
+    if !at_line_end {
+        return None;
+    }
+    let before = &text[..cursor];
+    if before.ends_with(char::is_whitespace) {
+        return None;
+    }
+    let part_start = before
+        .rfind([',', '\n', '{', '}', '|', ':'])
+        .map_or(0, |i| i + 1);
+    let part = &before[part_start..];
+    let word_starts = part
+        .char_indices()
+        .filter(|&(i, c)| {
+            !c.is_whitespace() && (i == 0 || part[..i].ends_with(char::is_whitespace))
+        })
+        .map(|(i, _)| i);
+    for start in word_starts {
+        let typed = &part[start..];
+        if typed.chars().count() < MIN_CHARS {
+            break;
+        }
+        let typed_lower = typed.to_lowercase();
+        let rest = candidates.iter().find_map(|candidate| {
+            let candidate = candidate.as_ref().trim();
+            let (head, rest) =
+                candidate.split_at(candidate.char_indices().nth(typed.chars().count())?.0);
+            (head.to_lowercase() == typed_lower).then_some(rest)
+        });
+        if rest.is_some() {
+            return rest;
+        }
+    }
+    None
+}
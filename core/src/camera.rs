@@ -0,0 +1,89 @@
+//! Photography terms for the shot, lens, and film, which the photorealistic
+//! models respond to
+
+use serde::{Deserialize, Serialize};
+
+pub const SHOTS: &[&str] = &[
+    "extreme close-up",
+    "close-up",
+    "medium shot",
+    "full body shot",
+    "wide shot",
+    "establishing shot",
+    "low angle shot",
+    "high angle shot",
+    "overhead shot",
+    "over-the-shoulder shot",
+    "dutch angle",
+];
+
+/// Focal lengths in millimeters
+pub const FOCAL_LENGTHS: &[u32] = &[14, 24, 35, 50, 85, 135, 200, 400];
+
+/// Apertures as f-numbers
+pub const APERTURES: &[f32] = &[1.2, 1.4, 1.8, 2.8, 4.0, 5.6, 8.0, 11.0, 16.0];
+
+pub const FILM_STOCKS: &[&str] = &[
+    "Kodak Portra 400",
+    "Kodak Ektar 100",
+    "Kodachrome 64",
+    "Kodak Tri-X 400",
+    "Fujifilm Velvia 50",
+    "Fujifilm Pro 400H",
+    "Ilford HP5 Plus",
+    "CineStill 800T",
+    "Polaroid SX-70",
+];
+
+/// The chosen camera settings, any of which may be left out
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Camera {
+    #[serde(default)]
+    pub shot: Option<String>,
+    #[serde(default)]
+    pub focal_length: Option<u32>,
+    #[serde(default)]
+    pub aperture: Option<f32>,
+    #[serde(default)]
+    pub film: Option<String>,
+}
+
+impl Camera {
+    /// The photographic terms for the chosen settings, one per setting
+    pub fn terms(&self) -> Vec<String> {
+        let mut terms = Vec::new();
+        if let Some(shot) = &self.shot {
+            terms.push(shot.clone());
+        }
+        if let Some(mm) = self.focal_length {
+            terms.push(lens(mm));
+        }
+        if let Some(f) = self.aperture {
+            terms.push(match f {
+                f if f <= 2.8 => format!("{}, shallow depth of field", f_number(f)),
+                f if f >= 8.0 => format!("{}, deep depth of field", f_number(f)),
+                f => f_number(f),
+            });
+        }
+        if let Some(film) = &self.film {
+            terms.push(format!("shot on {film}"));
+        }
+        terms
+    }
+}
+
+/// A lens described by the kind that photographers call it
+pub fn lens(mm: u32) -> String {
+    match mm {
+        0..=20 => format!("{mm}mm ultra wide-angle lens"),
+        21..=28 => format!("{mm}mm wide-angle lens"),
+        29..=60 => format!("{mm}mm lens"),
+        61..=135 => format!("{mm}mm portrait lens"),
+        _ => format!("{mm}mm telephoto lens"),
+    }
+}
+
+/// An aperture written the way it is on lenses, like `f/1.8` or `f/4`
+pub fn f_number(f: f32) -> String {
+    format!("f/{}", (f * 10.0).round() / 10.0)
+}
@@ -0,0 +1,165 @@
+//! Building the command string
+//!
+//! Flags are always written in the same order, which can be changed with
+//! [`Params::flag_order`], and flags with default values are left out, so
+//! that saved presets keep giving the same command.
+
+use std::fmt::Write;
+
+use crate::{enabled_urls, info, is_url, ImagePrompt, Params, Quality};
+
+impl Params {
+    /// Build the command for the given prompt text
+    #[allow(unused_must_use)]
+    pub fn command(&self, text: &str) -> String {
+        let images: Vec<&ImagePrompt> = self
+            .images
+            .iter()
+            .filter(|image| image.enabled && is_url(&image.url))
+            .collect();
+        let mut s = String::new();
+        let prefix = self.prefix.trim();
+        if !prefix.is_empty() {
+            write!(&mut s, "{prefix} ");
+        }
+        for image in &images {
+            write!(&mut s, "{} ", image.url.trim());
+        }
+        s.push_str(text.trim());
+        let sep = &self.separator;
+        if let Some(style) = &self.style.curr {
+            write!(&mut s, "{sep}{}", style.trim());
+        }
+        if let Some(body) = &self.body.curr {
+            write!(&mut s, "{sep}{} body", body.trim());
+        }
+        if let Some(hair) = &self.hair.curr {
+            write!(&mut s, "{sep}{} hair", hair.trim());
+        }
+        if let Some(pose) = &self.pose.curr {
+            write!(&mut s, "{sep}{} pose", pose.trim());
+        }
+        for (theme, weight) in self.enabled_themes() {
+            if weight.is_none() {
+                write!(&mut s, "{sep}{theme}");
+            }
+        }
+        for (options, enabled) in &self.permutations {
+            if *enabled && !options.trim().is_empty() {
+                write!(&mut s, "{sep}{{{}}}", options.trim());
+            }
+        }
+        if let Some(color) = &self.color.curr {
+            write!(&mut s, "{sep}{} colors", color.trim());
+        }
+        let weighted_themes = self
+            .enabled_themes()
+            .filter_map(|(theme, weight)| Some((theme, weight?)));
+        let mut segments = self
+            .segments
+            .iter()
+            .filter(|segment| !segment.text.trim().is_empty())
+            .map(|segment| (segment.text.trim(), segment.weight))
+            .chain(weighted_themes)
+            .peekable();
+        if segments.peek().is_some() {
+            s.push_str("::");
+            for (text, weight) in segments {
+                write!(&mut s, " {text}::{weight}");
+            }
+        }
+        let mut flags = self.flags(&images);
+        if !self.flag_order.is_empty() {
+            // Flags missing from the order keep their default order at the end
+            flags.sort_by_key(|(key, _)| {
+                (self.flag_order.iter())
+                    .position(|ordered| ordered == key)
+                    .unwrap_or(usize::MAX)
+            });
+        }
+        for (_, flag) in flags {
+            write!(&mut s, " {flag}");
+        }
+        s
+    }
+    /// The flags of the command in the default order, each with its key in
+    /// [`flag_keys`]
+    fn flags(&self, images: &[&ImagePrompt]) -> Vec<(&'static str, String)> {
+        let mut flags = Vec::new();
+        let mut add = |key, flag: String| flags.push((key, flag));
+        let negatives: Vec<&str> = self
+            .negatives
+            .iter()
+            .filter(|(neg, enabled)| *enabled && !neg.trim().is_empty())
+            .map(|(neg, _)| neg.trim())
+            .collect();
+        if !negatives.is_empty() {
+            add("no", format!("--no {}", negatives.join(", ")));
+        }
+        // Midjourney only accepts a single image weight, so use the average
+        let weights: Vec<f32> = images.iter().filter_map(|image| image.weight).collect();
+        if !weights.is_empty() {
+            let weight = weights.iter().sum::<f32>() / weights.len() as f32;
+            add("iw", format!("--iw {}", (weight * 100.0).round() / 100.0));
+        }
+        if self.quality != Quality::default() {
+            add("q", format!("--q {}", self.quality));
+        }
+        let supported = info::numbers().filter(|(info, _)| (info.supported)(&self.algorithm));
+        for (info, number) in supported {
+            if let Some(value) = number.flag_value(self) {
+                add(info.flags[0], format!("--{} {value}", info.flags[0]));
+            }
+        }
+        if let Some([w, h]) = self.aspect.wh() {
+            add("ar", format!("--ar {w}:{h}"));
+        }
+        if self.algorithm.supports_references() {
+            let style_refs = enabled_urls(&self.style_refs);
+            if !style_refs.is_empty() {
+                add("sref", format!("--sref {}", style_refs.join(" ")));
+            }
+            let character_refs = enabled_urls(&self.character_refs);
+            if !character_refs.is_empty() {
+                add("cref", format!("--cref {}", character_refs.join(" ")));
+            }
+        }
+        if self.video {
+            add("video", "--video".into());
+        }
+        if self.tile && self.algorithm.supports_tile() {
+            add("tile", "--tile".into());
+        }
+        if self.use_seed {
+            add("seed", format!("--{} {}", self.seed_mode, self.seed));
+        }
+        if let Some(speed) = self.speed {
+            add("speed", format!("--{speed}"));
+        }
+        if let Some(flag) = self.personalization.flag() {
+            add("p", flag);
+        }
+        if let Some(style) = self.model_style {
+            if self.algorithm.allowed_styles().contains(&style) {
+                add("style", format!("--style {style}"));
+            }
+        }
+        add("version", self.algorithm.flag().into());
+        flags
+    }
+}
+
+/// The keys that [`Params::flag_order`] orders flags by, in the default order
+///
+/// Most are the flag without `--`. The seed, speed, and version flags have
+/// more than one spelling, so they are `seed`, `speed`, and `version`.
+pub fn flag_keys() -> Vec<&'static str> {
+    let numbers = info::numbers().map(|(info, _)| info.flags[0]);
+    ["no", "iw", "q"]
+        .into_iter()
+        .chain(numbers)
+        .chain([
+            "ar", "sref", "cref", "video", "tile", "seed", "speed", "p", "style", "version",
+        ])
+        .collect()
+}
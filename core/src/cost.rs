@@ -0,0 +1,119 @@
+//! Rough estimates of the GPU time and subscription cost of a command
+//!
+//! Midjourney bills fast GPU time, and an average job at the default
+//! quality takes about a minute of it. The other factors come from the
+//! docs and are only meant to catch unexpectedly heavy commands.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{info, permute, Algorithm, Params, Quality, Speed};
+
+/// The GPU minutes of an average job at the default settings
+const BASE_MINUTES: f32 = 1.0;
+
+/// A Midjourney subscription plan, which sets the price of fast time
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Plan {
+    Basic,
+    #[default]
+    Standard,
+    Pro,
+    Mega,
+}
+
+impl Plan {
+    pub const ALL: [Plan; 4] = [Plan::Basic, Plan::Standard, Plan::Pro, Plan::Mega];
+    pub fn str(&self) -> &'static str {
+        match self {
+            Plan::Basic => "Basic",
+            Plan::Standard => "Standard",
+            Plan::Pro => "Pro",
+            Plan::Mega => "Mega",
+        }
+    }
+    /// The monthly price in dollars and the fast hours it includes
+    fn price_and_hours(&self) -> (f32, f32) {
+        match self {
+            Plan::Basic => (10.0, 3.3),
+            Plan::Standard => (30.0, 15.0),
+            Plan::Pro => (60.0, 30.0),
+            Plan::Mega => (120.0, 60.0),
+        }
+    }
+    /// The price of a minute of fast time in dollars
+    pub fn dollars_per_minute(&self) -> f32 {
+        let (price, hours) = self.price_and_hours();
+        price / (hours * 60.0)
+    }
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.str().fmt(f)
+    }
+}
+
+/// The estimated cost of a command
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Estimate {
+    /// The number of jobs, from repeats and permutations
+    pub jobs: usize,
+    /// The fast GPU minutes used, which is 0 in relax mode
+    pub fast_minutes: f32,
+    /// The GPU minutes used in relax mode, which only cost waiting
+    pub relaxed_minutes: f32,
+}
+
+impl Estimate {
+    /// The share of a plan's monthly price that the fast time is worth
+    pub fn dollars(&self, plan: Plan) -> f32 {
+        self.fast_minutes * plan.dollars_per_minute()
+    }
+}
+
+impl Params {
+    /// Estimate the GPU time of the command for some prompt text
+    pub fn estimate(&self, text: &str) -> Estimate {
+        let repeat = info::numbers()
+            .find(|(info, _)| info.name == "repeat")
+            .and_then(|(_, number)| number.flag_value(self))
+            .unwrap_or(1);
+        let jobs = permute::count(&self.command(text)).saturating_mul(repeat as usize);
+        let quality = match self.quality {
+            Quality::Quarter => 0.25,
+            Quality::Half => 0.5,
+            Quality::Full => 1.0,
+            Quality::Double => 2.0,
+        };
+        // The oldest versions are smaller models
+        let model = match self.algorithm {
+            Algorithm::V3 => 0.5,
+            Algorithm::V4 | Algorithm::Niji4 => 0.75,
+            _ => 1.0,
+        };
+        // Rendering the progress video takes a little extra time
+        let video = if self.video { 1.1 } else { 1.0 };
+        let minutes = jobs as f32 * BASE_MINUTES * quality * model * video;
+        match self.speed {
+            Some(Speed::Relax) => Estimate {
+                jobs,
+                fast_minutes: 0.0,
+                relaxed_minutes: minutes,
+            },
+            // Turbo mode uses twice the fast time
+            Some(Speed::Turbo) => Estimate {
+                jobs,
+                fast_minutes: minutes * 2.0,
+                relaxed_minutes: 0.0,
+            },
+            Some(Speed::Fast) | None => Estimate {
+                jobs,
+                fast_minutes: minutes,
+                relaxed_minutes: 0.0,
+            },
+        }
+    }
+}
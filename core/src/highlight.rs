@@ -0,0 +1,77 @@
+//! Splitting commands into their parts for display
+
+/// What part of a command some text is
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SpanKind {
+    /// `/imagine prompt:` or another prefix
+    Prefix,
+    /// Image prompt urls
+    Images,
+    /// The prompt text
+    Text,
+    /// Choices, themes, and other text added after the prompt
+    Suffixes,
+    /// A single flag and its value
+    Flag,
+}
+
+/// A piece of a command
+pub struct Span<'a> {
+    pub kind: SpanKind,
+    pub text: &'a str,
+}
+
+impl Span<'_> {
+    /// The name of the flag, without `--`, if this is a flag
+    pub fn flag(&self) -> Option<&str> {
+        if self.kind != SpanKind::Flag {
+            return None;
+        }
+        self.text
+            .trim_start()
+            .strip_prefix("--")
+            .and_then(|rest| rest.split_whitespace().next())
+    }
+}
+
+/// Split a command generated by [`Params::command`](crate::Params::command)
+/// for `text` with a prefix into spans
+///
+/// The spans cover the whole command, so joining them gives it back.
+pub fn highlight<'a>(command: &'a str, text: &str, prefix: &str) -> Vec<Span<'a>> {
+    let mut spans = Vec::new();
+    let mut push = |kind, text: &'a str| {
+        if !text.is_empty() {
+            spans.push(Span { kind, text });
+        }
+    };
+    let mut rest = command;
+    let prefix = prefix.trim();
+    if let Some(after) = rest.strip_prefix(prefix).filter(|_| !prefix.is_empty()) {
+        let prefix_len = command.len() - after.trim_start().len();
+        push(SpanKind::Prefix, &command[..prefix_len]);
+        rest = &command[prefix_len..];
+    }
+    let flags_start = if rest.starts_with("--") {
+        0
+    } else {
+        rest.find(" --").unwrap_or(rest.len())
+    };
+    let (body, mut flags) = rest.split_at(flags_start);
+    let text = text.trim();
+    match body.find(text).filter(|_| !text.is_empty()) {
+        Some(i) => {
+            push(SpanKind::Images, &body[..i]);
+            push(SpanKind::Text, &body[i..i + text.len()]);
+            push(SpanKind::Suffixes, &body[i + text.len()..]);
+        }
+        None => push(SpanKind::Suffixes, body),
+    }
+    while !flags.is_empty() {
+        let end = flags[1..].find(" --").map_or(flags.len(), |i| i + 1);
+        let (flag, after) = flags.split_at(end);
+        push(SpanKind::Flag, flag);
+        flags = after;
+    }
+    spans
+}
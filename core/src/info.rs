@@ -0,0 +1,363 @@
+//! Descriptions of each Midjourney parameter
+//!
+//! Number parameters are described completely enough here that their
+//! controls, flags, parsing, and range checks are all generated from
+//! [`PARAMS`], so adding one is a matter of adding an entry.
+
+use std::{fmt::Write, ops::RangeInclusive};
+
+use crate::{
+    default_character_weight, default_repeat, default_stop, default_style_weight, enabled_urls,
+    Algorithm, Params,
+};
+
+/// What a parameter does and where it can be used
+pub struct ParamInfo {
+    /// The name shown next to the parameter's control
+    pub name: &'static str,
+    /// The flags the parameter adds to a command, without `--`
+    ///
+    /// The first is the one that is written, and the rest are accepted when
+    /// parsing.
+    pub flags: &'static [&'static str],
+    pub description: &'static str,
+    /// The page of the Midjourney documentation about the parameter
+    pub docs: &'static str,
+    /// Whether a version supports the parameter
+    pub supported: fn(&Algorithm) -> bool,
+    pub section: Section,
+    /// How to handle the parameter if it is a number
+    pub number: Option<Number>,
+}
+
+/// Where a parameter's control is shown
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    Basic,
+    Prompt,
+    References,
+}
+
+/// A parameter with a single number value
+pub struct Number {
+    pub get: fn(&Params) -> u32,
+    pub get_mut: fn(&mut Params) -> &mut u32,
+    pub default: fn(&Algorithm) -> u32,
+    /// The values a version accepts
+    pub range: fn(&Algorithm) -> RangeInclusive<u32>,
+    /// A checkbox that has to be checked for the flag to be added, in which
+    /// case it is added even with the default value
+    pub toggle: Option<fn(&mut Params) -> &mut bool>,
+    /// Whether the rest of the parameters allow the flag to be added,
+    /// including the toggle if there is one
+    pub enabled: fn(&Params) -> bool,
+    /// Whether a slider for the value should be logarithmic
+    pub logarithmic: bool,
+}
+
+impl Number {
+    /// The value to add to a command, if any
+    pub fn flag_value(&self, params: &Params) -> Option<u32> {
+        if !(self.enabled)(params) {
+            return None;
+        }
+        let value = (self.get)(params);
+        if self.toggle.is_none() && value == (self.default)(&params.algorithm) {
+            return None;
+        }
+        let range = (self.range)(&params.algorithm);
+        Some(value.clamp(*range.start(), *range.end()))
+    }
+}
+
+const fn always(_: &Algorithm) -> bool {
+    true
+}
+
+const fn always_enabled(_: &Params) -> bool {
+    true
+}
+
+/// Every parameter with a control
+pub const PARAMS: &[ParamInfo] = &[
+    ParamInfo {
+        name: "algorithm",
+        flags: &["v", "niji", "test", "testp"],
+        description: "The model version that generates the images",
+        docs: "https://docs.midjourney.com/docs/models",
+        supported: always,
+        section: Section::Basic,
+        number: None,
+    },
+    ParamInfo {
+        name: "model style",
+        flags: &["style"],
+        description: "A variant of the model version with a different look",
+        docs: "https://docs.midjourney.com/docs/style",
+        supported: |algorithm| !algorithm.allowed_styles().is_empty(),
+        section: Section::Basic,
+        number: None,
+    },
+    ParamInfo {
+        name: "aspect",
+        flags: &["ar"],
+        description: "The width to height ratio of the images",
+        docs: "https://docs.midjourney.com/docs/aspect-ratios",
+        supported: always,
+        section: Section::Basic,
+        number: None,
+    },
+    ParamInfo {
+        name: "quality",
+        flags: &["q"],
+        description: "How much rendering time to spend. Higher quality takes longer \
+            and uses more GPU minutes",
+        docs: "https://docs.midjourney.com/docs/quality",
+        supported: always,
+        section: Section::Basic,
+        number: None,
+    },
+    ParamInfo {
+        name: "speed",
+        flags: &["fast", "relax", "turbo"],
+        description: "Which GPU mode to run the job in, overriding the account setting",
+        docs: "https://docs.midjourney.com/docs/fast-relax",
+        supported: always,
+        section: Section::Basic,
+        number: None,
+    },
+    ParamInfo {
+        name: "stylize",
+        flags: &["stylize", "s"],
+        description: "How strongly Midjourney's default aesthetic is applied. Low values \
+            follow the prompt more closely",
+        docs: "https://docs.midjourney.com/docs/stylize",
+        supported: always,
+        section: Section::Basic,
+        number: Some(Number {
+            get: |params| params.stylize,
+            get_mut: |params| &mut params.stylize,
+            default: Algorithm::default_stylize,
+            range: Algorithm::stylize_range,
+            toggle: None,
+            enabled: always_enabled,
+            logarithmic: true,
+        }),
+    },
+    ParamInfo {
+        name: "chaos",
+        flags: &["chaos", "c"],
+        description: "How different the images in a grid are from each other",
+        docs: "https://docs.midjourney.com/docs/chaos",
+        supported: always,
+        section: Section::Basic,
+        number: Some(Number {
+            get: |params| params.chaos,
+            get_mut: |params| &mut params.chaos,
+            default: |_| 0,
+            range: |_| 0..=100,
+            toggle: None,
+            enabled: always_enabled,
+            logarithmic: false,
+        }),
+    },
+    ParamInfo {
+        name: "weird",
+        flags: &["weird", "w"],
+        description: "Adds quirky and unconventional qualities to the images",
+        docs: "https://docs.midjourney.com/docs/weird",
+        supported: Algorithm::supports_weird,
+        section: Section::Basic,
+        number: Some(Number {
+            get: |params| params.weird,
+            get_mut: |params| &mut params.weird,
+            default: |_| 0,
+            range: |_| 0..=3000,
+            toggle: None,
+            enabled: always_enabled,
+            logarithmic: true,
+        }),
+    },
+    ParamInfo {
+        name: "seed",
+        flags: &["seed", "sameseed"],
+        description: "The starting noise. The same seed and prompt give similar images, \
+            and sameseed uses one seed for the whole grid",
+        docs: "https://docs.midjourney.com/docs/seeds",
+        supported: always,
+        section: Section::Basic,
+        number: None,
+    },
+    ParamInfo {
+        name: "stop",
+        flags: &["stop"],
+        description: "Finish the job partway through, as a percentage, for blurrier and \
+            less detailed images",
+        docs: "https://docs.midjourney.com/docs/stop",
+        supported: always,
+        section: Section::Basic,
+        number: Some(Number {
+            get: |params| params.stop,
+            get_mut: |params| &mut params.stop,
+            default: |_| default_stop(),
+            range: |_| 10..=100,
+            toggle: Some(|params| &mut params.use_stop),
+            enabled: |params| params.use_stop,
+            logarithmic: false,
+        }),
+    },
+    ParamInfo {
+        name: "repeat",
+        flags: &["repeat", "r"],
+        description: "Run the same prompt multiple times. Requires fast mode",
+        docs: "https://docs.midjourney.com/docs/repeat",
+        supported: always,
+        section: Section::Basic,
+        number: Some(Number {
+            get: |params| params.repeat,
+            get_mut: |params| &mut params.repeat,
+            default: |_| default_repeat(),
+            range: |_| 2..=40,
+            toggle: Some(|params| &mut params.use_repeat),
+            enabled: |params| params.use_repeat,
+            logarithmic: false,
+        }),
+    },
+    ParamInfo {
+        name: "personalize",
+        flags: &["p"],
+        description: "Apply the style learned from your image rankings or a \
+            personalization code",
+        docs: "https://docs.midjourney.com/docs/personalization",
+        supported: always,
+        section: Section::Basic,
+        number: None,
+    },
+    ParamInfo {
+        name: "video",
+        flags: &["video"],
+        description: "Save a video of the initial grid being generated",
+        docs: "https://docs.midjourney.com/docs/video",
+        supported: Algorithm::supports_video,
+        section: Section::Basic,
+        number: None,
+    },
+    ParamInfo {
+        name: "tile",
+        flags: &["tile"],
+        description: "Make images that can be repeated seamlessly as tiles",
+        docs: "https://docs.midjourney.com/docs/tile",
+        supported: Algorithm::supports_tile,
+        section: Section::Basic,
+        number: None,
+    },
+    ParamInfo {
+        name: "permutations",
+        flags: &[],
+        description: "{a, b} options that each make a separate job",
+        docs: "https://docs.midjourney.com/docs/permutations",
+        supported: always,
+        section: Section::Prompt,
+        number: None,
+    },
+    ParamInfo {
+        name: "exclude",
+        flags: &["no"],
+        description: "Things to keep out of the images",
+        docs: "https://docs.midjourney.com/docs/no",
+        supported: always,
+        section: Section::Prompt,
+        number: None,
+    },
+    ParamInfo {
+        name: "style refs",
+        flags: &["sref"],
+        description: "Image urls whose style is applied to the images",
+        docs: "https://docs.midjourney.com/docs/style-reference",
+        supported: Algorithm::supports_references,
+        section: Section::References,
+        number: None,
+    },
+    ParamInfo {
+        name: "style weight",
+        flags: &["sw"],
+        description: "How strongly the style references are applied",
+        docs: "https://docs.midjourney.com/docs/style-reference",
+        supported: Algorithm::supports_references,
+        section: Section::References,
+        number: Some(Number {
+            get: |params| params.style_weight,
+            get_mut: |params| &mut params.style_weight,
+            default: |_| default_style_weight(),
+            range: |_| 0..=1000,
+            toggle: None,
+            enabled: |params| !enabled_urls(&params.style_refs).is_empty(),
+            logarithmic: false,
+        }),
+    },
+    ParamInfo {
+        name: "character refs",
+        flags: &["cref"],
+        description: "Image urls of a character to keep consistent",
+        docs: "https://docs.midjourney.com/docs/character-reference",
+        supported: Algorithm::supports_references,
+        section: Section::References,
+        number: None,
+    },
+    ParamInfo {
+        name: "character weight",
+        flags: &["cw"],
+        description: "How much of the character to copy. 0 copies just the face, while \
+            100 also copies hair and clothing",
+        docs: "https://docs.midjourney.com/docs/character-reference",
+        supported: Algorithm::supports_references,
+        section: Section::References,
+        number: Some(Number {
+            get: |params| params.character_weight,
+            get_mut: |params| &mut params.character_weight,
+            default: |_| default_character_weight(),
+            range: |_| 0..=100,
+            toggle: None,
+            enabled: |params| !enabled_urls(&params.character_refs).is_empty(),
+            logarithmic: false,
+        }),
+    },
+];
+
+/// Find a parameter by the name shown next to its control
+pub fn find(name: &str) -> Option<&'static ParamInfo> {
+    PARAMS.iter().find(|info| info.name == name)
+}
+
+/// The number parameters, with their info
+pub fn numbers() -> impl Iterator<Item = (&'static ParamInfo, &'static Number)> {
+    PARAMS
+        .iter()
+        .filter_map(|info| Some((info, info.number.as_ref()?)))
+}
+
+/// Find a number parameter by one of its flags
+pub fn find_number(flag: &str) -> Option<(&'static ParamInfo, &'static Number)> {
+    numbers().find(|(info, _)| info.flags.contains(&flag))
+}
+
+impl ParamInfo {
+    /// A description of the parameter and the values it accepts with a
+    /// version
+    #[allow(unused_must_use)]
+    pub fn help(&self, algorithm: Algorithm) -> String {
+        let mut help = self.description.to_string();
+        if !(self.supported)(&algorithm) {
+            write!(help, "\n\nnot supported by {algorithm}");
+        } else if let Some(number) = &self.number {
+            let range = (number.range)(&algorithm);
+            write!(
+                help,
+                "\n\n{} to {} with {algorithm}",
+                range.start(),
+                range.end()
+            );
+        }
+        help
+    }
+}
@@ -0,0 +1,904 @@
+//! Building Midjourney `/imagine` commands from prompt parameters
+//!
+//! [`Params`] holds everything that goes into a command besides the prompt
+//! text, and [`Params::command`] (in [`command`]) turns the two into a
+//! command string.
+//! [`parse::parse`] goes the other way, and [`permute`] expands the
+//! `{a, b}` permutation syntax. [`highlight`] splits a command into its
+//! parts for display, [`lint`] finds parameters that don't work together,
+//! [`template`] fills in `{variable}` placeholders, [`diff`] compares
+//! commands, [`stats`] counts the terms used in prompts, [`info`]
+//! describes each parameter, and [`sd`] converts prompts for Stable
+//! Diffusion tools. [`profile`] picks how the final prompt is written for
+//! each image generator, and [`thesaurus`] suggests other words for the
+//! prompt text. [`normalize`] cleans up text pasted from other apps,
+//! [`autocomplete`] completes phrases as they are typed, [`camera`] phrases
+//! photography terms, and [`palette`] names colors. [`cost`] estimates the
+//! GPU time a command uses.
+
+pub mod autocomplete;
+pub mod camera;
+pub mod command;
+pub mod cost;
+pub mod diff;
+pub mod highlight;
+pub mod info;
+pub mod lint;
+pub mod normalize;
+pub mod palette;
+pub mod parse;
+pub mod permute;
+pub mod profile;
+pub mod sd;
+pub mod stats;
+pub mod template;
+pub mod thesaurus;
+
+use std::{
+    fmt::{self, Write},
+    mem,
+    ops::RangeInclusive,
+};
+
+use profile::Profile;
+use serde::{
+    de::{self, EnumAccess, MapAccess, VariantAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+/// The parameters that make up a prompt, which can be saved as a preset
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(remote = "Self")]
+pub struct Params {
+    #[serde(default)]
+    pub images: Vec<ImagePrompt>,
+    #[serde(default)]
+    pub segments: Vec<Segment>,
+    pub style: Choices,
+    pub themes: Vec<Theme>,
+    #[serde(default)]
+    pub theme_groups: Vec<ThemeGroup>,
+    /// Theme weights as older versions saved them, which are moved onto the
+    /// themes when loading
+    #[serde(default, skip_serializing, rename = "theme_weights")]
+    pub old_theme_weights: Vec<(String, f32)>,
+    #[serde(default)]
+    pub permutations: Vec<(String, bool)>,
+    #[serde(default)]
+    pub negatives: Vec<(String, bool)>,
+    pub color: Choices,
+    pub body: Choices,
+    pub hair: Choices,
+    pub pose: Choices,
+    pub algorithm: Algorithm,
+    #[serde(default)]
+    pub model_style: Option<ModelStyle>,
+    pub aspect: Aspect,
+    /// Named aspect ratios added by the user
+    #[serde(default)]
+    pub custom_aspects: Vec<(String, [u32; 2])>,
+    #[serde(default)]
+    pub quality: Quality,
+    #[serde(default)]
+    pub speed: Option<Speed>,
+    pub stylize: u32,
+    #[serde(default)]
+    pub chaos: u32,
+    #[serde(default)]
+    pub weird: u32,
+    pub video: bool,
+    #[serde(default)]
+    pub tile: bool,
+    pub use_seed: bool,
+    #[serde(default)]
+    pub seed_mode: SeedMode,
+    pub seed: u32,
+    #[serde(default)]
+    pub style_refs: Vec<(String, bool)>,
+    #[serde(default = "default_style_weight")]
+    pub style_weight: u32,
+    #[serde(default)]
+    pub character_refs: Vec<(String, bool)>,
+    #[serde(default = "default_character_weight")]
+    pub character_weight: u32,
+    #[serde(default)]
+    pub personalization: Personalization,
+    #[serde(default)]
+    pub use_repeat: bool,
+    #[serde(default = "default_repeat")]
+    pub repeat: u32,
+    #[serde(default)]
+    pub use_stop: bool,
+    #[serde(default = "default_stop")]
+    pub stop: u32,
+    /// What the command starts with, which can be changed for other bots or
+    /// left empty
+    #[serde(default = "default_prefix")]
+    pub prefix: String,
+    /// Free-form notes, which are not part of the command
+    #[serde(default)]
+    pub notes: String,
+    /// The image generator the prompt is written for
+    #[serde(default)]
+    pub profile: Profile,
+    /// The image size used when converting prompts for Stable Diffusion
+    #[serde(default = "default_sd_size")]
+    pub sd_size: u32,
+    /// What suffixes like the style and themes are joined to the prompt with
+    #[serde(default = "default_separator")]
+    pub separator: String,
+    /// The order flags are written in, by the keys from
+    /// [`command::flag_keys`], or empty for the default order
+    #[serde(default)]
+    pub flag_order: Vec<String>,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            images: Vec::new(),
+            segments: Vec::new(),
+            style: Choices::new(["ultra realistic", "lo-fi anime"]),
+            themes: ["cyberpunk", "steampunk"]
+                .map(|s| Theme::new(s, false))
+                .into(),
+            theme_groups: Vec::new(),
+            old_theme_weights: Vec::new(),
+            permutations: Vec::new(),
+            negatives: Vec::new(),
+            color: Choices::new(["vibrant", "muted", "grayscale", "high contrast"]),
+            body: Choices::new(["feminine", "masculine"]),
+            hair: Choices::new(["blonde", "brown", "black", "red", "light brown"]),
+            pose: Choices::new(["dynamic", "relaxed", "confident"]),
+            algorithm: Algorithm::V3,
+            model_style: None,
+            aspect: Aspect::Square,
+            custom_aspects: Vec::new(),
+            quality: Quality::default(),
+            speed: None,
+            stylize: Algorithm::V3.default_stylize(),
+            chaos: 0,
+            weird: 0,
+            use_seed: false,
+            seed_mode: SeedMode::default(),
+            seed: 0,
+            style_refs: Vec::new(),
+            style_weight: default_style_weight(),
+            character_refs: Vec::new(),
+            character_weight: default_character_weight(),
+            personalization: Personalization::default(),
+            use_repeat: false,
+            repeat: default_repeat(),
+            use_stop: false,
+            stop: default_stop(),
+            prefix: default_prefix(),
+            notes: String::new(),
+            profile: Profile::default(),
+            sd_size: default_sd_size(),
+            separator: default_separator(),
+            flag_order: Vec::new(),
+            video: false,
+            tile: false,
+        }
+    }
+}
+
+// `remote = "Self"` turns the derived implementations into inherent
+// functions, so that the real ones can move old fields into place after
+// loading
+impl Serialize for Params {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Params::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Params {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut params = Params::deserialize(deserializer)?;
+        for (name, weight) in mem::take(&mut params.old_theme_weights) {
+            let grouped = params
+                .theme_groups
+                .iter_mut()
+                .flat_map(|group| &mut group.themes);
+            for theme in params.themes.iter_mut().chain(grouped) {
+                if theme.name.trim() == name.trim() {
+                    theme.weight = Some(weight);
+                }
+            }
+        }
+        Ok(params)
+    }
+}
+
+pub fn default_sd_size() -> u32 {
+    1024
+}
+
+pub fn default_separator() -> String {
+    ", ".into()
+}
+
+/// The prefix of Midjourney's `/imagine` command
+pub fn default_prefix() -> String {
+    "/imagine prompt:".into()
+}
+
+/// The default `--stop` percentage
+pub fn default_stop() -> u32 {
+    100
+}
+
+/// The default `--repeat` count
+pub fn default_repeat() -> u32 {
+    2
+}
+
+/// The style reference weight Midjourney uses when `--sw` is not given
+pub fn default_style_weight() -> u32 {
+    100
+}
+
+/// The character reference weight Midjourney uses when `--cw` is not given
+pub fn default_character_weight() -> u32 {
+    100
+}
+
+/// A Midjourney model version
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Algorithm {
+    V3,
+    V4,
+    V5,
+    #[serde(rename = "v5.1")]
+    V5_1,
+    #[serde(rename = "v5.2")]
+    V5_2,
+    V6,
+    Niji4,
+    Niji5,
+    Niji6,
+    Test,
+    TestPhoto,
+}
+
+impl Algorithm {
+    pub const ALL: [Algorithm; 11] = [
+        Algorithm::V3,
+        Algorithm::V4,
+        Algorithm::V5,
+        Algorithm::V5_1,
+        Algorithm::V5_2,
+        Algorithm::V6,
+        Algorithm::Niji4,
+        Algorithm::Niji5,
+        Algorithm::Niji6,
+        Algorithm::Test,
+        Algorithm::TestPhoto,
+    ];
+    pub fn str(&self) -> &'static str {
+        match self {
+            Algorithm::V3 => "v3",
+            Algorithm::V4 => "v4",
+            Algorithm::V5 => "v5",
+            Algorithm::V5_1 => "v5.1",
+            Algorithm::V5_2 => "v5.2",
+            Algorithm::V6 => "v6",
+            Algorithm::Niji4 => "niji 4",
+            Algorithm::Niji5 => "niji 5",
+            Algorithm::Niji6 => "niji 6",
+            Algorithm::Test => "test",
+            Algorithm::TestPhoto => "testp",
+        }
+    }
+    /// The flag that selects this version
+    pub fn flag(&self) -> &'static str {
+        match self {
+            Algorithm::V3 => "--v 3",
+            Algorithm::V4 => "--v 4",
+            Algorithm::V5 => "--v 5",
+            Algorithm::V5_1 => "--v 5.1",
+            Algorithm::V5_2 => "--v 5.2",
+            Algorithm::V6 => "--v 6",
+            Algorithm::Niji4 => "--niji 4",
+            Algorithm::Niji5 => "--niji 5",
+            Algorithm::Niji6 => "--niji 6",
+            Algorithm::Test => "--test",
+            Algorithm::TestPhoto => "--testp",
+        }
+    }
+    pub fn is_niji(&self) -> bool {
+        matches!(self, Algorithm::Niji4 | Algorithm::Niji5 | Algorithm::Niji6)
+    }
+    /// Whether this version supports `--tile`
+    pub fn supports_tile(&self) -> bool {
+        !matches!(
+            self,
+            Algorithm::V4 | Algorithm::Niji4 | Algorithm::Test | Algorithm::TestPhoto
+        )
+    }
+    /// Whether this version supports `--video`
+    pub fn supports_video(&self) -> bool {
+        !matches!(
+            self,
+            Algorithm::V4 | Algorithm::Niji4 | Algorithm::Test | Algorithm::TestPhoto
+        )
+    }
+    /// Whether this version supports `--weird`
+    pub fn supports_weird(&self) -> bool {
+        matches!(self, Algorithm::V5_2 | Algorithm::V6 | Algorithm::Niji6)
+    }
+    /// Whether this version supports `--sref` and `--cref`
+    pub fn supports_references(&self) -> bool {
+        matches!(self, Algorithm::V6 | Algorithm::Niji6)
+    }
+    /// The valid range of `--stylize` values
+    pub fn stylize_range(&self) -> RangeInclusive<u32> {
+        match self {
+            Algorithm::V3 | Algorithm::Test | Algorithm::TestPhoto => 625..=60000,
+            _ => 0..=1000,
+        }
+    }
+    /// The `--stylize` value used when the flag is not given
+    pub fn default_stylize(&self) -> u32 {
+        match self {
+            Algorithm::V3 | Algorithm::Test | Algorithm::TestPhoto => 2500,
+            _ => 100,
+        }
+    }
+    /// The values this version accepts for `--style`
+    pub fn allowed_styles(&self) -> &'static [ModelStyle] {
+        match self {
+            Algorithm::V5_1 | Algorithm::V5_2 | Algorithm::V6 | Algorithm::Niji6 => {
+                &[ModelStyle::Raw]
+            }
+            Algorithm::Niji5 => &[
+                ModelStyle::Cute,
+                ModelStyle::Expressive,
+                ModelStyle::Original,
+                ModelStyle::Scenic,
+            ],
+            _ => &[],
+        }
+    }
+    /// Whether this version supports an aspect ratio
+    pub fn supports_aspect(&self, aspect: Aspect) -> bool {
+        match aspect {
+            Aspect::Custom(..) => !matches!(self, Algorithm::Test | Algorithm::TestPhoto),
+            _ => self.allowed_aspects().contains(&aspect),
+        }
+    }
+    /// The preset aspect ratios this version supports
+    pub fn allowed_aspects(&self) -> &'static [Aspect] {
+        match self {
+            Algorithm::Test | Algorithm::TestPhoto => {
+                &[Aspect::Square, Aspect::Portrait, Aspect::Landscape]
+            }
+            _ => &[
+                Aspect::Square,
+                Aspect::Portrait,
+                Aspect::Landscape,
+                Aspect::Tall,
+                Aspect::Wide,
+                Aspect::UltraWide,
+            ],
+        }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.str().fmt(f)
+    }
+}
+
+/// A value for `--style`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModelStyle {
+    Raw,
+    Cute,
+    Expressive,
+    Original,
+    Scenic,
+}
+
+impl ModelStyle {
+    pub const ALL: [ModelStyle; 5] = [
+        ModelStyle::Raw,
+        ModelStyle::Cute,
+        ModelStyle::Expressive,
+        ModelStyle::Original,
+        ModelStyle::Scenic,
+    ];
+    pub fn str(&self) -> &'static str {
+        match self {
+            ModelStyle::Raw => "raw",
+            ModelStyle::Cute => "cute",
+            ModelStyle::Expressive => "expressive",
+            ModelStyle::Original => "original",
+            ModelStyle::Scenic => "scenic",
+        }
+    }
+}
+
+impl fmt::Display for ModelStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.str().fmt(f)
+    }
+}
+
+/// An aspect ratio for `--ar`
+///
+/// Aspects are saved as their name, or as `w:h` if they are custom, rather
+/// than as an enum, since YAML writes enum variants with data as tags, which
+/// can't be read back from a struct that flattens [`Params`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Aspect {
+    Square,
+    Portrait,
+    Landscape,
+    Tall,
+    Wide,
+    UltraWide,
+    /// Any other ratio, already reduced
+    Custom(u32, u32),
+}
+
+impl Aspect {
+    pub const ALL: [Aspect; 6] = [
+        Aspect::Square,
+        Aspect::Portrait,
+        Aspect::Landscape,
+        Aspect::Tall,
+        Aspect::Wide,
+        Aspect::UltraWide,
+    ];
+    pub fn str(&self) -> &'static str {
+        match self {
+            Aspect::Square => "square",
+            Aspect::Tall => "tall",
+            Aspect::Portrait => "portrait",
+            Aspect::Landscape => "landscape",
+            Aspect::Wide => "wide",
+            Aspect::UltraWide => "ultrawide",
+            Aspect::Custom(..) => "custom",
+        }
+    }
+    /// The aspect with the ratio of a width and height, reduced to lowest
+    /// terms
+    pub fn from_wh(w: u32, h: u32) -> Aspect {
+        let divisor = gcd(w, h).max(1);
+        let (w, h) = (w / divisor, h / divisor);
+        if w == h {
+            return Aspect::Square;
+        }
+        Aspect::ALL
+            .into_iter()
+            .find(|aspect| aspect.wh() == Some([w, h]))
+            .unwrap_or(Aspect::Custom(w, h))
+    }
+    /// The name of the aspect along with its ratio
+    pub fn aspect_string(&self) -> String {
+        let mut s = self.to_string();
+        if let Some([w, h]) = self.wh() {
+            write!(&mut s, " {w}:{h}").unwrap();
+        }
+        s
+    }
+    /// The width and height of the ratio, or `None` for square
+    pub fn wh(&self) -> Option<[u32; 2]> {
+        Some(match self {
+            Aspect::Square => return None,
+            Aspect::Portrait => [2, 3],
+            Aspect::Landscape => [3, 2],
+            Aspect::Tall => [1, 2],
+            Aspect::Wide => [16, 9],
+            Aspect::UltraWide => [21, 9],
+            Aspect::Custom(w, h) => [*w, *h],
+        })
+    }
+}
+
+/// The greatest common divisor of two numbers
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+impl fmt::Display for Aspect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.str().fmt(f)
+    }
+}
+
+impl Serialize for Aspect {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Aspect::Custom(w, h) => serializer.collect_str(&format_args!("{w}:{h}")),
+            aspect => serializer.serialize_str(aspect.str()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Aspect {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(AspectVisitor)
+    }
+}
+
+/// Reads aspects as they are saved now, and custom ones the way older
+/// versions saved them: as a YAML tag or a single-entry map
+struct AspectVisitor;
+
+impl<'de> Visitor<'de> for AspectVisitor {
+    type Value = Aspect;
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an aspect name or a w:h ratio")
+    }
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Aspect, E> {
+        if let Some(aspect) = Aspect::ALL.into_iter().find(|aspect| aspect.str() == s) {
+            return Ok(aspect);
+        }
+        let ratio = s
+            .split_once(':')
+            .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)));
+        match ratio {
+            Some((w, h)) if w > 0 && h > 0 => Ok(Aspect::from_wh(w, h)),
+            _ => Err(E::invalid_value(de::Unexpected::Str(s), &self)),
+        }
+    }
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Aspect, A::Error> {
+        let (name, variant): (String, _) = data.variant()?;
+        if name != "custom" {
+            return Err(de::Error::unknown_variant(&name, &["custom"]));
+        }
+        let (w, h) = variant.newtype_variant()?;
+        Ok(Aspect::from_wh(w, h))
+    }
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Aspect, A::Error> {
+        match map.next_entry::<String, (u32, u32)>()? {
+            Some((name, (w, h))) if name == "custom" => Ok(Aspect::from_wh(w, h)),
+            _ => Err(de::Error::invalid_type(de::Unexpected::Map, &self)),
+        }
+    }
+}
+
+/// A value for `--q`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Quality {
+    #[serde(rename = "0.25")]
+    Quarter,
+    #[serde(rename = "0.5")]
+    Half,
+    #[default]
+    #[serde(rename = "1")]
+    Full,
+    #[serde(rename = "2")]
+    Double,
+}
+
+impl Quality {
+    pub const ALL: [Quality; 4] = [
+        Quality::Quarter,
+        Quality::Half,
+        Quality::Full,
+        Quality::Double,
+    ];
+    pub fn str(&self) -> &'static str {
+        match self {
+            Quality::Quarter => "0.25",
+            Quality::Half => "0.5",
+            Quality::Full => "1",
+            Quality::Double => "2",
+        }
+    }
+}
+
+impl fmt::Display for Quality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.str().fmt(f)
+    }
+}
+
+/// A speed mode flag
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Speed {
+    Fast,
+    Relax,
+    Turbo,
+}
+
+impl Speed {
+    pub const ALL: [Speed; 3] = [Speed::Fast, Speed::Relax, Speed::Turbo];
+    pub fn str(&self) -> &'static str {
+        match self {
+            Speed::Fast => "fast",
+            Speed::Relax => "relax",
+            Speed::Turbo => "turbo",
+        }
+    }
+}
+
+impl fmt::Display for Speed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.str().fmt(f)
+    }
+}
+
+/// Whether a seed is passed with `--seed` or `--sameseed`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SeedMode {
+    Seed,
+    #[default]
+    SameSeed,
+}
+
+impl SeedMode {
+    pub fn str(&self) -> &'static str {
+        match self {
+            SeedMode::Seed => "seed",
+            SeedMode::SameSeed => "sameseed",
+        }
+    }
+}
+
+impl fmt::Display for SeedMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.str().fmt(f)
+    }
+}
+
+/// The most characters Discord accepts for the `prompt` option of `/imagine`
+pub const MAX_PROMPT_LENGTH: usize = 6000;
+
+/// The number of characters in the `prompt` option of a command, which is
+/// what [`MAX_PROMPT_LENGTH`] applies to
+pub fn prompt_length(command: &str, prefix: &str) -> usize {
+    strip_prefix(command, prefix).chars().count()
+}
+
+/// The part of a command after its prefix
+pub fn strip_prefix<'a>(command: &'a str, prefix: &str) -> &'a str {
+    command
+        .strip_prefix(prefix.trim())
+        .unwrap_or(command)
+        .trim()
+}
+
+/// How a command is written when it is copied
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CopyFormat {
+    /// The full command, including the prefix
+    #[default]
+    Command,
+    /// Just the prompt, without the prefix
+    Prompt,
+    /// The full command in a markdown code block
+    Markdown,
+}
+
+impl CopyFormat {
+    pub const ALL: [CopyFormat; 3] = [
+        CopyFormat::Command,
+        CopyFormat::Prompt,
+        CopyFormat::Markdown,
+    ];
+    pub fn str(&self) -> &'static str {
+        match self {
+            CopyFormat::Command => "command",
+            CopyFormat::Prompt => "prompt",
+            CopyFormat::Markdown => "markdown",
+        }
+    }
+    /// Format a command generated by [`Params::command`] with a prefix
+    pub fn format(&self, command: &str, prefix: &str) -> String {
+        match self {
+            CopyFormat::Command => command.into(),
+            CopyFormat::Prompt => strip_prefix(command, prefix).into(),
+            CopyFormat::Markdown => format!("```\n{command}\n```"),
+        }
+    }
+}
+
+impl fmt::Display for CopyFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.str().fmt(f)
+    }
+}
+
+/// A theme, which is added after the prompt text like the choices, or as a
+/// multi-prompt segment if it has a weight
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "SavedTheme", into = "SavedTheme")]
+pub struct Theme {
+    pub name: String,
+    pub enabled: bool,
+    pub weight: Option<f32>,
+}
+
+impl Theme {
+    pub fn new(name: impl Into<String>, enabled: bool) -> Self {
+        Theme {
+            name: name.into(),
+            enabled,
+            weight: None,
+        }
+    }
+}
+
+/// How a theme is saved, which is the same as the entries of the other
+/// lists unless it has a weight
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum SavedTheme {
+    Weighted(String, bool, f32),
+    Unweighted(String, bool),
+}
+
+impl From<SavedTheme> for Theme {
+    fn from(saved: SavedTheme) -> Self {
+        match saved {
+            SavedTheme::Weighted(name, enabled, weight) => Theme {
+                name,
+                enabled,
+                weight: Some(weight),
+            },
+            SavedTheme::Unweighted(name, enabled) => Theme::new(name, enabled),
+        }
+    }
+}
+
+impl From<Theme> for SavedTheme {
+    fn from(theme: Theme) -> Self {
+        match theme.weight {
+            Some(weight) => SavedTheme::Weighted(theme.name, theme.enabled, weight),
+            None => SavedTheme::Unweighted(theme.name, theme.enabled),
+        }
+    }
+}
+
+/// A named group of themes that can be enabled or disabled together
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ThemeGroup {
+    pub name: String,
+    pub enabled: bool,
+    pub themes: Vec<Theme>,
+}
+
+/// An additional part of a multi-prompt
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Segment {
+    pub text: String,
+    pub weight: f32,
+}
+
+/// An image url to include at the start of the prompt
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ImagePrompt {
+    pub url: String,
+    pub enabled: bool,
+    /// The image weight, where `None` leaves it to Midjourney
+    pub weight: Option<f32>,
+}
+
+/// Get the enabled entries of a url list that are actually urls
+pub fn enabled_urls(items: &[(String, bool)]) -> Vec<&str> {
+    items
+        .iter()
+        .filter(|(url, enabled)| *enabled && is_url(url))
+        .map(|(url, _)| url.trim())
+        .collect()
+}
+
+/// Check whether a string looks like a url that Midjourney can fetch
+pub fn is_url(s: &str) -> bool {
+    let s = s.trim();
+    let rest = s
+        .strip_prefix("https://")
+        .or_else(|| s.strip_prefix("http://"));
+    rest.is_some_and(|rest| !rest.is_empty() && !rest.contains(char::is_whitespace))
+}
+
+/// Named personalization codes, one of which may be used with `--p`
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Personalization {
+    pub enabled: bool,
+    /// The name of the selected code, or `None` to use the default profile
+    pub curr: Option<String>,
+    pub codes: Vec<(String, String)>,
+}
+
+impl Personalization {
+    /// The `--p` flag, if personalization is enabled
+    pub fn flag(&self) -> Option<String> {
+        if !self.enabled {
+            return None;
+        }
+        let code = self.curr.as_ref().and_then(|curr| {
+            self.codes
+                .iter()
+                .find(|(name, _)| name == curr)
+                .map(|(_, code)| code.trim())
+                .filter(|code| !code.is_empty())
+        });
+        Some(match code {
+            Some(code) => format!("--p {code}"),
+            None => "--p".into(),
+        })
+    }
+}
+
+/// A list of options, at most one of which is selected
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Choices {
+    pub curr: Option<String>,
+    pub choices: Vec<String>,
+}
+
+impl Params {
+    /// The enabled themes and their weights, including those from enabled
+    /// theme groups
+    pub fn enabled_themes(&self) -> impl Iterator<Item = (&str, Option<f32>)> {
+        let grouped = self
+            .theme_groups
+            .iter()
+            .filter(|group| group.enabled)
+            .flat_map(|group| &group.themes);
+        self.themes
+            .iter()
+            .chain(grouped)
+            .filter(|theme| theme.enabled && !theme.name.trim().is_empty())
+            .map(|theme| (theme.name.trim(), theme.weight))
+    }
+    /// Switch to a different version, adjusting the parameters it doesn't
+    /// support
+    pub fn set_algorithm(&mut self, algorithm: Algorithm) {
+        let prev = self.algorithm;
+        self.algorithm = algorithm;
+        if !algorithm.supports_aspect(self.aspect) {
+            self.aspect = match self.aspect.wh() {
+                Some([w, h]) if w > h => Aspect::Landscape,
+                Some(_) => Aspect::Portrait,
+                None => Aspect::Square,
+            };
+        }
+        if let Some(style) = self.model_style {
+            if !algorithm.allowed_styles().contains(&style) {
+                self.model_style = None;
+            }
+        }
+        if self.stylize == prev.default_stylize() {
+            self.stylize = algorithm.default_stylize();
+        } else {
+            let range = algorithm.stylize_range();
+            self.stylize = self.stylize.clamp(*range.start(), *range.end());
+        }
+    }
+}
+
+impl Choices {
+    pub fn new<'a>(choices: impl IntoIterator<Item = &'a str>) -> Self {
+        Choices {
+            curr: None,
+            choices: choices.into_iter().map(Into::into).collect(),
+        }
+    }
+    /// Select a choice, adding it if it doesn't exist
+    pub fn select(&mut self, value: &str) {
+        let choice = match self.choices.iter().find(|choice| choice.trim() == value) {
+            Some(choice) => choice.clone(),
+            None => {
+                self.choices.push(value.into());
+                value.into()
+            }
+        };
+        self.curr = Some(choice);
+    }
+}
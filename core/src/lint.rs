@@ -0,0 +1,211 @@
+//! Finding parameters that Midjourney will reject or that don't work together
+
+use std::fmt;
+
+use crate::{enabled_urls, info, strip_prefix, Algorithm, Params, SeedMode, Speed};
+
+/// A problem with a command
+pub enum Warning {
+    /// A flag that the chosen version doesn't support
+    Unsupported {
+        flag: &'static str,
+        algorithm: Algorithm,
+    },
+    /// A value outside the range the chosen version accepts
+    OutOfRange {
+        flag: &'static str,
+        value: u32,
+        min: u32,
+        max: u32,
+    },
+    /// Flags that work against each other
+    Conflict {
+        flag: &'static str,
+        other: &'static str,
+        reason: &'static str,
+    },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::Unsupported { flag, algorithm } => {
+                write!(f, "--{flag} is not supported by {algorithm}")
+            }
+            Warning::OutOfRange {
+                flag,
+                value,
+                min,
+                max,
+            } => write!(f, "--{flag} {value} is outside of {min}..={max}"),
+            Warning::Conflict {
+                flag,
+                other,
+                reason,
+            } => write!(f, "--{flag} with --{other}: {reason}"),
+        }
+    }
+}
+
+/// Check the parameters of a command for problems
+pub fn lint(params: &Params) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    let algorithm = params.algorithm;
+    let mut unsupported = |flag| warnings.push(Warning::Unsupported { flag, algorithm });
+    if params.video && !algorithm.supports_video() {
+        unsupported("video");
+    }
+    if params.tile && !algorithm.supports_tile() {
+        unsupported("tile");
+    }
+    if params.weird != 0 && !algorithm.supports_weird() {
+        unsupported("weird");
+    }
+    if !algorithm.supports_references() {
+        if !enabled_urls(&params.style_refs).is_empty() {
+            unsupported("sref");
+        }
+        if !enabled_urls(&params.character_refs).is_empty() {
+            unsupported("cref");
+        }
+    }
+    if let Some(style) = params.model_style {
+        if !algorithm.allowed_styles().contains(&style) {
+            unsupported("style");
+        }
+    }
+    if !algorithm.supports_aspect(params.aspect) {
+        unsupported("ar");
+    }
+    for (info, number) in info::numbers() {
+        let value = (number.get)(params);
+        let range = (number.range)(&algorithm);
+        if (number.enabled)(params) && !range.contains(&value) {
+            warnings.push(Warning::OutOfRange {
+                flag: info.flags[0],
+                value,
+                min: *range.start(),
+                max: *range.end(),
+            });
+        }
+    }
+    if params.use_seed && params.seed_mode == SeedMode::SameSeed && params.chaos != 0 {
+        warnings.push(Warning::Conflict {
+            flag: "sameseed",
+            other: "chaos",
+            reason: "every image starts from the same noise, so chaos has little effect",
+        });
+    }
+    if params.use_repeat && params.speed == Some(Speed::Relax) {
+        warnings.push(Warning::Conflict {
+            flag: "repeat",
+            other: "relax",
+            reason: "repeat requires fast mode",
+        });
+    }
+    warnings
+}
+
+/// Words that commonly get prompts rejected by Midjourney's content filter
+pub fn default_banned_words() -> Vec<(String, bool)> {
+    [
+        "blood",
+        "bloody",
+        "gore",
+        "gory",
+        "corpse",
+        "decapitated",
+        "torture",
+        "naked",
+        "nude",
+        "nsfw",
+        "sexy",
+        "seductive",
+        "lingerie",
+        "drugs",
+        "cocaine",
+    ]
+    .map(|word| (word.into(), true))
+    .into()
+}
+
+/// Lowercase the words of some text and join them with single spaces,
+/// including at the ends, so that whole words can be found with `contains`
+fn words(s: &str) -> String {
+    let words: Vec<String> = s
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    format!(" {} ", words.join(" "))
+}
+
+/// Find the enabled banned words and phrases that appear in a command,
+/// ignoring its prefix
+///
+/// Matching ignores case and punctuation, and only matches whole words.
+pub fn banned_words<'a>(command: &str, prefix: &str, banned: &'a [(String, bool)]) -> Vec<&'a str> {
+    let command = words(strip_prefix(command, prefix));
+    banned
+        .iter()
+        .filter(|(word, enabled)| *enabled && !word.trim().is_empty())
+        .map(|(word, _)| word.trim())
+        .filter(|word| command.contains(&words(word)))
+        .collect()
+}
+
+/// Tracks the themes seen so far, to find ones that repeat earlier themes or
+/// the prompt text
+struct Seen {
+    text: String,
+    themes: Vec<String>,
+}
+
+impl Seen {
+    fn new(text: &str) -> Self {
+        Seen {
+            text: words(text),
+            themes: Vec::new(),
+        }
+    }
+    /// Whether a theme is a duplicate, remembering it if it isn't
+    fn is_duplicate(&mut self, theme: &str) -> bool {
+        let theme = words(theme);
+        if self.themes.contains(&theme) || self.text.contains(&theme) {
+            return true;
+        }
+        self.themes.push(theme);
+        false
+    }
+}
+
+/// Find the enabled themes that repeat an earlier theme or already appear in
+/// the prompt text
+///
+/// Like [`banned_words`], matching ignores case and punctuation.
+pub fn duplicate_themes<'a>(params: &'a Params, text: &str) -> Vec<&'a str> {
+    let mut seen = Seen::new(text);
+    params
+        .enabled_themes()
+        .map(|(theme, _)| theme)
+        .filter(|theme| seen.is_duplicate(theme))
+        .collect()
+}
+
+/// Remove the themes found by [`duplicate_themes`]
+///
+/// Duplicates in theme groups are disabled rather than removed, so that the
+/// groups stay intact.
+pub fn remove_duplicate_themes(params: &mut Params, text: &str) {
+    let mut seen = Seen::new(text);
+    params.themes.retain(|theme| {
+        !theme.enabled || theme.name.trim().is_empty() || !seen.is_duplicate(&theme.name)
+    });
+    for group in params.theme_groups.iter_mut().filter(|group| group.enabled) {
+        for theme in &mut group.themes {
+            if theme.enabled && !theme.name.trim().is_empty() && seen.is_duplicate(&theme.name) {
+                theme.enabled = false;
+            }
+        }
+    }
+}
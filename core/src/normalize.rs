@@ -0,0 +1,71 @@
+//! Cleaning up text pasted from notes apps and word processors, which often
+//! has emoji, typographic punctuation, and stray separators
+
+/// Strip emoji, convert smart quotes and dashes to ASCII, and collapse
+/// repeated spaces and commas
+///
+/// Line breaks are kept, so that formats with more than one line still work.
+pub fn normalize(text: &str) -> String {
+    let mut s = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' | '\u{2032}' => s.push('\''),
+            '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' | '\u{2033}' => s.push('"'),
+            // Autocorrect turns the `--` of flags into a dash
+            '\u{2013}' | '\u{2014}' if s.is_empty() || s.ends_with(char::is_whitespace) => {
+                if chars.peek().is_some_and(char::is_ascii_alphabetic) {
+                    s.push_str("--");
+                } else {
+                    s.push('-');
+                }
+            }
+            '\u{2010}'..='\u{2015}' | '\u{2212}' => s.push('-'),
+            '\u{2026}' => s.push_str("..."),
+            '\u{00A0}' | '\u{2000}'..='\u{200A}' | '\u{202F}' | '\u{3000}' => s.push(' '),
+            c if is_emoji(c) => {}
+            c => s.push(c),
+        }
+    }
+    let mut normalized = String::with_capacity(s.len());
+    let trim_spaces = |normalized: &mut String| {
+        let len = normalized.trim_end_matches(' ').len();
+        normalized.truncate(len);
+    };
+    for c in s.chars() {
+        match c {
+            ' ' | '\t' => {
+                if !normalized.is_empty() && !normalized.ends_with([' ', '\n']) {
+                    normalized.push(' ');
+                }
+            }
+            ',' => {
+                trim_spaces(&mut normalized);
+                if !normalized.ends_with(',') {
+                    normalized.push(',');
+                }
+            }
+            '\n' => {
+                trim_spaces(&mut normalized);
+                normalized.push('\n');
+            }
+            c => normalized.push(c),
+        }
+    }
+    trim_spaces(&mut normalized);
+    normalized
+}
+
+/// Whether a character is an emoji or part of one, like a skin tone or the
+/// joiner between the parts of a combined emoji
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1F000..=0x1FAFF
+            | 0x2600..=0x27BF
+            | 0x2B00..=0x2BFF
+            | 0xFE00..=0xFE0F
+            | 0x200D
+            | 0xE0020..=0xE007F
+    )
+}
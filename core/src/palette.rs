@@ -0,0 +1,96 @@
+//! Color palettes, written in prompts as color names or hex codes
+
+use serde::{Deserialize, Serialize};
+
+/// Names for colors, which Midjourney understands better than hex codes
+const NAMED: &[(&str, [u8; 3])] = &[
+    ("black", [0, 0, 0]),
+    ("charcoal", [54, 69, 79]),
+    ("gray", [128, 128, 128]),
+    ("silver", [192, 192, 192]),
+    ("white", [255, 255, 255]),
+    ("ivory", [255, 255, 240]),
+    ("beige", [222, 203, 164]),
+    ("tan", [210, 180, 140]),
+    ("brown", [139, 69, 19]),
+    ("chocolate", [90, 50, 25]),
+    ("maroon", [128, 0, 0]),
+    ("crimson", [220, 20, 60]),
+    ("red", [255, 0, 0]),
+    ("coral", [255, 127, 80]),
+    ("salmon", [250, 128, 114]),
+    ("orange", [255, 140, 0]),
+    ("amber", [255, 191, 0]),
+    ("gold", [212, 175, 55]),
+    ("mustard", [225, 173, 1]),
+    ("yellow", [255, 255, 0]),
+    ("cream", [255, 253, 208]),
+    ("olive", [128, 128, 0]),
+    ("lime", [50, 205, 50]),
+    ("green", [0, 128, 0]),
+    ("forest green", [34, 85, 34]),
+    ("sage", [156, 175, 136]),
+    ("mint", [152, 255, 152]),
+    ("emerald", [80, 200, 120]),
+    ("teal", [0, 128, 128]),
+    ("turquoise", [64, 224, 208]),
+    ("cyan", [0, 255, 255]),
+    ("sky blue", [135, 206, 235]),
+    ("azure", [0, 127, 255]),
+    ("blue", [0, 0, 255]),
+    ("cobalt", [0, 71, 171]),
+    ("navy", [0, 0, 128]),
+    ("indigo", [75, 0, 130]),
+    ("violet", [143, 0, 255]),
+    ("purple", [128, 0, 128]),
+    ("lavender", [181, 126, 220]),
+    ("plum", [142, 69, 133]),
+    ("magenta", [255, 0, 255]),
+    ("hot pink", [255, 105, 180]),
+    ("pink", [255, 192, 203]),
+    ("blush", [222, 93, 131]),
+    ("burgundy", [128, 0, 32]),
+];
+
+/// A named set of colors
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Palette {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub colors: Vec<[u8; 3]>,
+}
+
+impl Palette {
+    /// The terms for the palette's colors, either names or hex codes
+    ///
+    /// Colors with the same name are only written once.
+    pub fn terms(&self, named: bool) -> Vec<String> {
+        let mut terms: Vec<String> = Vec::new();
+        for &rgb in &self.colors {
+            let term = if named { name(rgb).into() } else { hex(rgb) };
+            if !terms.contains(&term) {
+                terms.push(term);
+            }
+        }
+        terms
+    }
+}
+
+/// A color as a hex code, like `#1e90ff`
+pub fn hex([r, g, b]: [u8; 3]) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// The name of the closest named color
+pub fn name(rgb: [u8; 3]) -> &'static str {
+    // Weighted for how sensitive the eye is to each channel
+    let distance = |other: [u8; 3]| -> u32 {
+        let d = |i: usize| (i32::from(rgb[i]) - i32::from(other[i])).unsigned_abs();
+        2 * d(0).pow(2) + 4 * d(1).pow(2) + 3 * d(2).pow(2)
+    };
+    NAMED
+        .iter()
+        .min_by_key(|(_, other)| distance(*other))
+        .map_or("gray", |(name, _)| name)
+}
@@ -0,0 +1,462 @@
+//! Parsing of `/imagine` commands back into prompt parameters
+
+use std::{fmt, iter, mem, str::FromStr};
+
+use crate::{
+    info, is_url, Algorithm, Aspect, ImagePrompt, ModelStyle, Params, Quality, SeedMode, Segment,
+    Speed,
+};
+
+#[derive(Debug)]
+pub enum ParseError {
+    UnknownFlag(String),
+    MissingValue(String),
+    InvalidValue { flag: String, value: String },
+    UnexpectedText(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::UnknownFlag(flag) => write!(f, "unknown flag --{flag}"),
+            ParseError::MissingValue(flag) => write!(f, "missing value for --{flag}"),
+            ParseError::InvalidValue { flag, value } => {
+                write!(f, "invalid value for --{flag}: {value}")
+            }
+            ParseError::UnexpectedText(text) => write!(f, "unexpected text: {text}"),
+        }
+    }
+}
+
+/// The result of parsing a command
+pub struct Parsed {
+    pub text: String,
+    pub params: Params,
+    pub errors: Vec<ParseError>,
+}
+
+/// Parse a command
+///
+/// The choice and theme libraries of `base` are kept, with entries added for
+/// anything in the command that they don't already contain.
+/// Commands starting with the prefix of `base` are parsed as well as
+/// `/imagine` commands, and suffixes are split at the separator of `base`.
+pub fn parse(command: &str, base: &Params) -> Parsed {
+    let mut params = base.clone();
+    clear(&mut params);
+    let mut errors = Vec::new();
+
+    let command = command.trim();
+    let prefix = base.prefix.trim();
+    let command = match command.strip_prefix(prefix).filter(|_| !prefix.is_empty()) {
+        Some(rest) => rest.trim(),
+        None => {
+            let command = command
+                .strip_prefix("/imagine")
+                .map_or(command, str::trim_start);
+            command.strip_prefix("prompt:").unwrap_or(command).trim()
+        }
+    };
+    let (body, flags) = if command.starts_with("--") {
+        ("", command)
+    } else {
+        match command.find(" --") {
+            Some(i) => command.split_at(i),
+            None => (command, ""),
+        }
+    };
+
+    // Image prompts
+    let mut body = body.trim();
+    while let Some(url) = body.split_whitespace().next().filter(|token| is_url(token)) {
+        params.images.push(ImagePrompt {
+            url: url.into(),
+            enabled: true,
+            weight: None,
+        });
+        body = body[url.len()..].trim_start();
+    }
+
+    // Multi-prompt segments
+    let mut parts = body.split("::");
+    let main = parts.next().unwrap_or("");
+    for part in parts {
+        let part = part.trim_start();
+        let weight_len = part
+            .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-'))
+            .unwrap_or(part.len());
+        let (weight, text) = part.split_at(weight_len);
+        if let (Ok(weight), Some(segment)) = (weight.parse::<f32>(), params.segments.last_mut()) {
+            segment.weight = weight;
+        }
+        let text = text.trim();
+        if !text.is_empty() {
+            params.segments.push(Segment {
+                text: text.into(),
+                weight: 1.0,
+            });
+        }
+    }
+    // Weighted themes are written as segments
+    let segments = mem::take(&mut params.segments);
+    params.segments = (segments.into_iter())
+        .filter(|segment| !enable_theme(&mut params, &segment.text, Some(segment.weight)))
+        .collect();
+
+    // Text and suffixes
+    let mut text_parts = Vec::new();
+    for (i, part) in split_top_level(main, &params.separator)
+        .into_iter()
+        .enumerate()
+    {
+        if i == 0 || !apply_suffix(&mut params, part) {
+            text_parts.push(part);
+        }
+    }
+    let text = text_parts.join(&params.separator);
+
+    // Flags
+    let mut numbers = Vec::new();
+    let mut algorithm = None;
+    let mut tokens = flags.split_whitespace().peekable();
+    while let Some(token) = tokens.next() {
+        let Some(flag) = token.strip_prefix("--") else {
+            errors.push(ParseError::UnexpectedText(token.into()));
+            continue;
+        };
+        let mut values = Vec::new();
+        while let Some(value) = tokens.next_if(|token| !token.starts_with("--")) {
+            values.push(value);
+        }
+        let value = values.first().copied();
+        let mut extra = values.get(1..).unwrap_or_default();
+        match flag {
+            "no" => {
+                extra = &[];
+                for negative in values.join(" ").split(',') {
+                    let negative = negative.trim();
+                    if !negative.is_empty() {
+                        enable_or_push(&mut params.negatives, negative);
+                    }
+                }
+            }
+            "iw" => {
+                if let Some(weight) = number(flag, value, &mut errors) {
+                    for image in &mut params.images {
+                        image.weight = Some(weight);
+                    }
+                }
+            }
+            "q" | "quality" => {
+                if let Some(quality) =
+                    variant(flag, value, &Quality::ALL, Quality::str, &mut errors)
+                {
+                    params.quality = quality;
+                }
+            }
+            "ar" | "aspect" => {
+                let aspect = value.and_then(|value| {
+                    let (w, h) = value.split_once(':')?;
+                    let (w, h) = (w.parse::<u32>().ok()?, h.parse::<u32>().ok()?);
+                    (w > 0 && h > 0).then(|| Aspect::from_wh(w, h))
+                });
+                match (aspect, value) {
+                    (Some(aspect), _) => params.aspect = aspect,
+                    (None, Some(value)) => errors.push(ParseError::InvalidValue {
+                        flag: flag.into(),
+                        value: value.into(),
+                    }),
+                    (None, None) => errors.push(ParseError::MissingValue(flag.into())),
+                }
+            }
+            "sref" | "cref" => {
+                extra = &[];
+                let refs = if flag == "sref" {
+                    &mut params.style_refs
+                } else {
+                    &mut params.character_refs
+                };
+                for url in &values {
+                    if is_url(url) {
+                        enable_or_push(refs, url);
+                    } else {
+                        errors.push(ParseError::InvalidValue {
+                            flag: flag.into(),
+                            value: (*url).into(),
+                        });
+                    }
+                }
+            }
+            "video" => {
+                extra = &values[..];
+                params.video = true;
+            }
+            "tile" => {
+                extra = &values[..];
+                params.tile = true;
+            }
+            "seed" | "sameseed" => {
+                if let Some(seed) = number(flag, value, &mut errors) {
+                    params.use_seed = true;
+                    params.seed = seed;
+                    params.seed_mode = if flag == "seed" {
+                        SeedMode::Seed
+                    } else {
+                        SeedMode::SameSeed
+                    };
+                }
+            }
+            "fast" | "relax" | "turbo" => {
+                extra = &values[..];
+                params.speed = Speed::ALL.into_iter().find(|speed| speed.str() == flag);
+            }
+            "p" | "personalize" => {
+                extra = &[];
+                let personalization = &mut params.personalization;
+                personalization.enabled = true;
+                personalization.curr = value.map(|code| {
+                    match personalization.codes.iter().find(|(_, c)| c.trim() == code) {
+                        Some((name, _)) => name.clone(),
+                        None => {
+                            personalization.codes.push((code.into(), code.into()));
+                            code.into()
+                        }
+                    }
+                });
+            }
+            "style" => {
+                params.model_style =
+                    variant(flag, value, &ModelStyle::ALL, ModelStyle::str, &mut errors);
+            }
+            "v" | "version" | "niji" | "test" | "testp" => {
+                let full = match (flag, value) {
+                    ("version", Some(value)) => format!("--v {value}"),
+                    ("niji", None) => "--niji 6".into(),
+                    ("test" | "testp", _) => {
+                        extra = &values[..];
+                        format!("--{flag}")
+                    }
+                    (_, Some(value)) => format!("--{flag} {value}"),
+                    (_, None) => {
+                        errors.push(ParseError::MissingValue(flag.into()));
+                        continue;
+                    }
+                };
+                match Algorithm::ALL.into_iter().find(|algo| algo.flag() == full) {
+                    Some(algo) => algorithm = Some(algo),
+                    None => errors.push(ParseError::InvalidValue {
+                        flag: flag.into(),
+                        value: value.unwrap_or_default().into(),
+                    }),
+                }
+            }
+            _ => match info::find_number(flag) {
+                Some((_, param)) => {
+                    // The range depends on the version, which may come later
+                    if let Some(value) = number::<u32>(flag, value, &mut errors) {
+                        numbers.push((param, value));
+                    }
+                }
+                None => {
+                    extra = &[];
+                    errors.push(ParseError::UnknownFlag(flag.into()));
+                }
+            },
+        }
+        if !extra.is_empty() {
+            errors.push(ParseError::UnexpectedText(extra.join(" ")));
+        }
+    }
+
+    params.algorithm = algorithm.unwrap_or(Algorithm::V6);
+    for (_, number) in info::numbers() {
+        if number.toggle.is_none() {
+            *(number.get_mut)(&mut params) = (number.default)(&params.algorithm);
+        }
+    }
+    for (number, value) in numbers {
+        let range = (number.range)(&params.algorithm);
+        *(number.get_mut)(&mut params) = value.clamp(*range.start(), *range.end());
+        if let Some(toggle) = number.toggle {
+            *toggle(&mut params) = true;
+        }
+    }
+    if let Some(style) = params.model_style {
+        if !params.algorithm.allowed_styles().contains(&style) {
+            params.model_style = None;
+        }
+    }
+
+    Parsed {
+        text,
+        params,
+        errors,
+    }
+}
+
+/// Reset everything that a command can set, keeping the libraries of choices
+fn clear(params: &mut Params) {
+    let defaults = Params::default();
+    params.images.clear();
+    params.segments.clear();
+    params.notes.clear();
+    for choices in [
+        &mut params.style,
+        &mut params.color,
+        &mut params.body,
+        &mut params.hair,
+        &mut params.pose,
+    ] {
+        choices.curr = None;
+    }
+    for group in &mut params.theme_groups {
+        group.enabled = false;
+    }
+    for theme in &mut params.themes {
+        theme.enabled = false;
+    }
+    for (_, enabled) in params
+        .permutations
+        .iter_mut()
+        .chain(&mut params.negatives)
+        .chain(&mut params.style_refs)
+        .chain(&mut params.character_refs)
+    {
+        *enabled = false;
+    }
+    params.personalization.enabled = false;
+    params.personalization.curr = None;
+    params.model_style = defaults.model_style;
+    params.aspect = defaults.aspect;
+    params.quality = defaults.quality;
+    params.speed = defaults.speed;
+    params.video = defaults.video;
+    params.tile = defaults.tile;
+    params.use_seed = defaults.use_seed;
+    for (_, number) in info::numbers() {
+        if let Some(toggle) = number.toggle {
+            *toggle(params) = false;
+        }
+    }
+}
+
+/// Split a string on separators that are not inside a permutation group
+///
+/// The separator is trimmed, so that the spacing around it doesn't matter.
+fn split_top_level<'a>(s: &'a str, separator: &str) -> Vec<&'a str> {
+    let separator = match separator.trim() {
+        "" => ",",
+        separator => separator,
+    };
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ if depth == 0 && i >= start && s[i..].starts_with(separator) => {
+                parts.push(s[start..i].trim());
+                start = i + separator.len();
+            }
+            _ => {}
+        }
+    }
+    parts.push(s[start..].trim());
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+/// Try to interpret part of the prompt as a suffix that command() would add
+fn apply_suffix(params: &mut Params, part: &str) -> bool {
+    if let Some(options) = part.strip_prefix('{').and_then(|p| p.strip_suffix('}')) {
+        enable_or_push(&mut params.permutations, options.trim());
+        return true;
+    }
+    for (choices, suffix) in [
+        (&mut params.body, " body"),
+        (&mut params.hair, " hair"),
+        (&mut params.pose, " pose"),
+        (&mut params.color, " colors"),
+    ] {
+        if let Some(value) = part.strip_suffix(suffix) {
+            choices.select(value.trim());
+            return true;
+        }
+    }
+    if params
+        .style
+        .choices
+        .iter()
+        .any(|style| style.trim() == part)
+    {
+        params.style.select(part);
+        return true;
+    }
+    enable_theme(params, part, None)
+}
+
+/// Enable the theme with a name, if there is one, giving it a weight
+fn enable_theme(params: &mut Params, name: &str, weight: Option<f32>) -> bool {
+    let groups = params
+        .theme_groups
+        .iter_mut()
+        .map(|group| (Some(&mut group.enabled), &mut group.themes));
+    for (group_enabled, themes) in iter::once((None, &mut params.themes)).chain(groups) {
+        if let Some(theme) = themes
+            .iter_mut()
+            .find(|theme| theme.name.trim() == name.trim())
+        {
+            theme.enabled = true;
+            theme.weight = weight;
+            if let Some(group_enabled) = group_enabled {
+                *group_enabled = true;
+            }
+            return true;
+        }
+    }
+    false
+}
+
+fn enable_or_push(items: &mut Vec<(String, bool)>, value: &str) {
+    match items.iter_mut().find(|(item, _)| item.trim() == value) {
+        Some((_, enabled)) => *enabled = true,
+        None => items.push((value.into(), true)),
+    }
+}
+
+fn number<T: FromStr>(flag: &str, value: Option<&str>, errors: &mut Vec<ParseError>) -> Option<T> {
+    let Some(value) = value else {
+        errors.push(ParseError::MissingValue(flag.into()));
+        return None;
+    };
+    let parsed = value.parse().ok();
+    if parsed.is_none() {
+        errors.push(ParseError::InvalidValue {
+            flag: flag.into(),
+            value: value.into(),
+        });
+    }
+    parsed
+}
+
+fn variant<T: Copy>(
+    flag: &str,
+    value: Option<&str>,
+    all: &[T],
+    name: fn(&T) -> &'static str,
+    errors: &mut Vec<ParseError>,
+) -> Option<T> {
+    let Some(value) = value else {
+        errors.push(ParseError::MissingValue(flag.into()));
+        return None;
+    };
+    let found = all.iter().copied().find(|item| name(item) == value);
+    if found.is_none() {
+        errors.push(ParseError::InvalidValue {
+            flag: flag.into(),
+            value: value.into(),
+        });
+    }
+    found
+}
@@ -0,0 +1,64 @@
+//! Expansion of Midjourney's `{a, b, c}` permutation syntax
+
+/// Split a string at its first top-level brace group
+///
+/// Returns the text before the group, the comma-separated options inside it,
+/// and the text after it.
+fn split_first_group(s: &str) -> Option<(&str, Vec<&str>, &str)> {
+    let start = s.find('{')?;
+    let mut depth = 0;
+    let mut options = Vec::new();
+    let mut option_start = start + 1;
+    for (i, c) in s[start..].char_indices().map(|(i, c)| (i + start, c)) {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    options.push(s[option_start..i].trim());
+                    return Some((&s[..start], options, &s[i + 1..]));
+                }
+            }
+            ',' if depth == 1 => {
+                options.push(s[option_start..i].trim());
+                option_start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Count the number of prompts a permutation string expands to
+pub fn count(s: &str) -> usize {
+    match split_first_group(s) {
+        Some((_, options, rest)) => options
+            .iter()
+            .map(|option| count(option))
+            .fold(0usize, usize::saturating_add)
+            .saturating_mul(count(rest)),
+        None => 1,
+    }
+}
+
+/// Expand a permutation string into at most `limit` prompts
+pub fn expand(s: &str, limit: usize) -> Vec<String> {
+    let mut expanded = Vec::new();
+    expand_into(String::new(), s, limit, &mut expanded);
+    expanded
+}
+
+fn expand_into(prefix: String, s: &str, limit: usize, expanded: &mut Vec<String>) {
+    if expanded.len() >= limit {
+        return;
+    }
+    match split_first_group(s) {
+        Some((before, options, rest)) => {
+            for option in options {
+                let next = format!("{option}{rest}");
+                expand_into(format!("{prefix}{before}"), &next, limit, expanded);
+            }
+        }
+        None => expanded.push(prefix + s),
+    }
+}
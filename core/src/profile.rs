@@ -0,0 +1,144 @@
+//! Output profiles, for writing prompts for image generators other than
+//! Midjourney
+//!
+//! Each [`Profile`] has a [`Formatter`] that decides which parameters exist
+//! and how the final prompt is written. The Midjourney profiles write
+//! [`Params::command`], and the rest leave out what their generator doesn't
+//! understand.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{sd, Algorithm, Params};
+
+/// The image generator prompts are written for
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Profile {
+    #[default]
+    Midjourney,
+    Niji,
+    #[serde(rename = "dall-e")]
+    DallE,
+    #[serde(rename = "stable diffusion")]
+    StableDiffusion,
+}
+
+impl Profile {
+    pub const ALL: [Profile; 4] = [
+        Profile::Midjourney,
+        Profile::Niji,
+        Profile::DallE,
+        Profile::StableDiffusion,
+    ];
+    pub fn str(&self) -> &'static str {
+        match self {
+            Profile::Midjourney => "Midjourney",
+            Profile::Niji => "niji・journey",
+            Profile::DallE => "DALL·E",
+            Profile::StableDiffusion => "Stable Diffusion",
+        }
+    }
+    pub fn formatter(&self) -> &'static dyn Formatter {
+        match self {
+            Profile::Midjourney => &Midjourney { niji: false },
+            Profile::Niji => &Midjourney { niji: true },
+            Profile::DallE => &DallE,
+            Profile::StableDiffusion => &StableDiffusion,
+        }
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.str().fmt(f)
+    }
+}
+
+/// How a profile writes prompts
+pub trait Formatter: Sync {
+    /// Whether the generator has a parameter, by the name its control is
+    /// shown with
+    ///
+    /// Text that is added to the prompt, like the style and themes, is always
+    /// used.
+    fn has(&self, param: &str) -> bool;
+    /// The versions that can be chosen, if the generator has versions
+    fn algorithms(&self) -> Vec<Algorithm> {
+        Vec::new()
+    }
+    /// Write the final prompt
+    fn format(&self, params: &Params, text: &str) -> String;
+}
+
+/// Midjourney commands, with either the main or the niji versions
+struct Midjourney {
+    niji: bool,
+}
+
+impl Formatter for Midjourney {
+    fn has(&self, _param: &str) -> bool {
+        true
+    }
+    fn algorithms(&self) -> Vec<Algorithm> {
+        Algorithm::ALL
+            .into_iter()
+            .filter(|algorithm| algorithm.is_niji() == self.niji)
+            .collect()
+    }
+    fn format(&self, params: &Params, text: &str) -> String {
+        params.command(text)
+    }
+}
+
+/// A plain description, since DALL·E takes no parameters in the prompt
+struct DallE;
+
+impl Formatter for DallE {
+    fn has(&self, _param: &str) -> bool {
+        false
+    }
+    fn format(&self, params: &Params, text: &str) -> String {
+        sd::describe(params, text, false)
+    }
+}
+
+/// The AUTOMATIC1111 generation parameters format
+struct StableDiffusion;
+
+impl Formatter for StableDiffusion {
+    fn has(&self, param: &str) -> bool {
+        matches!(
+            param,
+            "aspect" | "seed" | "exclude" | "permutations" | "image size"
+        )
+    }
+    fn format(&self, params: &Params, text: &str) -> String {
+        sd::SdPrompt::new(params, text, params.sd_size).automatic1111()
+    }
+}
+
+impl Params {
+    /// Write the prompt the way the profile's generator takes it
+    pub fn output(&self, text: &str) -> String {
+        self.profile.formatter().format(self, text)
+    }
+    /// Switch to a different profile, choosing a version it has if the
+    /// current one isn't
+    pub fn set_profile(&mut self, profile: Profile) {
+        self.profile = profile;
+        let algorithms = profile.formatter().algorithms();
+        if algorithms.is_empty() || algorithms.contains(&self.algorithm) {
+            return;
+        }
+        // The test versions come last but make a poor default
+        let latest = algorithms
+            .iter()
+            .rev()
+            .find(|algorithm| !matches!(algorithm, Algorithm::Test | Algorithm::TestPhoto));
+        if let Some(&latest) = latest {
+            self.set_algorithm(latest);
+        }
+    }
+}
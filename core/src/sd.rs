@@ -0,0 +1,247 @@
+//! Conversion of prompts for Stable Diffusion tools, so that the same idea
+//! can be tried locally
+
+use std::fmt::Write;
+
+use crate::{permute, Aspect, Params, Segment};
+
+/// A prompt as Stable Diffusion tools take it
+pub struct SdPrompt {
+    pub positive: String,
+    pub negative: String,
+    pub width: u32,
+    pub height: u32,
+    /// `None` for a random seed
+    pub seed: Option<u32>,
+}
+
+/// The image sizes models are commonly trained at, which are used as the
+/// side length of a square image
+pub const BASE_SIZES: [u32; 3] = [512, 768, 1024];
+
+impl SdPrompt {
+    /// Convert the parameters and prompt text, with images about as large as
+    /// a square of `base_size`
+    ///
+    /// Multi-prompt weights become `(text:weight)` emphasis, and only the
+    /// first option of each permutation is used. Flags without a Stable
+    /// Diffusion equivalent are dropped.
+    pub fn new(params: &Params, text: &str, base_size: u32) -> Self {
+        let positive = describe(params, text, true);
+        let negative: Vec<&str> = params
+            .negatives
+            .iter()
+            .filter(|(negative, enabled)| *enabled && !negative.trim().is_empty())
+            .map(|(negative, _)| negative.trim())
+            .collect();
+        let [w, h] = params.aspect.wh().unwrap_or([1, 1]);
+        let (width, height) = size(w, h, base_size);
+        SdPrompt {
+            positive,
+            negative: negative.join(", "),
+            width,
+            height,
+            seed: params.use_seed.then_some(params.seed),
+        }
+    }
+    /// The prompt in the generation parameters format of the AUTOMATIC1111
+    /// web UI, which can be pasted into its prompt box and read with the
+    /// button below Generate
+    #[allow(unused_must_use)]
+    pub fn automatic1111(&self) -> String {
+        let mut s = self.positive.clone();
+        if !self.negative.is_empty() {
+            write!(s, "\nNegative prompt: {}", self.negative);
+        }
+        // The web UI only reads the last line if it has at least 3 values,
+        // so the default steps and CFG scale are included
+        let seed = self.seed.map_or(-1, i64::from);
+        write!(
+            s,
+            "\nSteps: 20, CFG scale: 7, Seed: {seed}, Size: {}x{}",
+            self.width, self.height
+        );
+        s
+    }
+    /// Read a prompt in the AUTOMATIC1111 generation parameters format
+    ///
+    /// Settings other than the seed and size are ignored.
+    pub fn from_automatic1111(s: &str) -> Self {
+        let mut prompt = SdPrompt {
+            positive: String::new(),
+            negative: String::new(),
+            width: 512,
+            height: 512,
+            seed: None,
+        };
+        let mut lines: Vec<&str> = s.trim().lines().collect();
+        let settings = lines.last().copied();
+        if let Some(settings) = settings.filter(|line| line.starts_with("Steps: ")) {
+            lines.pop();
+            for setting in settings.split(", ") {
+                match setting.split_once(": ") {
+                    // A seed of -1 is random
+                    Some(("Seed", seed)) => prompt.seed = seed.parse().ok(),
+                    Some(("Size", size)) => {
+                        if let Some((w, h)) = size.split_once('x') {
+                            if let (Ok(w), Ok(h)) = (w.parse(), h.parse()) {
+                                (prompt.width, prompt.height) = (w, h);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let negative_start = lines
+            .iter()
+            .position(|line| line.starts_with("Negative prompt:"))
+            .unwrap_or(lines.len());
+        prompt.positive = lines[..negative_start].join("\n");
+        let negative = lines[negative_start..].join("\n");
+        prompt.negative = negative
+            .trim_start_matches("Negative prompt:")
+            .trim()
+            .into();
+        prompt
+    }
+    /// Set the parameters that have a Midjourney equivalent, and return the
+    /// prompt text
+    ///
+    /// `(text:weight)` emphasis becomes multi-prompt segments, and the
+    /// negative prompt's entries become `--no` entries. LoRAs and other
+    /// `<...>` extras are dropped.
+    pub fn apply(&self, params: &mut Params) -> String {
+        let (text, segments) = split_emphasis(&self.positive);
+        params.segments = segments;
+        params.negatives = self
+            .negative
+            .split([',', '\n'])
+            .map(str::trim)
+            .filter(|negative| !negative.is_empty())
+            .map(|negative| (negative.into(), true))
+            .collect();
+        params.use_seed = self.seed.is_some();
+        if let Some(seed) = self.seed {
+            params.seed = seed;
+        }
+        params.aspect = nearest_aspect(self.width, self.height);
+        text
+    }
+}
+
+/// The prompt text followed by the text the parameters add, with only the
+/// first option of each permutation
+///
+/// Weights are written as `(text:weight)` emphasis if `emphasis` is set, and
+/// dropped otherwise.
+#[allow(unused_must_use)]
+pub(crate) fn describe(params: &Params, text: &str, emphasis: bool) -> String {
+    let mut s = text.trim().to_string();
+    let weighted = |s: &mut String, text: &str, weight: f32| {
+        if emphasis {
+            write!(s, ", ({text}:{weight})")
+        } else {
+            write!(s, ", {text}")
+        }
+    };
+    for (choices, suffix) in [
+        (&params.style, ""),
+        (&params.body, " body"),
+        (&params.hair, " hair"),
+        (&params.pose, " pose"),
+    ] {
+        if let Some(choice) = &choices.curr {
+            write!(s, ", {}{suffix}", choice.trim());
+        }
+    }
+    for (theme, weight) in params.enabled_themes() {
+        match weight {
+            Some(weight) => weighted(&mut s, theme, weight),
+            None => write!(s, ", {theme}"),
+        };
+    }
+    for (options, enabled) in &params.permutations {
+        if *enabled && !options.trim().is_empty() {
+            write!(s, ", {{{}}}", options.trim());
+        }
+    }
+    if let Some(color) = &params.color.curr {
+        write!(s, ", {} colors", color.trim());
+    }
+    for segment in &params.segments {
+        if !segment.text.trim().is_empty() {
+            weighted(&mut s, segment.text.trim(), segment.weight);
+        }
+    }
+    permute::expand(&s, 1).into_iter().next().unwrap_or(s)
+}
+
+/// Separate `(text:weight)` parts from the rest of a prompt, dropping
+/// emphasis without a weight and `<...>` extras
+fn split_emphasis(positive: &str) -> (String, Vec<Segment>) {
+    let mut text = String::new();
+    let mut segments = Vec::new();
+    let mut rest = positive;
+    while let Some(start) = rest.find(['(', '<']) {
+        text.push_str(&rest[..start]);
+        let close = if rest[start..].starts_with('<') {
+            '>'
+        } else {
+            ')'
+        };
+        let Some(len) = rest[start..].find(close) else {
+            rest = &rest[start..];
+            break;
+        };
+        let inner = rest[start + 1..start + len].trim_start_matches('(');
+        rest = rest[start + len + 1..].trim_start_matches(')');
+        if close == '>' {
+            continue;
+        }
+        let weighted = inner
+            .rsplit_once(':')
+            .and_then(|(part, weight)| Some((part.trim(), weight.trim().parse().ok()?)));
+        match weighted {
+            Some((part, weight)) => segments.push(Segment {
+                text: part.into(),
+                weight,
+            }),
+            None => text.push_str(inner),
+        }
+    }
+    text.push_str(rest);
+    // Removed parts leave their separators behind
+    let text = text
+        .replace(['(', ')', '[', ']'], "")
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ");
+    (text, segments)
+}
+
+/// The aspect ratio closest to a size, if one is close enough, or the exact
+/// ratio otherwise
+fn nearest_aspect(width: u32, height: u32) -> Aspect {
+    let ratio = (f64::from(width) / f64::from(height.max(1))).ln();
+    let distance = |aspect: &Aspect| {
+        let [w, h] = aspect.wh().unwrap_or([1, 1]);
+        (ratio - (f64::from(w) / f64::from(h)).ln()).abs()
+    };
+    Aspect::ALL
+        .into_iter()
+        .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+        .filter(|aspect| distance(aspect) < 0.05)
+        .unwrap_or_else(|| Aspect::from_wh(width, height))
+}
+
+/// The width and height with a ratio of `w` to `h` and about the area of a
+/// square of `base_size`, rounded to multiples of 64
+fn size(w: u32, h: u32, base_size: u32) -> (u32, u32) {
+    let area = f64::from(base_size).powi(2);
+    let ratio = f64::from(w) / f64::from(h);
+    let round = |x: f64| ((x / 64.0).round() as u32).max(1) * 64;
+    (round((area * ratio).sqrt()), round((area / ratio).sqrt()))
+}
@@ -0,0 +1,56 @@
+//! Counting words and terms in prompts
+
+use std::collections::HashMap;
+
+use crate::Params;
+
+/// Words too common to be interesting as terms
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "as", "at", "by", "for", "from", "in", "into", "of", "on", "or", "the", "to",
+    "with",
+];
+
+/// The number of words in some text
+pub fn word_count(text: &str) -> usize {
+    text.split_whitespace().count()
+}
+
+/// Sort counts from most to least common, breaking ties alphabetically, and
+/// keep the first `n`
+fn top<T: Ord>(counts: HashMap<T, usize>, n: usize) -> Vec<(T, usize)> {
+    let mut counts: Vec<(T, usize)> = counts.into_iter().collect();
+    counts.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+    counts.truncate(n);
+    counts
+}
+
+/// The `n` most used terms in some prompt texts, ignoring case, punctuation,
+/// and common words
+pub fn top_terms<'a>(texts: impl IntoIterator<Item = &'a str>, n: usize) -> Vec<(String, usize)> {
+    let mut counts = HashMap::new();
+    for text in texts {
+        let terms = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|term| term.len() > 1 && !term.chars().all(|c| c.is_ascii_digit()))
+            .map(str::to_lowercase)
+            .filter(|term| !STOP_WORDS.contains(&term.as_str()));
+        for term in terms {
+            *counts.entry(term).or_insert(0) += 1;
+        }
+    }
+    top(counts, n)
+}
+
+/// The `n` themes that are enabled most often
+pub fn top_themes<'a>(
+    params: impl IntoIterator<Item = &'a Params>,
+    n: usize,
+) -> Vec<(&'a str, usize)> {
+    let mut counts = HashMap::new();
+    for params in params {
+        for (theme, _) in params.enabled_themes() {
+            *counts.entry(theme).or_insert(0) += 1;
+        }
+    }
+    top(counts, n)
+}
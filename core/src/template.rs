@@ -0,0 +1,58 @@
+//! Prompt templates with `{variable}` placeholders
+//!
+//! Braces that contain a comma are permutation options rather than
+//! variables, so the two can be used together.
+
+use std::collections::BTreeMap;
+
+/// Find the braced parts of a text, with their byte ranges
+fn braced(text: &str) -> impl Iterator<Item = (usize, usize, &str)> {
+    let mut start = 0;
+    std::iter::from_fn(move || {
+        let open = start + text[start..].find('{')?;
+        let close = open + text[open..].find('}')?;
+        start = close + 1;
+        Some((open, close + 1, &text[open + 1..close]))
+    })
+}
+
+fn is_variable(name: &str) -> bool {
+    let name = name.trim();
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == ' ')
+}
+
+/// The names of the variables in a template, in order of first appearance
+pub fn variables(text: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    for (.., name) in braced(text) {
+        let name = name.trim();
+        if is_variable(name) && !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+/// Replace each variable that has a value
+///
+/// Variables without a value are left as they are.
+pub fn fill(text: &str, values: &BTreeMap<String, String>) -> String {
+    let mut filled = String::new();
+    let mut last = 0;
+    for (start, end, name) in braced(text) {
+        let value = values
+            .get(name.trim())
+            .map(|value| value.trim())
+            .filter(|value| is_variable(name) && !value.is_empty());
+        if let Some(value) = value {
+            filled.push_str(&text[last..start]);
+            filled.push_str(value);
+            last = end;
+        }
+    }
+    filled.push_str(&text[last..]);
+    filled
+}
@@ -0,0 +1,143 @@
+//! A small offline thesaurus of words that are common in image prompts, for
+//! varying the wording of a prompt
+
+use std::ops::Range;
+
+/// Groups of words that can replace each other, one group per line
+///
+/// A word can be in more than one group, in which case the synonyms from all
+/// of its groups are offered.
+const GROUPS: &str = "\
+beautiful, gorgeous, stunning, lovely, exquisite, elegant
+ugly, grotesque, hideous, unsightly
+big, large, huge, giant, enormous, massive, colossal, towering
+small, tiny, little, miniature, petite, diminutive
+old, ancient, aged, antique, weathered, timeworn
+new, modern, contemporary, fresh, futuristic
+dark, dim, shadowy, gloomy, murky, somber
+bright, luminous, radiant, vivid, brilliant, glowing
+light, soft, gentle, delicate, subtle
+strong, powerful, mighty, bold, intense
+calm, serene, tranquil, peaceful, still, quiet
+chaotic, frantic, turbulent, wild, stormy
+happy, joyful, cheerful, jubilant, gleeful, content
+sad, melancholy, sorrowful, mournful, wistful, forlorn
+angry, furious, enraged, fierce, wrathful
+scary, eerie, creepy, sinister, ominous, haunting
+mysterious, enigmatic, cryptic, arcane, mystical
+magical, enchanted, mystical, ethereal, otherworldly, fantastical
+dreamy, ethereal, surreal, hazy, whimsical
+cute, adorable, charming, sweet, endearing
+detailed, intricate, elaborate, ornate, complex, meticulous
+simple, minimal, minimalist, plain, clean, sparse
+colorful, vibrant, vivid, saturated, kaleidoscopic
+muted, desaturated, faded, pastel, washed-out
+shiny, glossy, gleaming, lustrous, polished, reflective
+rough, rugged, coarse, gritty, textured
+smooth, sleek, silky, polished, glossy
+wet, damp, soaked, dripping, rainy
+cold, icy, frozen, frosty, wintry, chilly
+hot, fiery, blazing, scorching, sweltering
+fast, swift, rapid, speedy, dynamic
+slow, languid, leisurely, unhurried, lazy
+tall, towering, lofty, soaring, high
+city, metropolis, town, cityscape, megacity
+forest, woods, woodland, jungle, grove
+mountain, peak, summit, cliff, ridge
+sea, ocean, waves, surf, tide
+river, stream, creek, brook, waterway
+sky, heavens, firmament, skyline
+sunset, dusk, twilight, evening, sundown
+sunrise, dawn, daybreak, morning, first light
+night, nighttime, midnight, darkness
+fog, mist, haze, smoke, vapor
+storm, tempest, thunderstorm, squall, gale
+castle, fortress, citadel, palace, stronghold
+house, home, cottage, cabin, dwelling
+street, road, alley, avenue, boulevard
+garden, meadow, field, orchard, park
+ruins, wreckage, remains, rubble, debris
+warrior, fighter, soldier, knight, champion
+wizard, mage, sorcerer, magician, warlock
+woman, lady, girl, heroine, maiden
+man, gentleman, guy, hero, fellow
+robot, android, cyborg, automaton, mech
+monster, beast, creature, fiend, demon
+dragon, wyrm, drake, serpent, wyvern
+portrait, headshot, closeup, bust
+landscape, vista, panorama, scenery, view
+painting, artwork, canvas, illustration, piece
+photo, photograph, snapshot, shot, picture
+drawing, sketch, doodle, illustration, lineart
+cinematic, filmic, dramatic, epic, theatrical
+realistic, photorealistic, lifelike, hyperrealistic, naturalistic
+cartoon, animated, toon, comic
+glow, shine, gleam, shimmer, sparkle, radiance
+shadow, silhouette, shade, darkness
+light, illumination, lighting, glow
+color, hue, tint, shade, tone
+red, crimson, scarlet, ruby, vermilion
+blue, azure, cobalt, sapphire, cerulean, navy
+green, emerald, jade, verdant, olive
+yellow, golden, amber, gold, mustard
+purple, violet, lavender, amethyst, plum
+white, ivory, pearl, snowy, alabaster
+black, ebony, jet, onyx, obsidian
+gray, grey, silver, ash, slate
+brown, chocolate, umber, sepia, bronze
+pink, rose, blush, magenta, fuchsia
+orange, tangerine, copper, rust, apricot
+";
+
+/// Other words for a word, written in the same case
+///
+/// Words are matched ignoring case, and a capitalized word gets capitalized
+/// synonyms.
+pub fn synonyms(word: &str) -> Vec<String> {
+    let lower = word.to_lowercase();
+    let mut synonyms: Vec<&str> = Vec::new();
+    for group in GROUPS.lines() {
+        let words: Vec<&str> = group.split(',').map(str::trim).collect();
+        if words.contains(&lower.as_str()) {
+            for synonym in words {
+                if synonym != lower && !synonyms.contains(&synonym) {
+                    synonyms.push(synonym);
+                }
+            }
+        }
+    }
+    let capitalized = word.chars().next().is_some_and(char::is_uppercase);
+    synonyms
+        .into_iter()
+        .map(|synonym| {
+            if capitalized {
+                let mut chars = synonym.chars();
+                chars.next().map_or(String::new(), |first| {
+                    first.to_uppercase().chain(chars).collect()
+                })
+            } else {
+                synonym.into()
+            }
+        })
+        .collect()
+}
+
+/// The byte range of the word at or just before a byte index in some text
+pub fn word_at(text: &str, index: usize) -> Option<Range<usize>> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '-' || c == '\'';
+    let mut index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    let start = text[..index]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_word(*c))
+        .last()
+        .map_or(index, |(i, _)| i);
+    let end = text[index..]
+        .char_indices()
+        .find(|(_, c)| !is_word(*c))
+        .map_or(text.len(), |(i, _)| index + i);
+    (start < end).then_some(start..end)
+}
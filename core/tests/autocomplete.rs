@@ -0,0 +1,33 @@
+//! Tests for completing phrases as they are typed
+
+use midjourney_prompt_core::autocomplete::completion;
+
+const CANDIDATES: &[&str] = &["cyberpunk", "film grain", "Cinematic lighting", "cyber"];
+
+fn complete(text: &str) -> Option<&'static str> {
+    completion(text, text.len(), CANDIDATES)
+}
+
+#[test]
+fn completes_after_min_chars() {
+    assert_eq!(complete("a cat, cy"), None);
+    assert_eq!(complete("a cat, cyb"), Some("erpunk"));
+    assert_eq!(complete("a cat, cyberpunk"), None);
+    assert_eq!(complete("a cat, cyb "), None);
+}
+
+#[test]
+fn completes_phrases_ignoring_case() {
+    assert_eq!(complete("a cat, FILM g"), Some("rain"));
+    assert_eq!(complete("a cat in cinem"), Some("atic lighting"));
+    assert_eq!(complete("{a cat, a dog} cinematic l"), Some("ighting"));
+}
+
+#[test]
+fn only_at_line_end() {
+    let text = "a cat, cyb\n--ar 2:3";
+    assert_eq!(completion(text, 10, CANDIDATES), Some("erpunk"));
+    assert_eq!(completion(text, 9, CANDIDATES), None);
+    assert_eq!(completion("cyb, a cat", 3, CANDIDATES), None);
+    assert_eq!(completion("cyé", 2, CANDIDATES), None);
+}
@@ -0,0 +1,37 @@
+//! Tests for phrasing camera settings
+
+use midjourney_prompt_core::camera::{f_number, lens, Camera};
+
+#[test]
+fn terms() {
+    assert!(Camera::default().terms().is_empty());
+    let camera = Camera {
+        shot: Some("close-up".into()),
+        focal_length: Some(85),
+        aperture: Some(1.8),
+        film: Some("Kodak Portra 400".into()),
+    };
+    assert_eq!(
+        camera.terms(),
+        [
+            "close-up",
+            "85mm portrait lens",
+            "f/1.8, shallow depth of field",
+            "shot on Kodak Portra 400"
+        ]
+    );
+}
+
+#[test]
+fn lenses_and_apertures() {
+    assert_eq!(lens(14), "14mm ultra wide-angle lens");
+    assert_eq!(lens(35), "35mm lens");
+    assert_eq!(lens(400), "400mm telephoto lens");
+    assert_eq!(f_number(4.0), "f/4");
+    assert_eq!(f_number(5.6), "f/5.6");
+    let camera = Camera {
+        aperture: Some(11.0),
+        ..Camera::default()
+    };
+    assert_eq!(camera.terms(), ["f/11, deep depth of field"]);
+}
@@ -0,0 +1,373 @@
+//! Tests for building commands from parameters
+//!
+//! The golden tests compare full commands against the files in
+//! `tests/golden`. After an intended change to the output, run the tests
+//! with `UPDATE_GOLDEN=1` to rewrite the files, and review the diff.
+
+use std::{env, fs, path::Path};
+
+use midjourney_prompt_core::*;
+
+mod common;
+
+use common::empty;
+
+fn list(items: &[&str]) -> Vec<(String, bool)> {
+    items.iter().map(|item| (item.to_string(), true)).collect()
+}
+
+fn themes(names: &[&str]) -> Vec<Theme> {
+    names.iter().map(|name| Theme::new(*name, true)).collect()
+}
+
+/// The flags of a command, in order
+fn flags(command: &str) -> Vec<&str> {
+    command
+        .split_whitespace()
+        .filter_map(|word| word.strip_prefix("--"))
+        .collect()
+}
+
+#[test]
+fn defaults_are_omitted() {
+    assert_eq!(empty().command("a cat"), "/imagine prompt: a cat --v 3");
+    for algorithm in Algorithm::ALL {
+        let mut params = empty();
+        params.set_algorithm(algorithm);
+        assert_eq!(
+            params.command("a cat"),
+            format!("/imagine prompt: a cat {}", algorithm.flag())
+        );
+    }
+}
+
+#[test]
+fn custom_prefix() {
+    let mut params = empty();
+    params.prefix = " /relay ".into();
+    assert_eq!(params.command("a cat"), "/relay a cat --v 3");
+    params.prefix.clear();
+    assert_eq!(params.command("a cat"), "a cat --v 3");
+}
+
+#[test]
+fn values_equal_to_defaults_are_omitted() {
+    let mut params = empty();
+    params.set_algorithm(Algorithm::V6);
+    params.style_refs = list(&["https://example.com/style.png"]);
+    params.character_refs = list(&["https://example.com/character.png"]);
+    params.style_weight = default_style_weight();
+    params.character_weight = default_character_weight();
+    let command = params.command("a cat");
+    assert!(!command.contains("--sw"));
+    assert!(!command.contains("--cw"));
+    assert!(!command.contains("--stylize"));
+}
+
+#[test]
+fn toggled_numbers_are_added_with_defaults() {
+    let mut params = empty();
+    params.use_stop = true;
+    params.use_repeat = true;
+    assert_eq!(
+        params.command("a cat"),
+        "/imagine prompt: a cat --stop 100 --repeat 2 --v 3"
+    );
+}
+
+#[test]
+fn numbers_are_clamped() {
+    let mut params = empty();
+    params.chaos = 500;
+    params.use_stop = true;
+    params.stop = 1;
+    assert_eq!(
+        params.command("a cat"),
+        "/imagine prompt: a cat --chaos 100 --stop 10 --v 3"
+    );
+}
+
+#[test]
+fn weights_need_references() {
+    let mut params = empty();
+    params.set_algorithm(Algorithm::V6);
+    params.style_weight = 300;
+    params.character_weight = 0;
+    assert_eq!(params.command("a cat"), "/imagine prompt: a cat --v 6");
+    params.style_refs = vec![("https://example.com/style.png".into(), false)];
+    params.character_refs = list(&["not a url"]);
+    assert_eq!(params.command("a cat"), "/imagine prompt: a cat --v 6");
+}
+
+#[test]
+fn unsupported_flags_are_omitted() {
+    let mut params = empty();
+    params.set_algorithm(Algorithm::V4);
+    params.tile = true;
+    params.model_style = Some(ModelStyle::Raw);
+    params.weird = 250;
+    params.style_refs = list(&["https://example.com/style.png"]);
+    params.character_refs = list(&["https://example.com/character.png"]);
+    assert_eq!(params.command("a cat"), "/imagine prompt: a cat --v 4");
+}
+
+#[test]
+fn text_is_trimmed() {
+    let mut params = empty();
+    params.style = Choices::new([" lo-fi anime "]);
+    params.style.curr = Some(" lo-fi anime ".into());
+    params.themes = themes(&["  cyberpunk", "", "   "]);
+    params.permutations = list(&[" red, blue ", "  "]);
+    params.negatives = list(&[" text ", ""]);
+    params.segments = vec![
+        Segment {
+            text: " a dog ".into(),
+            weight: 2.0,
+        },
+        Segment {
+            text: "  ".into(),
+            weight: 1.0,
+        },
+    ];
+    assert_eq!(
+        params.command("  a cat  "),
+        "/imagine prompt: a cat, lo-fi anime, cyberpunk, {red, blue}:: a dog::2 --no text --v 3"
+    );
+}
+
+#[test]
+fn images_come_first() {
+    let mut params = empty();
+    params.images = vec![
+        ImagePrompt {
+            url: " https://example.com/a.png ".into(),
+            enabled: true,
+            weight: Some(1.0),
+        },
+        ImagePrompt {
+            url: "https://example.com/b.png".into(),
+            enabled: false,
+            weight: Some(2.0),
+        },
+        ImagePrompt {
+            url: "https://example.com/c.png".into(),
+            enabled: true,
+            weight: Some(0.5),
+        },
+    ];
+    assert_eq!(
+        params.command("a cat"),
+        "/imagine prompt: https://example.com/a.png https://example.com/c.png a cat --iw 0.75 --v 3"
+    );
+}
+
+#[test]
+fn weighted_themes_are_segments() {
+    let mut params = empty();
+    params.themes = themes(&["cyberpunk", "steampunk"]);
+    params.themes[1].weight = Some(0.5);
+    assert_eq!(
+        params.command("a cat"),
+        "/imagine prompt: a cat, cyberpunk:: steampunk::0.5 --v 3"
+    );
+}
+
+#[test]
+fn flag_order() {
+    let mut params = empty();
+    params.set_algorithm(Algorithm::V6);
+    params.negatives = list(&["text"]);
+    params.images = vec![ImagePrompt {
+        url: "https://example.com/a.png".into(),
+        enabled: true,
+        weight: Some(1.5),
+    }];
+    params.quality = Quality::Double;
+    params.stylize = 250;
+    params.chaos = 10;
+    params.weird = 20;
+    params.use_stop = true;
+    params.stop = 50;
+    params.use_repeat = true;
+    params.repeat = 3;
+    params.style_refs = list(&["https://example.com/style.png"]);
+    params.style_weight = 200;
+    params.character_refs = list(&["https://example.com/character.png"]);
+    params.character_weight = 50;
+    params.aspect = Aspect::Wide;
+    params.video = true;
+    params.tile = true;
+    params.use_seed = true;
+    params.seed_mode = SeedMode::Seed;
+    params.seed = 42;
+    params.speed = Some(Speed::Relax);
+    params.personalization.enabled = true;
+    params.model_style = Some(ModelStyle::Raw);
+    assert_eq!(
+        flags(&params.command("a cat")),
+        [
+            "no", "iw", "q", "stylize", "chaos", "weird", "stop", "repeat", "sw", "cw", "ar",
+            "sref", "cref", "video", "tile", "seed", "relax", "p", "style", "v"
+        ]
+    );
+}
+
+#[test]
+fn custom_separator() {
+    let mut params = empty();
+    params.style.curr = Some("oil painting".into());
+    params.themes = themes(&["cyberpunk", "steampunk"]);
+    params.separator = " · ".into();
+    assert_eq!(
+        params.command("a cat"),
+        "/imagine prompt: a cat · oil painting · cyberpunk · steampunk --v 3"
+    );
+    params.separator = ", in the style of ".into();
+    assert_eq!(
+        params.command("a cat"),
+        "/imagine prompt: a cat, in the style of oil painting, in the style of cyberpunk, \
+         in the style of steampunk --v 3"
+    );
+}
+
+#[test]
+fn custom_flag_order() {
+    let mut params = empty();
+    params.set_algorithm(Algorithm::V6);
+    params.negatives = list(&["text"]);
+    params.aspect = Aspect::Wide;
+    params.chaos = 10;
+    params.flag_order = ["version", "ar", "unknown"].map(String::from).into();
+    // Flags missing from the order come last in their default order
+    assert_eq!(flags(&params.command("a cat")), ["v", "ar", "no", "chaos"]);
+    params.flag_order = command::flag_keys()
+        .into_iter()
+        .rev()
+        .map(String::from)
+        .collect();
+    assert_eq!(flags(&params.command("a cat")), ["v", "ar", "chaos", "no"]);
+}
+
+#[test]
+fn number_flags_follow_the_table() {
+    let mut params = empty();
+    params.set_algorithm(Algorithm::V6);
+    params.style_refs = list(&["https://example.com/style.png"]);
+    params.character_refs = list(&["https://example.com/character.png"]);
+    for (_, number) in info::numbers() {
+        if let Some(toggle) = number.toggle {
+            *toggle(&mut params) = true;
+        }
+        let range = (number.range)(&params.algorithm);
+        let default = (number.default)(&params.algorithm);
+        *(number.get_mut)(&mut params) = if default == *range.end() {
+            *range.start()
+        } else {
+            *range.end()
+        };
+    }
+    let command = params.command("a cat");
+    let expected: Vec<&str> = info::numbers()
+        .map(|(info, _)| info.flags[0])
+        .chain(["sref", "cref", "v"])
+        .collect();
+    assert_eq!(flags(&command), expected);
+}
+
+/// Compare a command with a golden file, or rewrite the file if
+/// `UPDATE_GOLDEN` is set
+fn golden(name: &str, params: &Params, text: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(name)
+        .with_extension("txt");
+    let command = params.command(text);
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&path, format!("{command}\n")).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("unable to read {}: {e}", path.display()));
+    assert_eq!(command, expected.trim_end_matches('\n'), "{name}");
+}
+
+#[test]
+fn golden_default() {
+    golden("default", &Params::default(), "a cat");
+}
+
+#[test]
+fn golden_portrait() {
+    let mut params = Params::default();
+    params.set_algorithm(Algorithm::V6);
+    params.model_style = Some(ModelStyle::Raw);
+    params.style.select("ultra realistic");
+    params.hair.select("red");
+    params.pose.select("relaxed");
+    params.themes = themes(&["cinematic lighting"]);
+    params.negatives = list(&["text", "watermark"]);
+    params.aspect = Aspect::Portrait;
+    params.quality = Quality::Double;
+    params.stylize = 250;
+    params.personalization = Personalization {
+        enabled: true,
+        curr: Some("mine".into()),
+        codes: vec![("mine".into(), "abc123".into())],
+    };
+    golden("portrait", &params, "portrait of an old fisherman");
+}
+
+#[test]
+fn golden_niji() {
+    let mut params = Params::default();
+    params.set_algorithm(Algorithm::Niji5);
+    params.model_style = Some(ModelStyle::Scenic);
+    params.color.select("vibrant");
+    params.theme_groups = vec![ThemeGroup {
+        name: "moods".into(),
+        enabled: true,
+        themes: themes(&["whimsical", "dreamy"]),
+    }];
+    params.permutations = list(&["dawn, dusk"]);
+    params.aspect = Aspect::Custom(7, 4);
+    params.chaos = 25;
+    params.use_seed = true;
+    params.seed = 1234;
+    params.speed = Some(Speed::Fast);
+    golden("niji", &params, "a castle on a floating island");
+}
+
+#[test]
+fn golden_references() {
+    let mut params = Params::default();
+    params.set_algorithm(Algorithm::V6);
+    params.images = vec![ImagePrompt {
+        url: "https://example.com/base.png".into(),
+        enabled: true,
+        weight: Some(1.25),
+    }];
+    params.segments = vec![Segment {
+        text: "neon signs".into(),
+        weight: 0.5,
+    }];
+    params.themes = themes(&["cyberpunk"]);
+    params.themes[0].weight = Some(2.0);
+    params.style_refs = list(&[
+        "https://example.com/style1.png",
+        "https://example.com/style2.png",
+    ]);
+    params.style_weight = 400;
+    params.character_refs = list(&["https://example.com/hero.png"]);
+    params.character_weight = 0;
+    params.weird = 250;
+    params.use_stop = true;
+    params.stop = 80;
+    params.use_repeat = true;
+    params.repeat = 4;
+    params.video = true;
+    golden(
+        "references",
+        &params,
+        "a hero walking through a city at night",
+    );
+}
@@ -0,0 +1,16 @@
+//! Fixtures shared by the tests
+
+use midjourney_prompt_core::*;
+
+/// Parameters with nothing selected, so that only the version flag is added
+pub fn empty() -> Params {
+    Params {
+        style: Choices::new([]),
+        themes: Vec::new(),
+        color: Choices::new([]),
+        body: Choices::new([]),
+        hair: Choices::new([]),
+        pose: Choices::new([]),
+        ..Params::default()
+    }
+}
@@ -0,0 +1,47 @@
+//! Tests for estimating GPU time
+
+use midjourney_prompt_core::{cost::Plan, Params, Quality, Speed};
+
+#[test]
+fn default_job() {
+    let params = Params::default();
+    let estimate = params.estimate("a cat");
+    assert_eq!(estimate.jobs, 1);
+    assert!(estimate.fast_minutes > 0.0);
+    assert_eq!(estimate.relaxed_minutes, 0.0);
+}
+
+#[test]
+fn jobs_multiply() {
+    let mut params = Params::default();
+    let single = params.estimate("a cat").fast_minutes;
+    params.use_repeat = true;
+    params.repeat = 3;
+    let estimate = params.estimate("a {cat, dog}");
+    assert_eq!(estimate.jobs, 6);
+    assert!((estimate.fast_minutes - 6.0 * single).abs() < 1e-4);
+}
+
+#[test]
+fn quality_and_speed() {
+    let mut params = Params::default();
+    let single = params.estimate("a cat").fast_minutes;
+    params.quality = Quality::Double;
+    assert_eq!(params.estimate("a cat").fast_minutes, 2.0 * single);
+    params.speed = Some(Speed::Turbo);
+    assert_eq!(params.estimate("a cat").fast_minutes, 4.0 * single);
+    params.speed = Some(Speed::Relax);
+    let estimate = params.estimate("a cat");
+    assert_eq!(estimate.fast_minutes, 0.0);
+    assert_eq!(estimate.relaxed_minutes, 2.0 * single);
+    assert_eq!(estimate.dollars(Plan::Standard), 0.0);
+}
+
+#[test]
+fn plans() {
+    assert!(Plan::Basic.dollars_per_minute() > Plan::Standard.dollars_per_minute());
+    assert_eq!(
+        Plan::Standard.dollars_per_minute(),
+        Plan::Pro.dollars_per_minute()
+    );
+}
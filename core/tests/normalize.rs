@@ -0,0 +1,42 @@
+//! Tests for cleaning up pasted text
+
+use midjourney_prompt_core::normalize::normalize;
+
+#[test]
+fn punctuation_becomes_ascii() {
+    assert_eq!(
+        normalize("a \u{201C}quiet\u{201D} caf\u{e9}\u{2019}s terrace \u{2014} at dusk\u{2026}"),
+        "a \"quiet\" caf\u{e9}'s terrace - at dusk..."
+    );
+}
+
+#[test]
+fn dashes_before_flags_become_double_hyphens() {
+    assert_eq!(
+        normalize("/imagine prompt: a cat \u{2014}ar 16:9 \u{2013}v 6"),
+        "/imagine prompt: a cat --ar 16:9 --v 6"
+    );
+    assert_eq!(normalize("2010\u{2013}2020"), "2010-2020");
+}
+
+#[test]
+fn emoji_are_stripped() {
+    assert_eq!(
+        normalize(
+            "a cat \u{1F431}, \u{2728} sparkles, \u{1F469}\u{1F3FD}\u{200D}\u{1F680} astronaut"
+        ),
+        "a cat, sparkles, astronaut"
+    );
+}
+
+#[test]
+fn separators_are_collapsed() {
+    assert_eq!(
+        normalize("  a cat,,  neon ,  , rain\u{a0}\u{a0}lights  "),
+        "a cat, neon, rain lights"
+    );
+    assert_eq!(
+        normalize("```\n/imagine  a cat \n```"),
+        "```\n/imagine a cat\n```"
+    );
+}
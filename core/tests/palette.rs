@@ -0,0 +1,22 @@
+//! Tests for writing palettes as color terms
+
+use midjourney_prompt_core::palette::{hex, name, Palette};
+
+#[test]
+fn names_and_hex_codes() {
+    assert_eq!(hex([30, 144, 255]), "#1e90ff");
+    assert_eq!(name([0, 0, 0]), "black");
+    assert_eq!(name([10, 10, 120]), "navy");
+    assert_eq!(name([250, 250, 250]), "white");
+    assert_eq!(name([200, 30, 60]), "crimson");
+}
+
+#[test]
+fn terms() {
+    let palette = Palette {
+        name: "sunset".into(),
+        colors: vec![[255, 130, 0], [250, 135, 5], [75, 0, 130]],
+    };
+    assert_eq!(palette.terms(true), ["orange", "indigo"]);
+    assert_eq!(palette.terms(false), ["#ff8200", "#fa8705", "#4b0082"]);
+}
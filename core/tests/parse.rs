@@ -0,0 +1,209 @@
+//! Property tests for parsing commands
+//!
+//! Commands built from parameters should parse back into parameters that
+//! build the same command, and no input, however mangled, should make the
+//! parser panic.
+
+use midjourney_prompt_core::{parse::parse, *};
+use proptest::{collection::vec, option, prelude::*, sample::select};
+
+const WORDS: &[&str] = &[
+    "cat", "dog", "castle", "forest", "neon", "portrait", "old", "misty", "river", "robot",
+];
+const THEMES: &[&str] = &[
+    "cyberpunk",
+    "steampunk",
+    "watercolor",
+    "film grain",
+    "bokeh",
+];
+const NEGATIVES: &[&str] = &["text", "watermark", "blur", "people"];
+const URLS: &[&str] = &[
+    "https://example.com/a.png",
+    "https://example.com/b.png",
+    "https://example.com/c.png",
+];
+
+/// Prompt text that won't be mistaken for anything command() adds
+fn text() -> impl Strategy<Value = String> {
+    vec(select(WORDS), 1..6).prop_map(|words| words.join(" "))
+}
+
+/// Enable some of a list of items
+fn list(items: &'static [&'static str]) -> impl Strategy<Value = Vec<(String, bool)>> {
+    vec(any::<bool>(), items.len()).prop_map(move |enabled| {
+        items
+            .iter()
+            .zip(enabled)
+            .map(|(item, enabled)| (item.to_string(), enabled))
+            .collect()
+    })
+}
+
+/// Enable and weight some of the themes
+fn themes() -> impl Strategy<Value = Vec<Theme>> {
+    let weight = option::of(select(vec![0.5, 1.0, 2.0, -0.5]));
+    vec((any::<bool>(), weight), THEMES.len()).prop_map(|themes| {
+        THEMES
+            .iter()
+            .zip(themes)
+            .map(|(name, (enabled, weight))| Theme {
+                name: name.to_string(),
+                enabled,
+                weight,
+            })
+            .collect()
+    })
+}
+
+fn choice(choices: &Choices) -> impl Strategy<Value = Option<String>> {
+    option::of(select(choices.choices.clone()))
+}
+
+fn aspect() -> impl Strategy<Value = Aspect> {
+    (1..30u32, 1..30u32).prop_map(|(w, h)| Aspect::from_wh(w, h))
+}
+
+fn params() -> impl Strategy<Value = Params> {
+    let defaults = Params::default();
+    let prompt = (
+        (
+            select(vec!["/imagine prompt:", "/relay prompt:", ""]),
+            select(vec![", ", " · ", ", in the style of "]),
+        ),
+        vec(select(URLS), 0..3),
+        choice(&defaults.style),
+        choice(&defaults.hair),
+        choice(&defaults.color),
+        themes(),
+        list(NEGATIVES),
+    );
+    let flags = (
+        select(Algorithm::ALL.to_vec()),
+        option::of(select(ModelStyle::ALL.to_vec())),
+        aspect(),
+        select(Quality::ALL.to_vec()),
+        option::of(select(Speed::ALL.to_vec())),
+        option::of((
+            select(vec![SeedMode::Seed, SeedMode::SameSeed]),
+            any::<u32>(),
+        )),
+        (any::<bool>(), any::<bool>(), any::<bool>()),
+    );
+    let numbers = (
+        0..=60000u32,
+        0..=100u32,
+        0..=3000u32,
+        option::of(10..=100u32),
+        option::of(2..=40u32),
+        (list(URLS), 0..=1000u32),
+        (list(URLS), 0..=100u32),
+    );
+    (prompt, flags, numbers).prop_map(move |(prompt, flags, numbers)| {
+        let ((prefix, separator), images, style, hair, color, themes, negatives) = prompt;
+        let (algorithm, model_style, aspect, quality, speed, seed, toggles) = flags;
+        let (stylize, chaos, weird, stop, repeat, style_refs, character_refs) = numbers;
+        let mut params = defaults.clone();
+        params.set_algorithm(algorithm);
+        params.prefix = prefix.into();
+        params.separator = separator.into();
+        params.images = images
+            .into_iter()
+            .map(|url| ImagePrompt {
+                url: url.into(),
+                enabled: true,
+                weight: None,
+            })
+            .collect();
+        params.style.curr = style;
+        params.hair.curr = hair;
+        params.color.curr = color;
+        params.themes = themes;
+        params.negatives = negatives;
+        params.model_style = model_style;
+        params.aspect = aspect;
+        params.quality = quality;
+        params.speed = speed;
+        if let Some((seed_mode, seed)) = seed {
+            params.use_seed = true;
+            params.seed_mode = seed_mode;
+            params.seed = seed;
+        }
+        (params.video, params.tile, params.personalization.enabled) = toggles;
+        params.stylize = stylize;
+        params.chaos = chaos;
+        params.weird = weird;
+        if let Some(stop) = stop {
+            params.use_stop = true;
+            params.stop = stop;
+        }
+        if let Some(repeat) = repeat {
+            params.use_repeat = true;
+            params.repeat = repeat;
+        }
+        (params.style_refs, params.style_weight) = style_refs;
+        (params.character_refs, params.character_weight) = character_refs;
+        params
+    })
+}
+
+/// Pieces of commands, so that random input gets past the easy cases
+fn fragment() -> impl Strategy<Value = String> {
+    prop_oneof![
+        Just("/imagine prompt:".to_string()),
+        Just("--".to_string()),
+        Just("::".to_string()),
+        Just("{".to_string()),
+        Just("}".to_string()),
+        Just(",".to_string()),
+        Just(" ".to_string()),
+        Just("https://".to_string()),
+        select(URLS).prop_map(String::from),
+        select(WORDS).prop_map(String::from),
+        select(vec![
+            "--v",
+            "--niji",
+            "--ar",
+            "--q",
+            "--no",
+            "--iw",
+            "--sref",
+            "--cref",
+            "--stylize",
+            "--chaos",
+            "--weird",
+            "--stop",
+            "--repeat",
+            "--seed",
+            "--style",
+            "--p",
+        ])
+        .prop_map(String::from),
+        "-?[0-9.:]{1,6}",
+        "\\PC{0,8}",
+    ]
+}
+
+proptest! {
+    #[test]
+    fn build_parse_build(params in params(), text in text()) {
+        let command = params.command(&text);
+        let parsed = parse(&command, &params);
+        let errors: Vec<String> = parsed.errors.iter().map(ToString::to_string).collect();
+        prop_assert!(errors.is_empty(), "{command}: {errors:?}");
+        prop_assert_eq!(&parsed.text, &text);
+        prop_assert_eq!(parsed.params.command(&parsed.text), command);
+    }
+
+    #[test]
+    fn parse_any_string(input in "\\PC*") {
+        let parsed = parse(&input, &Params::default());
+        parsed.params.command(&parsed.text);
+    }
+
+    #[test]
+    fn parse_command_fragments(fragments in vec(fragment(), 0..20)) {
+        let parsed = parse(&fragments.concat(), &Params::default());
+        parsed.params.command(&parsed.text);
+    }
+}
@@ -0,0 +1,62 @@
+//! Tests for writing prompts with each profile
+
+use midjourney_prompt_core::{profile::Profile, sd::SdPrompt, *};
+
+mod common;
+
+fn params() -> Params {
+    let mut params = Params {
+        themes: vec![
+            Theme::new("cyberpunk", true),
+            Theme {
+                weight: Some(1.5),
+                ..Theme::new("neon", true)
+            },
+        ],
+        negatives: vec![("text".into(), true)],
+        ..common::empty()
+    };
+    params.set_algorithm(Algorithm::V6);
+    params.aspect = Aspect::Wide;
+    params
+}
+
+#[test]
+fn midjourney_writes_commands() {
+    let params = params();
+    assert_eq!(
+        params.output("a {cat, dog}"),
+        params.command("a {cat, dog}")
+    );
+}
+
+#[test]
+fn dall_e_writes_descriptions() {
+    let mut params = params();
+    params.set_profile(Profile::DallE);
+    assert_eq!(params.output("a {cat, dog}"), "a cat, cyberpunk, neon");
+}
+
+#[test]
+fn stable_diffusion_writes_generation_parameters() {
+    let mut params = params();
+    params.set_profile(Profile::StableDiffusion);
+    params.sd_size = 512;
+    assert_eq!(
+        params.output("a cat"),
+        SdPrompt::new(&params, "a cat", 512).automatic1111()
+    );
+}
+
+#[test]
+fn profiles_keep_their_versions() {
+    let mut params = params();
+    params.set_profile(Profile::Niji);
+    assert_eq!(params.algorithm, Algorithm::Niji6);
+    params.set_algorithm(Algorithm::Niji5);
+    params.set_profile(Profile::DallE);
+    params.set_profile(Profile::Niji);
+    assert_eq!(params.algorithm, Algorithm::Niji5);
+    params.set_profile(Profile::Midjourney);
+    assert_eq!(params.algorithm, Algorithm::V6);
+}
@@ -0,0 +1,65 @@
+//! Tests for loading parameters saved by older versions
+
+use midjourney_prompt_core::*;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[test]
+fn theme_weights_move_onto_themes() {
+    let mut saved = serde_json::to_value(Params::default()).unwrap();
+    saved["themes"] = json!([["cyberpunk", true], ["steampunk", false]]);
+    saved["theme_groups"] = json!([{
+        "name": "moods",
+        "enabled": true,
+        "themes": [["dreamy", true]],
+    }]);
+    saved["theme_weights"] = json!([["steampunk", 0.5], ["dreamy", 2.0]]);
+    let params: Params = serde_json::from_value(saved).unwrap();
+    assert_eq!(
+        params.themes,
+        [
+            Theme::new("cyberpunk", true),
+            Theme {
+                weight: Some(0.5),
+                ..Theme::new("steampunk", false)
+            },
+        ]
+    );
+    assert_eq!(params.theme_groups[0].themes[0].weight, Some(2.0));
+    assert!(params.old_theme_weights.is_empty());
+    let resaved = serde_json::to_value(&params).unwrap();
+    assert_eq!(
+        resaved["themes"],
+        json!([["cyberpunk", true], ["steampunk", false, 0.5]])
+    );
+    assert!(resaved.get("theme_weights").is_none());
+}
+
+/// Prompt files flatten the parameters into the other settings
+#[derive(Serialize, Deserialize)]
+struct Flattened {
+    #[serde(flatten)]
+    params: Params,
+}
+
+#[test]
+fn custom_aspects_load_when_flattened() {
+    let params = Params {
+        aspect: Aspect::Custom(7, 4),
+        ..Params::default()
+    };
+    let yaml = serde_yaml::to_string(&Flattened { params }).unwrap();
+    assert!(yaml.contains("aspect: 7:4"), "{yaml}");
+    let loaded: Flattened = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(loaded.params.aspect, Aspect::Custom(7, 4));
+}
+
+#[test]
+fn custom_aspects_load_as_older_versions_saved_them() {
+    let tagged: Aspect = serde_yaml::from_str("!custom\n- 7\n- 4\n").unwrap();
+    assert_eq!(tagged, Aspect::Custom(7, 4));
+    let map: Aspect = serde_json::from_value(json!({ "custom": [7, 4] })).unwrap();
+    assert_eq!(map, Aspect::Custom(7, 4));
+    let named: Aspect = serde_yaml::from_str("ultrawide").unwrap();
+    assert_eq!(named, Aspect::UltraWide);
+}
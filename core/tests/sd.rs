@@ -0,0 +1,98 @@
+//! Tests for converting prompts for Stable Diffusion tools
+
+use midjourney_prompt_core::{sd::SdPrompt, *};
+
+mod common;
+
+use common::empty;
+
+#[test]
+fn sizes_keep_the_area() {
+    let mut params = empty();
+    let sd = SdPrompt::new(&params, "a cat", 1024);
+    assert_eq!((sd.width, sd.height), (1024, 1024));
+    params.aspect = Aspect::Portrait;
+    let sd = SdPrompt::new(&params, "a cat", 1024);
+    assert_eq!((sd.width, sd.height), (832, 1280));
+    params.aspect = Aspect::Wide;
+    let sd = SdPrompt::new(&params, "a cat", 512);
+    assert_eq!((sd.width, sd.height), (704, 384));
+}
+
+#[test]
+fn weights_become_emphasis() {
+    let mut params = empty();
+    params.themes = vec![Theme::new("cyberpunk", true), Theme::new("neon", true)];
+    params.themes[1].weight = Some(1.5);
+    params.segments = vec![Segment {
+        text: "rain".into(),
+        weight: 0.5,
+    }];
+    params.permutations = vec![("red, blue".into(), true)];
+    params.negatives = vec![("text".into(), true), ("blur".into(), true)];
+    let sd = SdPrompt::new(&params, "a {cat, dog}", 512);
+    assert_eq!(sd.positive, "a cat, cyberpunk, (neon:1.5), red, (rain:0.5)");
+    assert_eq!(sd.negative, "text, blur");
+}
+
+#[test]
+fn automatic1111_format() {
+    let mut params = empty();
+    params.negatives = vec![("text".into(), true)];
+    params.use_seed = true;
+    params.seed = 42;
+    assert_eq!(
+        SdPrompt::new(&params, "a cat", 512).automatic1111(),
+        "a cat\nNegative prompt: text\nSteps: 20, CFG scale: 7, Seed: 42, Size: 512x512"
+    );
+    params.negatives.clear();
+    params.use_seed = false;
+    assert_eq!(
+        SdPrompt::new(&params, "a cat", 512).automatic1111(),
+        "a cat\nSteps: 20, CFG scale: 7, Seed: -1, Size: 512x512"
+    );
+}
+
+#[test]
+fn read_automatic1111() {
+    let sd = SdPrompt::from_automatic1111(
+        "a cat, ((masterpiece)), (neon:1.5), <lora:detail:0.8>\nin the rain\n\
+         Negative prompt: text, blur\nwatermark\n\
+         Steps: 30, Sampler: Euler a, CFG scale: 7, Seed: 42, Size: 832x1280, Model: sdxl",
+    );
+    assert_eq!(
+        sd.positive,
+        "a cat, ((masterpiece)), (neon:1.5), <lora:detail:0.8>\nin the rain"
+    );
+    assert_eq!(sd.negative, "text, blur\nwatermark");
+    assert_eq!((sd.width, sd.height, sd.seed), (832, 1280, Some(42)));
+    let sd = SdPrompt::from_automatic1111("a cat\nSteps: 20, Seed: -1, Size: 512x512");
+    assert_eq!((sd.positive.as_str(), sd.seed), ("a cat", None));
+}
+
+#[test]
+fn apply_to_params() {
+    let mut params = empty();
+    let sd = SdPrompt::from_automatic1111(
+        "a cat, ((masterpiece)), (neon:1.5), <lora:detail:0.8>, in the rain\n\
+         Negative prompt: text, blur\nwatermark\n\
+         Steps: 30, Seed: 42, Size: 832x1280",
+    );
+    assert_eq!(sd.apply(&mut params), "a cat, masterpiece, in the rain");
+    assert_eq!(params.segments.len(), 1);
+    assert_eq!(params.segments[0].text, "neon");
+    assert_eq!(params.segments[0].weight, 1.5);
+    assert_eq!(
+        params.negatives,
+        [
+            ("text".into(), true),
+            ("blur".into(), true),
+            ("watermark".into(), true)
+        ]
+    );
+    assert_eq!((params.use_seed, params.seed), (true, 42));
+    assert_eq!(params.aspect, Aspect::Portrait);
+    SdPrompt::from_automatic1111("a cat\nSteps: 20, Seed: -1, Size: 1000x300").apply(&mut params);
+    assert!(!params.use_seed);
+    assert_eq!(params.aspect, Aspect::Custom(10, 3));
+}
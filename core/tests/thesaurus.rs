@@ -0,0 +1,31 @@
+//! Tests for suggesting other words
+
+use midjourney_prompt_core::thesaurus::{synonyms, word_at};
+
+#[test]
+fn synonyms_keep_case() {
+    assert!(synonyms("misty").is_empty());
+    let lower = synonyms("fog");
+    assert!(lower.contains(&"mist".to_string()));
+    assert!(!lower.contains(&"fog".to_string()));
+    assert!(synonyms("Fog").contains(&"Mist".to_string()));
+}
+
+#[test]
+fn synonyms_from_every_group() {
+    let synonyms = synonyms("light");
+    assert!(synonyms.contains(&"soft".to_string()));
+    assert!(synonyms.contains(&"illumination".to_string()));
+}
+
+#[test]
+fn words_at_indices() {
+    let text = "an old castle, café";
+    assert_eq!(word_at(text, 0), Some(0..2));
+    assert_eq!(word_at(text, 4), Some(3..6));
+    // Just after a word
+    assert_eq!(word_at(text, 13), Some(7..13));
+    assert_eq!(word_at(text, 14), None);
+    // In the middle of a character
+    assert_eq!(word_at(text, text.len() - 1), Some(15..text.len()));
+}
@@ -0,0 +1,61 @@
+//! A searchable list of artists, tagged by medium and era, for adding
+//! "in the style of" suffixes
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Artist {
+    pub name: String,
+    pub medium: String,
+    pub era: String,
+}
+
+impl Artist {
+    /// The suffix that selecting the artist adds
+    pub fn suffix(&self) -> String {
+        format!("in the style of {}", self.name)
+    }
+}
+
+/// The artists that ship with the app
+pub fn bundled() -> Vec<Artist> {
+    serde_yaml::from_str(include_str!("artists.yaml")).expect("the bundled artists are valid")
+}
+
+/// Add artists from a user's file, replacing bundled ones with the same name
+pub fn merge(artists: &mut Vec<Artist>, other: Vec<Artist>) {
+    for artist in other {
+        match artists.iter_mut().find(|a| a.name == artist.name) {
+            Some(existing) => *existing = artist,
+            None => artists.push(artist),
+        }
+    }
+}
+
+/// The distinct values of a tag, in the order they first appear
+pub fn tags(artists: &[Artist], tag: impl Fn(&Artist) -> &str) -> Vec<&str> {
+    let mut tags = Vec::new();
+    for artist in artists {
+        if !tags.contains(&tag(artist)) {
+            tags.push(tag(artist));
+        }
+    }
+    tags
+}
+
+/// What the artist browser shows
+#[derive(Default)]
+pub struct Filter {
+    pub search: String,
+    pub medium: Option<String>,
+    pub era: Option<String>,
+}
+
+impl Filter {
+    pub fn matches(&self, artist: &Artist) -> bool {
+        let search = self.search.trim().to_lowercase();
+        artist.name.to_lowercase().contains(&search)
+            && self.medium.iter().all(|medium| *medium == artist.medium)
+            && self.era.iter().all(|era| *era == artist.era)
+    }
+}
@@ -0,0 +1,62 @@
+//! Presets packed into a single file or string that can be shared
+//!
+//! A preset's theme groups are part of its parameters, so a bundle holds
+//! everything needed to use the preset elsewhere.
+
+use std::collections::BTreeMap;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use midjourney_prompt_core::Params;
+use serde::{Deserialize, Serialize};
+
+/// The start of a bundle string, so it can be found in pasted text
+const PREFIX: &str = "mjp-preset.";
+
+/// A named preset
+#[derive(Serialize, Deserialize)]
+pub struct Bundle {
+    pub name: String,
+    pub params: Params,
+}
+
+impl Bundle {
+    /// The bundle as a string that is safe to put in urls and chat messages
+    pub fn encode(&self) -> Result<String, String> {
+        let json = serde_json::to_vec(self).map_err(|e| e.to_string())?;
+        Ok(format!("{PREFIX}{}", URL_SAFE_NO_PAD.encode(json)))
+    }
+    /// Read a bundle from text containing a bundle string or from the
+    /// contents of a bundle file
+    pub fn decode(text: &str) -> Result<Self, String> {
+        let Some(start) = text.find(PREFIX) else {
+            return serde_yaml::from_str(text).map_err(|_| "no preset was found".into());
+        };
+        let encoded = text[start + PREFIX.len()..]
+            .split(|c: char| !c.is_ascii_alphanumeric() && c != '-' && c != '_')
+            .next()
+            .unwrap_or_default();
+        let json = URL_SAFE_NO_PAD
+            .decode(encoded)
+            .map_err(|_| "the preset string is cut off or corrupted")?;
+        serde_json::from_slice(&json).map_err(|e| format!("the preset is invalid: {e}"))
+    }
+    /// Add the preset to a set of presets without replacing a different
+    /// preset of the same name, returning the name it was added as
+    pub fn import(self, presets: &mut BTreeMap<String, Params>) -> String {
+        let mut name = self.name.trim().to_string();
+        if name.is_empty() {
+            name = "imported".into();
+        }
+        let base = name.clone();
+        let mut n = 1;
+        while presets
+            .get(&name)
+            .is_some_and(|params| *params != self.params)
+        {
+            n += 1;
+            name = format!("{base} ({n})");
+        }
+        presets.insert(name.clone(), self.params);
+        name
+    }
+}
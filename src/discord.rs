@@ -0,0 +1,37 @@
+//! Sending commands to Discord
+
+use serde::Serialize;
+
+use crate::platform;
+
+#[derive(Serialize)]
+struct WebhookMessage<'a> {
+    content: &'a str,
+}
+
+/// Post a message to a channel through a webhook
+pub fn send_webhook(url: &str, content: &str) -> Result<(), String> {
+    let body = serde_json::to_string(&WebhookMessage { content }).map_err(|e| e.to_string())?;
+    platform::post_json(url.trim(), None, &body).map(drop)
+}
+
+/// A link that opens a channel, in the desktop app or in the browser
+///
+/// Discord links can't fill in a message, so the command has to be pasted.
+/// Without a server, the channel is taken to be a direct message.
+pub fn channel_link(guild: &str, channel: &str, app: bool) -> Option<String> {
+    let channel = channel.trim();
+    if channel.is_empty() {
+        return None;
+    }
+    let guild = match guild.trim() {
+        "" => "@me",
+        guild => guild,
+    };
+    let base = if app {
+        "discord://-"
+    } else {
+        "https://discord.com"
+    };
+    Some(format!("{base}/channels/{guild}/{channel}"))
+}
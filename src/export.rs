@@ -0,0 +1,294 @@
+//! Serialization of settings and commands to the file formats other tools use
+
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+};
+
+use midjourney_prompt_core::{sd::SdPrompt, Params};
+use serde::{de::DeserializeOwned, Serialize};
+
+#[derive(Clone, Copy)]
+pub enum Format {
+    Yaml,
+    Json,
+    Toml,
+}
+
+impl Format {
+    /// Determine the format from a path's extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        Some(match extension.as_str() {
+            "yaml" | "yml" => Format::Yaml,
+            "json" => Format::Json,
+            "toml" => Format::Toml,
+            _ => return None,
+        })
+    }
+    pub fn serialize<T: Serialize>(self, value: &T) -> Result<String, String> {
+        match self {
+            Format::Yaml => serde_yaml::to_string(value).map_err(|e| e.to_string()),
+            Format::Json => serde_json::to_string_pretty(value).map_err(|e| e.to_string()),
+            Format::Toml => toml::to_string_pretty(value).map_err(|e| e.to_string()),
+        }
+    }
+    pub fn deserialize<T: DeserializeOwned>(self, s: &str) -> Result<T, String> {
+        match self {
+            Format::Yaml => serde_yaml::from_str(s).map_err(|e| e.to_string()),
+            Format::Json => serde_json::from_str(s).map_err(|e| e.to_string()),
+            Format::Toml => toml::from_str(s).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// Write a value to a file in the format given by its extension
+pub fn write<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    let format = Format::from_path(path).ok_or("use a .yaml, .json, or .toml file")?;
+    let s = format.serialize(value)?;
+    fs::write(path, s).map_err(|e| e.to_string())
+}
+
+/// Read a value from a file in the format given by its extension
+pub fn read<T: DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let format = Format::from_path(path).ok_or("use a .yaml, .json, or .toml file")?;
+    let s = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    format.deserialize(&s)
+}
+
+/// Read the entries of a plain text list, separated by newlines or commas
+pub fn read_list(path: &Path) -> Result<Vec<String>, String> {
+    let s = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    Ok(s.split(['\n', ','])
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(Into::into)
+        .collect())
+}
+
+/// A generated command and what it was generated from
+pub struct CommandRow<'a> {
+    pub text: &'a str,
+    pub params: &'a Params,
+    pub command: String,
+}
+
+const CSV_COLUMNS: [&str; 12] = [
+    "text", "version", "aspect", "quality", "speed", "stylize", "chaos", "weird", "seed", "tile",
+    "video", "command",
+];
+
+/// Add commands to the end of a file
+///
+/// `.csv` files get a column for each parameter. Anything else gets one
+/// command per line.
+pub fn append_commands(path: &Path, rows: &[CommandRow]) -> Result<(), String> {
+    let csv = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("csv"));
+    let is_new = fs::metadata(path).map_or(true, |meta| meta.len() == 0);
+    let mut contents = String::new();
+    if csv && is_new {
+        csv_line(&mut contents, CSV_COLUMNS.map(String::from));
+    }
+    for row in rows {
+        if csv {
+            let params = row.params;
+            csv_line(
+                &mut contents,
+                [
+                    row.text.trim().into(),
+                    params.algorithm.to_string(),
+                    params.aspect.aspect_string(),
+                    params.quality.to_string(),
+                    params
+                        .speed
+                        .map_or(String::new(), |speed| speed.to_string()),
+                    params.stylize.to_string(),
+                    params.chaos.to_string(),
+                    params.weird.to_string(),
+                    if params.use_seed {
+                        params.seed.to_string()
+                    } else {
+                        String::new()
+                    },
+                    params.tile.to_string(),
+                    params.video.to_string(),
+                    row.command.clone(),
+                ],
+            );
+        } else {
+            contents.push_str(&row.command);
+            contents.push('\n');
+        }
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| e.to_string())?;
+    file.write_all(contents.as_bytes())
+        .map_err(|e| e.to_string())
+}
+
+fn csv_line<const N: usize>(contents: &mut String, fields: [String; N]) {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            contents.push(',');
+        }
+        if field.contains([',', '"', '\n', '\r']) {
+            contents.push('"');
+            contents.push_str(&field.replace('"', "\"\""));
+            contents.push('"');
+        } else {
+            contents.push_str(field);
+        }
+    }
+    contents.push('\n');
+}
+
+/// A link to an output of another node: the node's ID and the output's index
+type Link = (&'static str, u32);
+
+/// A node of a ComfyUI workflow in the API format
+#[derive(Serialize)]
+struct Node<I> {
+    class_type: &'static str,
+    inputs: I,
+}
+
+/// The default text to image workflow, keyed by node ID
+#[derive(Serialize)]
+struct Workflow<'a> {
+    #[serde(rename = "3")]
+    sampler: Node<Sampler>,
+    #[serde(rename = "4")]
+    checkpoint: Node<Checkpoint<'a>>,
+    #[serde(rename = "5")]
+    latent: Node<Latent>,
+    #[serde(rename = "6")]
+    positive: Node<TextEncode<'a>>,
+    #[serde(rename = "7")]
+    negative: Node<TextEncode<'a>>,
+    #[serde(rename = "8")]
+    decode: Node<Decode>,
+    #[serde(rename = "9")]
+    save: Node<Save>,
+}
+
+#[derive(Serialize)]
+struct Sampler {
+    seed: u32,
+    steps: u32,
+    cfg: f32,
+    sampler_name: &'static str,
+    scheduler: &'static str,
+    denoise: f32,
+    model: Link,
+    positive: Link,
+    negative: Link,
+    latent_image: Link,
+}
+
+#[derive(Serialize)]
+struct Checkpoint<'a> {
+    ckpt_name: &'a str,
+}
+
+#[derive(Serialize)]
+struct Latent {
+    width: u32,
+    height: u32,
+    batch_size: u32,
+}
+
+#[derive(Serialize)]
+struct TextEncode<'a> {
+    text: &'a str,
+    clip: Link,
+}
+
+#[derive(Serialize)]
+struct Decode {
+    samples: Link,
+    vae: Link,
+}
+
+#[derive(Serialize)]
+struct Save {
+    filename_prefix: &'static str,
+    images: Link,
+}
+
+/// Write a ComfyUI workflow in the API format that generates the prompt with
+/// a checkpoint
+///
+/// Prompts without a seed get a random one, since ComfyUI needs one.
+pub fn write_comfyui_workflow(
+    path: &Path,
+    prompt: &SdPrompt,
+    checkpoint: &str,
+) -> Result<(), String> {
+    let workflow = Workflow {
+        sampler: Node {
+            class_type: "KSampler",
+            inputs: Sampler {
+                seed: prompt.seed.unwrap_or_else(rand::random),
+                steps: 20,
+                cfg: 7.0,
+                sampler_name: "euler",
+                scheduler: "normal",
+                denoise: 1.0,
+                model: ("4", 0),
+                positive: ("6", 0),
+                negative: ("7", 0),
+                latent_image: ("5", 0),
+            },
+        },
+        checkpoint: Node {
+            class_type: "CheckpointLoaderSimple",
+            inputs: Checkpoint {
+                ckpt_name: checkpoint.trim(),
+            },
+        },
+        latent: Node {
+            class_type: "EmptyLatentImage",
+            inputs: Latent {
+                width: prompt.width,
+                height: prompt.height,
+                batch_size: 1,
+            },
+        },
+        positive: Node {
+            class_type: "CLIPTextEncode",
+            inputs: TextEncode {
+                text: &prompt.positive,
+                clip: ("4", 1),
+            },
+        },
+        negative: Node {
+            class_type: "CLIPTextEncode",
+            inputs: TextEncode {
+                text: &prompt.negative,
+                clip: ("4", 1),
+            },
+        },
+        decode: Node {
+            class_type: "VAEDecode",
+            inputs: Decode {
+                samples: ("3", 0),
+                vae: ("4", 2),
+            },
+        },
+        save: Node {
+            class_type: "SaveImage",
+            inputs: Save {
+                filename_prefix: "midjourney-prompt",
+                images: ("8", 0),
+            },
+        },
+    };
+    let s = serde_json::to_string_pretty(&workflow).map_err(|e| e.to_string())?;
+    fs::write(path, s).map_err(|e| e.to_string())
+}
@@ -0,0 +1,178 @@
+//! Suggestions for improving prompts from a language model, through an
+//! OpenAI-compatible chat completions API
+
+use eframe::egui::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::{platform, task::Task};
+
+const DEFAULT_URL: &str = "https://api.openai.com/v1/chat/completions";
+const DEFAULT_MODEL: &str = "gpt-4o-mini";
+
+/// What to ask the model for
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Request {
+    Expand,
+    Rephrase,
+    Keywords,
+}
+
+impl Request {
+    pub const ALL: [Request; 3] = [Request::Expand, Request::Rephrase, Request::Keywords];
+    pub fn str(&self) -> &'static str {
+        match self {
+            Request::Expand => "expand",
+            Request::Rephrase => "rephrase",
+            Request::Keywords => "style keywords",
+        }
+    }
+    fn instruction(&self) -> &'static str {
+        match self {
+            Request::Expand => {
+                "Write 3 more detailed versions of this Midjourney prompt, adding subject, \
+                 setting, lighting, and composition details."
+            }
+            Request::Rephrase => {
+                "Write 3 rephrasings of this Midjourney prompt that keep its meaning but \
+                 are clearer and more evocative."
+            }
+            Request::Keywords => {
+                "Suggest 8 short style keywords, like art styles, mediums, or lighting, \
+                 that would suit this Midjourney prompt."
+            }
+        }
+    }
+}
+
+/// The API connection and the latest suggestions
+#[derive(Serialize, Deserialize)]
+pub struct Assistant {
+    /// Saved in secrets.yaml
+    #[serde(default, skip_serializing)]
+    pub api_key: String,
+    #[serde(default = "default_url")]
+    pub url: String,
+    #[serde(default = "default_model")]
+    pub model: String,
+    #[serde(skip)]
+    pub suggestions: Vec<String>,
+    /// What the suggestions are for
+    #[serde(skip)]
+    pub request: Option<Request>,
+    #[serde(skip)]
+    task: Option<Task<Result<Vec<String>, String>>>,
+}
+
+fn default_url() -> String {
+    DEFAULT_URL.into()
+}
+
+fn default_model() -> String {
+    DEFAULT_MODEL.into()
+}
+
+impl Default for Assistant {
+    fn default() -> Self {
+        Assistant {
+            api_key: String::new(),
+            url: default_url(),
+            model: default_model(),
+            suggestions: Vec::new(),
+            request: None,
+            task: None,
+        }
+    }
+}
+
+impl Assistant {
+    pub fn is_busy(&self) -> bool {
+        self.task.is_some()
+    }
+    /// Start asking for suggestions for a prompt
+    pub fn ask(&mut self, ctx: &Context, request: Request, prompt: &str) {
+        let url = self.url.trim().to_string();
+        let authorization = format!("Bearer {}", self.api_key.trim());
+        let content = format!("{}\n\n{}", request.instruction(), prompt.trim());
+        let body = serde_json::to_string(&ChatRequest {
+            model: self.model.trim(),
+            messages: [
+                Message {
+                    role: "system",
+                    content: "You help write prompts for the Midjourney image generator. \
+                              Reply with one suggestion per line and nothing else.",
+                },
+                Message {
+                    role: "user",
+                    content: &content,
+                },
+            ],
+        })
+        .map_err(|e| e.to_string());
+        self.request = Some(request);
+        self.suggestions.clear();
+        self.task = Some(Task::spawn(ctx, move || {
+            let response = platform::post_json(&url, Some(&authorization), &body?)?;
+            parse_suggestions(&response)
+        }));
+    }
+    /// Take the suggestions if they have arrived, returning any error
+    pub fn poll(&mut self) -> Result<(), String> {
+        let Some(result) = self.task.as_ref().and_then(Task::poll) else {
+            return Ok(());
+        };
+        self.task = None;
+        self.suggestions = result?;
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: [Message<'a>; 2],
+}
+
+#[derive(Serialize)]
+struct Message<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<Choice>,
+}
+
+#[derive(Deserialize)]
+struct Choice {
+    message: ResponseMessage,
+}
+
+#[derive(Deserialize)]
+struct ResponseMessage {
+    content: String,
+}
+
+/// Split a reply into suggestions, without any list markers the model added
+fn parse_suggestions(response: &str) -> Result<Vec<String>, String> {
+    let response: ChatResponse = serde_json::from_str(response).map_err(|e| e.to_string())?;
+    let content = response
+        .choices
+        .into_iter()
+        .next()
+        .ok_or("the response has no reply")?
+        .message
+        .content;
+    Ok(content
+        .lines()
+        .map(|line| {
+            line.trim()
+                .trim_start_matches(|c: char| c.is_ascii_digit())
+                .trim_start_matches(['-', '*', '•', '.', ')'])
+                .trim()
+                .trim_matches('"')
+                .to_string()
+        })
+        .filter(|line| !line.is_empty())
+        .collect())
+}
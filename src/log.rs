@@ -0,0 +1,38 @@
+//! A log of every copied command, written as JSON lines
+
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+};
+
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+/// The size at which the log is moved aside and a new one is started
+const MAX_LOG_BYTES: u64 = 1 << 20;
+
+#[derive(Serialize)]
+struct LogEntry<'a> {
+    time: DateTime<Local>,
+    command: &'a str,
+}
+
+/// Add a command to the end of the log
+pub fn append(path: &Path, command: &str) -> Result<(), String> {
+    if fs::metadata(path).is_ok_and(|meta| meta.len() > MAX_LOG_BYTES) {
+        let _ = fs::rename(path, path.with_extension("jsonl.old"));
+    }
+    let entry = LogEntry {
+        time: Local::now(),
+        command,
+    };
+    let mut line = serde_json::to_string(&entry).map_err(|e| e.to_string())?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|e| e.to_string())
+}
@@ -1,399 +1,4269 @@
+mod artists;
+mod bundle;
+mod discord;
+mod export;
+#[cfg(feature = "llm")]
+mod llm;
+mod log;
+mod platform;
+mod png;
+mod project;
+mod qr;
+mod secrets;
+mod shortcuts;
+mod snippets;
+mod sync;
+mod task;
+mod thumbnails;
+mod toast;
+mod translate;
+#[cfg(feature = "tui")]
+mod tui;
+mod undo;
+mod upload;
+
 use std::{
-    fmt::{self, Write},
-    fs,
-    path::PathBuf,
+    cell::RefCell,
+    collections::BTreeMap,
+    env, fs, io, iter,
+    ops::Range,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::Duration,
 };
 
-use clipboard::{ClipboardContext, ClipboardProvider};
-use eframe::egui::*;
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Local};
+use eframe::egui::{
+    text::{CCursor, CCursorRange},
+    text_edit::{TextEditOutput, TextEditState},
+    *,
+};
+use midjourney_prompt_core::{
+    autocomplete,
+    camera::{self, Camera},
+    command,
+    cost::Plan,
+    default_prefix, default_separator,
+    diff::{diff_words, Change},
+    highlight::{highlight, SpanKind},
+    info::{self, Number, ParamInfo, Section},
+    is_url,
+    lint::{banned_words, default_banned_words, duplicate_themes, lint, remove_duplicate_themes},
+    normalize::normalize,
+    palette::Palette,
+    parse, permute,
+    profile::Profile,
+    prompt_length,
+    sd::{self, SdPrompt},
+    stats, template, thesaurus, Algorithm, Aspect, Choices, CopyFormat, ImagePrompt, Params,
+    Personalization, Quality, SeedMode, Segment, Speed, Theme, ThemeGroup, MAX_PROMPT_LENGTH,
+};
+use secrets::Secrets;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use shortcuts::Action;
+use thumbnails::Thumbnails;
 
+#[cfg(not(target_arch = "wasm32"))]
 fn main() {
-    let prompt: Prompt = fs::read(Prompt::path())
-        .ok()
-        .and_then(|bytes| serde_yaml::from_slice(&bytes).ok())
-        .unwrap_or_else(|| Prompt {
-            text: String::new(),
-            style: Choices::new(["ultra realistic", "lo-fi anime"]),
-            themes: ["cyberpunk", "steampunk"].map(|s| (s.into(), false)).into(),
-            color: Choices::new(["vibrant", "muted", "grayscale", "high contrast"]),
-            body: Choices::new(["feminine", "masculine"]),
-            hair: Choices::new(["blonde", "brown", "black", "red", "light brown"]),
-            pose: Choices::new(["dynamic", "relaxed", "confident"]),
-            algorithm: Algorithm::V3,
-            aspect: Aspect::Square,
-            stylize: DEFAULT_STYLIZE,
-            use_seed: false,
-            seed: 0,
-            video: false,
-            copy_on_change: true,
-            copied_command: String::new(),
-        });
+    #[allow(unused_mut)]
+    let mut prompt = Prompt::load();
+    #[cfg(feature = "tui")]
+    if env::args().skip(1).any(|arg| arg == "--tui") {
+        for error in &prompt.load_errors {
+            eprintln!("failed to load {}: {}", error.path.display(), error.message);
+        }
+        if prompt.pull_due {
+            prompt.pull_due = false;
+            let pull = Prompt::pull_sync(prompt.sync.clone());
+            prompt.finish_pull(pull);
+        }
+        if let Err(e) = tui::run(&mut prompt) {
+            eprintln!("{e}");
+        }
+        prompt.save_all();
+        prompt.push_now();
+        for error in &prompt.errors {
+            eprintln!("{error}");
+        }
+        // Sync errors are toasts, which the TUI doesn't show
+        for message in prompt.toasts.history() {
+            if message.level == toast::Level::Error {
+                eprintln!("{}", message.text);
+            }
+        }
+        return;
+    }
     let options = eframe::NativeOptions {
-        min_window_size: Some([600.0, 400.0].into()),
-        initial_window_size: Some([600.0, 700.0].into()),
+        min_window_size: Some([300.0, 60.0].into()),
+        initial_window_size: Some(prompt.window_size.unwrap_or(DEFAULT_WINDOW_SIZE).into()),
+        initial_window_pos: prompt.window_pos.map(Into::into),
+        // This can only be set at startup, so compact mode stays on top after
+        // a restart
+        always_on_top: prompt.compact,
         ..Default::default()
     };
     eframe::run_native(
         "Midjourney Prompt",
         options,
-        Box::new(|cc| {
-            cc.egui_ctx.set_pixels_per_point(2.0);
-            Box::new(prompt)
-        }),
+        Box::new(|_cc| Box::new(prompt)),
     );
 }
 
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    console_error_panic_hook::set_once();
+    let prompt = Prompt::load();
+    eframe::start_web(
+        "the_canvas_id",
+        eframe::WebOptions::default(),
+        Box::new(|_cc| Box::new(prompt)),
+    )
+    .expect("failed to start eframe");
+}
+
+/// The sync state after pulling, what was pulled, and the error that
+/// stopped it, if any
+type PullResult = (sync::State, sync::Pulled, Result<(), String>);
+
+/// The sync state before and after pushing, and the conflicts or the error
+/// that stopped it
+type PushResult = (sync::State, sync::State, Result<Vec<String>, String>);
+
+/// A data file that exists but could not be loaded
+struct LoadError {
+    path: PathBuf,
+    message: String,
+    /// Whether a backup was loaded in its place
+    recovered: bool,
+}
+
+/// Load a yaml file, falling back to the newest valid backup
+///
+/// Returns `None` without an error if the file doesn't exist.
+fn load_yaml<T: DeserializeOwned>(path: PathBuf, errors: &mut Vec<LoadError>) -> Option<T> {
+    let message = match platform::read(&path) {
+        Ok(bytes) => match serde_yaml::from_slice(&bytes) {
+            Ok(value) => return Some(value),
+            Err(e) => e.to_string(),
+        },
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return None,
+        Err(e) => e.to_string(),
+    };
+    let recovered =
+        platform::read_backups(&path).find_map(|bytes| serde_yaml::from_slice(&bytes).ok());
+    errors.push(LoadError {
+        path,
+        message,
+        recovered: recovered.is_some(),
+    });
+    recovered
+}
+
+fn save_yaml<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    let yaml = serde_yaml::to_string(value).map_err(|e| e.to_string())?;
+    platform::write(path, &yaml).map_err(|e| e.to_string())
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Images can't be downloaded on the web
+fn default_show_thumbnails() -> bool {
+    cfg!(not(target_arch = "wasm32"))
+}
+
+fn default_ui_scale() -> f32 {
+    2.0
+}
+
+const DEFAULT_WINDOW_SIZE: [f32; 2] = [600.0, 700.0];
+const COMPACT_WINDOW_SIZE: [f32; 2] = [500.0, 70.0];
+
+const MIN_UI_SCALE: f32 = 0.5;
+const MAX_UI_SCALE: f32 = 4.0;
+
 #[derive(Serialize, Deserialize)]
 struct Prompt {
     #[serde(skip)]
     text: String,
-    style: Choices,
-    themes: Vec<(String, bool)>,
-    color: Choices,
-    body: Choices,
-    hair: Choices,
-    pose: Choices,
-    algorithm: Algorithm,
-    aspect: Aspect,
-    stylize: u32,
-    video: bool,
+    #[serde(flatten)]
+    params: Params,
     copy_on_change: bool,
-    use_seed: bool,
-    seed: u32,
+    #[serde(default = "default_true")]
+    dark_mode: bool,
+    /// The size and position of the window when it was last open, in points
+    #[serde(default)]
+    window_size: Option<[f32; 2]>,
+    #[serde(default)]
+    window_pos: Option<[f32; 2]>,
+    /// Show only the prompt and a copy button, in a small window that stays
+    /// on top of others
+    #[serde(default)]
+    compact: bool,
+    /// The window size to return to when leaving compact mode
+    #[serde(default)]
+    expanded_size: Option<[f32; 2]>,
+    /// Pixels per point
+    #[serde(default = "default_ui_scale")]
+    ui_scale: f32,
+    /// The color of selections and links, or `None` for the default
+    #[serde(default)]
+    accent_color: Option<[u8; 3]>,
+    /// Write every copied command to a log file
+    #[serde(default = "default_true")]
+    log_copies: bool,
+    #[serde(default)]
+    copy_format: CopyFormat,
+    /// Clean up emoji and typographic punctuation in copied commands
+    #[serde(default = "default_true")]
+    normalize_copies: bool,
+    /// Whether images at urls are downloaded to show thumbnails
+    #[serde(default = "default_show_thumbnails")]
+    show_thumbnails: bool,
+    #[serde(skip)]
+    thumbnails: Thumbnails,
+    /// The checkpoint file ComfyUI workflows load
+    #[serde(default)]
+    comfyui_checkpoint: String,
+    /// The file ComfyUI workflows are saved to
+    #[serde(default)]
+    comfyui_path: String,
+    /// Refuse to copy commands longer than Midjourney accepts
+    #[serde(default)]
+    block_long_copies: bool,
+    #[serde(default)]
+    recent_seeds: Vec<u32>,
+    /// Words that get prompts rejected, which are pointed out before copying
+    #[serde(default = "default_banned_words")]
+    banned_words: Vec<(String, bool)>,
+    #[serde(skip)]
+    toasts: toast::Toasts,
+    #[serde(skip)]
+    command_cache: RefCell<CommandCache>,
+    #[serde(skip)]
+    presets: BTreeMap<String, Params>,
+    #[serde(skip)]
+    preset_name: String,
+    #[serde(skip)]
+    show_compare: bool,
+    #[serde(skip)]
+    show_share: bool,
+    #[serde(skip)]
+    show_qr: bool,
+    /// Commands kept to be copied again, like while comparing variants
+    #[serde(default)]
+    slots: [String; SLOT_NAMES.len()],
+    /// A pasted preset string to import
+    #[serde(skip)]
+    share_text: String,
+    #[serde(skip)]
+    share_path: String,
+    #[serde(skip)]
+    show_shortcuts: bool,
+    /// The names of the two presets being compared
+    #[serde(skip)]
+    compare: [String; 2],
+    /// Values for the `{variable}`s in the prompt text
+    #[serde(default)]
+    variables: BTreeMap<String, String>,
+    #[serde(skip)]
+    templates: BTreeMap<String, String>,
+    /// Prompt texts to generate commands for, one per line
+    #[serde(skip)]
+    batch_subjects: String,
+    /// The text or CSV file commands are exported to
+    #[serde(default)]
+    commands_path: String,
+    #[serde(skip)]
+    template_name: String,
+    #[serde(skip)]
+    history: Vec<HistoryEntry>,
+    #[serde(skip)]
+    completions: Completions,
+    #[serde(skip)]
+    favorites: Vec<Favorite>,
+    #[serde(skip)]
+    favorites_search: String,
+    #[serde(skip)]
+    history_search: String,
+    /// When the command copied on change is added to the history, if it
+    /// hasn't changed by then
+    #[serde(skip)]
+    history_due: Option<f64>,
+    /// Job ids or image urls waiting to be added to a history entry
+    #[serde(skip)]
+    new_jobs: String,
+    /// The browser shown in the side panel, if any
+    #[serde(default)]
+    library_tab: Option<LibraryTab>,
+    #[serde(skip)]
+    snippets: snippets::Library,
+    /// The snippets shared by all projects, which a project's snippets
+    /// override by category
+    #[serde(skip)]
+    global_snippets: snippets::Library,
+    #[serde(skip)]
+    projects: project::Projects,
+    #[serde(skip)]
+    new_project: String,
+    #[serde(skip)]
+    sync: sync::State,
+    /// Data files that changed both here and in the sync folder
+    #[serde(skip)]
+    sync_conflicts: Vec<String>,
+    /// Whether synced files should be pulled before anything is shown
+    #[serde(skip)]
+    pull_due: bool,
+    #[serde(skip)]
+    pull_task: Option<task::Task<PullResult>>,
+    /// Whether changed files should be pushed once nothing else is syncing
+    #[serde(skip)]
+    push_due: bool,
+    #[serde(skip)]
+    push_task: Option<task::Task<PushResult>>,
+    /// The bundled artists and the ones from the artists file
+    #[serde(skip)]
+    artists: Vec<artists::Artist>,
     #[serde(skip)]
-    copied_command: String,
+    artist_filter: artists::Filter,
+    /// The settings in the camera panel
+    #[serde(default)]
+    camera: Camera,
+    /// The colors being picked
+    #[serde(default)]
+    palette: Palette,
+    /// Saved palettes, which are kept with the prompt file
+    #[serde(default)]
+    palettes: Vec<Palette>,
+    /// Whether colors are written as names rather than hex codes
+    #[serde(default = "default_true")]
+    color_names: bool,
+    #[serde(default)]
+    show_diff: bool,
+    /// The file snippets are imported from and exported to
+    #[serde(default)]
+    snippets_path: String,
+    /// The template for random prompts, with `{category}` for snippets
+    #[serde(default = "snippets::default_template")]
+    surprise_template: String,
+    #[serde(skip)]
+    parse_errors: Vec<String>,
+    /// A flag clicked in the command preview, whose control will be scrolled
+    /// to on the next frame
+    #[serde(skip)]
+    jump_to_flag: Option<String>,
+    #[serde(skip)]
+    undo: undo::Undo<Snapshot>,
+    /// Files that failed to load, which won't be saved over until confirmed
+    #[serde(skip)]
+    load_errors: Vec<LoadError>,
+    /// Errors that don't stop the app from working, shown until dismissed
+    #[serde(skip)]
+    errors: Vec<String>,
+    #[serde(default)]
+    export_path: String,
+    /// A text file of themes to import
+    #[serde(default)]
+    themes_path: String,
+    /// Offer to import commands copied from elsewhere
+    #[serde(default)]
+    watch_clipboard: bool,
+    #[serde(skip)]
+    clipboard: platform::Clipboard,
+    #[serde(default)]
+    clipboard_backend: platform::ClipboardBackend,
+    #[serde(skip)]
+    clipboard_seen: Option<String>,
+    /// When the clipboard was last checked
+    #[serde(skip)]
+    clipboard_polled: Option<f64>,
+    #[serde(skip)]
+    clipboard_offer: Option<String>,
+    /// Saved in secrets.yaml
+    #[serde(default, skip_serializing)]
+    discord_webhook: String,
+    #[serde(default)]
+    discord_guild: String,
+    #[serde(default)]
+    discord_channel: String,
+    /// Whether channel links open the desktop app rather than the browser
+    #[serde(default)]
+    discord_link_app: bool,
+    #[serde(skip)]
+    discord_task: Option<task::Task<Result<String, String>>>,
+    #[cfg(feature = "llm")]
+    #[serde(default)]
+    llm: llm::Assistant,
+    #[serde(default)]
+    translation: translate::Settings,
+    #[serde(skip)]
+    translate_task: Option<task::Task<Result<String, String>>>,
+    /// The subscription plan that costs are estimated for
+    #[serde(default)]
+    plan: Plan,
+    /// Where dropped images are uploaded to use them as image prompts
+    #[serde(default)]
+    upload: upload::Settings,
+    #[serde(skip)]
+    upload_tasks: Vec<task::Task<Result<String, String>>>,
 }
 
-const DEFAULT_STYLIZE: u32 = 2500;
+/// The settings that can be shared with other people and tools
+#[derive(Serialize, Deserialize)]
+struct SharedSettings {
+    params: Params,
+    #[serde(default)]
+    presets: BTreeMap<String, Params>,
+    #[serde(default)]
+    templates: BTreeMap<String, String>,
+}
 
-#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
-enum Algorithm {
-    V3,
-    Test,
-    TestPhoto,
+/// The editable state of a prompt, used for undo and redo
+#[derive(Clone, PartialEq)]
+struct Snapshot {
+    text: String,
+    params: Params,
 }
 
-impl Algorithm {
-    fn str(&self) -> &'static str {
-        match self {
-            Algorithm::V3 => "v3",
-            Algorithm::Test => "test",
-            Algorithm::TestPhoto => "testp",
-        }
-    }
-    fn allowed_aspects(&self) -> &'static [Aspect] {
-        match self {
-            Algorithm::V3 => &[
-                Aspect::Square,
-                Aspect::Portrait,
-                Aspect::Landscape,
-                Aspect::Tall,
-                Aspect::Wide,
-                Aspect::UltraWide,
-            ],
-            Algorithm::Test | Algorithm::TestPhoto => {
-                &[Aspect::Square, Aspect::Portrait, Aspect::Landscape]
-            }
-        }
-    }
+/// The last command that was built and what it was built from
+#[derive(Default)]
+struct CommandCache {
+    text: String,
+    variables: BTreeMap<String, String>,
+    params: Option<Params>,
+    command: Rc<str>,
+}
+
+/// A starred prompt
+#[derive(Serialize, Deserialize)]
+struct Favorite {
+    name: String,
+    /// Comma-separated tags
+    tags: String,
+    command: String,
+    text: String,
+    params: Params,
 }
 
-impl fmt::Display for Algorithm {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.str().fmt(f)
+impl Favorite {
+    fn matches(&self, search: &str) -> bool {
+        let search = search.trim().to_lowercase();
+        search.is_empty()
+            || [&self.name, &self.tags, &self.command]
+                .iter()
+                .any(|s| s.to_lowercase().contains(&search))
     }
 }
 
+/// The browsers that can be shown in the side panel
 #[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
-enum Aspect {
-    Square,
-    Portrait,
-    Landscape,
-    Tall,
-    Wide,
-    UltraWide,
+enum LibraryTab {
+    Presets,
+    Favorites,
+    History,
+    Snippets,
+    Artists,
+    Stats,
+    Messages,
 }
 
-impl Aspect {
+impl LibraryTab {
+    const ALL: [Self; 7] = [
+        LibraryTab::Presets,
+        LibraryTab::Favorites,
+        LibraryTab::History,
+        LibraryTab::Snippets,
+        LibraryTab::Artists,
+        LibraryTab::Stats,
+        LibraryTab::Messages,
+    ];
     fn str(&self) -> &'static str {
         match self {
-            Aspect::Square => "square",
-            Aspect::Tall => "tall",
-            Aspect::Portrait => "portrait",
-            Aspect::Landscape => "landscape",
-            Aspect::Wide => "wide",
-            Aspect::UltraWide => "ultrawide",
-        }
-    }
-    fn aspect_string(&self) -> String {
-        let mut s = self.to_string();
-        if let Some([w, h]) = self.wh() {
-            write!(&mut s, " {w}:{h}").unwrap();
-        }
-        s
-    }
-    fn wh(&self) -> Option<[u8; 2]> {
-        Some(match self {
-            Aspect::Square => return None,
-            Aspect::Portrait => [2, 3],
-            Aspect::Landscape => [3, 2],
-            Aspect::Tall => [1, 2],
-            Aspect::Wide => [16, 9],
-            Aspect::UltraWide => [21, 9],
-        })
+            LibraryTab::Presets => "presets",
+            LibraryTab::Favorites => "favorites",
+            LibraryTab::History => "history",
+            LibraryTab::Snippets => "snippets",
+            LibraryTab::Artists => "artists",
+            LibraryTab::Stats => "stats",
+            LibraryTab::Messages => "messages",
+        }
     }
 }
 
-impl fmt::Display for Aspect {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.str().fmt(f)
+/// A previously copied command along with the state that produced it
+#[derive(Serialize, Deserialize)]
+struct HistoryEntry {
+    time: DateTime<Local>,
+    command: String,
+    text: String,
+    params: Params,
+    /// Midjourney job ids and image urls of what the command made
+    #[serde(default)]
+    jobs: Vec<String>,
+}
+
+impl HistoryEntry {
+    /// Whether the entry's command or jobs contain some text, or a job is
+    /// part of it, so that pasting an image url finds its job id
+    fn matches(&self, search: &str) -> bool {
+        let search = search.trim();
+        self.command.contains(search)
+            || (self.jobs.iter()).any(|job| job.contains(search) || search.contains(job.as_str()))
     }
 }
 
-#[derive(Serialize, Deserialize)]
-struct Choices {
-    curr: Option<String>,
-    choices: Vec<String>,
+const MAX_RECENT_SEEDS: usize = 10;
+const MAX_HISTORY: usize = 200;
+/// How long a command copied on change has to stay the same before it is
+/// added to the history
+const HISTORY_SETTLE_SECS: f64 = 3.0;
+/// The fast GPU minutes above which the estimate is highlighted
+const HEAVY_FAST_MINUTES: f32 = 10.0;
+const MAX_PERMUTATION_JOBS: usize = 40;
+/// The names of the clipboard slots, which the slot shortcuts match
+const SLOT_NAMES: [&str; 3] = ["A", "B", "C"];
+
+trait PersonalizationUi {
+    fn row_ui(&mut self, ui: &mut Ui, algorithm: Algorithm);
+}
+
+impl PersonalizationUi for Personalization {
+    fn row_ui(&mut self, ui: &mut Ui, algorithm: Algorithm) {
+        param_label(ui, "personalize", algorithm);
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.enabled, "");
+            if self.enabled {
+                ComboBox::from_id_source("personalization")
+                    .selected_text(self.curr.as_deref().unwrap_or("default"))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.curr, None, "default");
+                        for (name, _) in self.codes.iter().filter(|(name, _)| !name.is_empty()) {
+                            ui.selectable_value(&mut self.curr, Some(name.clone()), name);
+                        }
+                    });
+            }
+        });
+        CollapsingHeader::new("edit")
+            .id_source("personalization")
+            .show(ui, |ui| {
+                for i in 0..self.codes.len() {
+                    let removed = ui
+                        .horizontal(|ui| {
+                            let (name, code) = &mut self.codes[i];
+                            TextEdit::singleline(name)
+                                .hint_text("name")
+                                .desired_width(80.0)
+                                .ui(ui);
+                            TextEdit::singleline(code)
+                                .hint_text("code")
+                                .desired_width(80.0)
+                                .ui(ui);
+                            ui.button("-").clicked()
+                        })
+                        .inner;
+                    if removed {
+                        self.codes.remove(i);
+                        break;
+                    }
+                }
+                if ui.button("+").clicked() {
+                    self.codes.push((String::new(), String::new()));
+                }
+            });
+        ui.end_row();
+    }
 }
 
 impl Prompt {
+    fn load() -> Self {
+        let mut load_errors = Vec::new();
+        let sync: sync::State = load_yaml(Self::sync_path(), &mut load_errors).unwrap_or_default();
+        let mut projects = Self::load_projects(&mut load_errors);
+        if let Some(current) = load_yaml(Self::current_project_path(), &mut load_errors) {
+            projects.current = current;
+        }
+        // A prompt file from the command line isn't part of any project
+        let current = projects
+            .current
+            .clone()
+            .filter(|_| Self::cli_path().is_none());
+        project::set_current(current.filter(|name| projects.names.contains(name)));
+        let mut prompt: Prompt =
+            load_yaml(Self::path(), &mut load_errors).unwrap_or_else(|| Prompt {
+                text: String::new(),
+                params: Params::default(),
+                copy_on_change: true,
+                dark_mode: true,
+                ui_scale: default_ui_scale(),
+                window_size: None,
+                window_pos: None,
+                compact: false,
+                expanded_size: None,
+                accent_color: None,
+                log_copies: true,
+                copy_format: CopyFormat::default(),
+                normalize_copies: true,
+                show_thumbnails: default_show_thumbnails(),
+                thumbnails: Thumbnails::default(),
+                comfyui_checkpoint: String::new(),
+                comfyui_path: String::new(),
+                block_long_copies: false,
+                recent_seeds: Vec::new(),
+                banned_words: default_banned_words(),
+                toasts: Default::default(),
+                command_cache: Default::default(),
+                presets: BTreeMap::new(),
+                preset_name: String::new(),
+                show_compare: false,
+                show_share: false,
+                show_qr: false,
+                slots: Default::default(),
+                share_text: String::new(),
+                share_path: String::new(),
+                show_shortcuts: false,
+                compare: Default::default(),
+                variables: BTreeMap::new(),
+                templates: BTreeMap::new(),
+                batch_subjects: String::new(),
+                commands_path: String::new(),
+                template_name: String::new(),
+                history: Vec::new(),
+                completions: Completions::default(),
+                favorites: Vec::new(),
+                favorites_search: String::new(),
+                history_search: String::new(),
+                history_due: None,
+                new_jobs: String::new(),
+                library_tab: None,
+                snippets: snippets::Library::new(),
+                global_snippets: snippets::Library::new(),
+                projects: project::Projects::default(),
+                new_project: String::new(),
+                sync: sync::State::default(),
+                sync_conflicts: Vec::new(),
+                pull_due: false,
+                pull_task: None,
+                push_due: false,
+                push_task: None,
+                artists: Vec::new(),
+                artist_filter: Default::default(),
+                camera: Camera::default(),
+                palette: Palette::default(),
+                palettes: Vec::new(),
+                color_names: true,
+                show_diff: false,
+                snippets_path: String::new(),
+                surprise_template: snippets::default_template(),
+                parse_errors: Vec::new(),
+                jump_to_flag: None,
+                undo: Default::default(),
+                load_errors: Vec::new(),
+                errors: Vec::new(),
+                export_path: String::new(),
+                themes_path: String::new(),
+                watch_clipboard: false,
+                clipboard: platform::Clipboard::default(),
+                clipboard_backend: platform::ClipboardBackend::default(),
+                clipboard_seen: None,
+                clipboard_polled: None,
+                clipboard_offer: None,
+                discord_webhook: String::new(),
+                discord_guild: String::new(),
+                discord_channel: String::new(),
+                discord_link_app: false,
+                discord_task: None,
+                #[cfg(feature = "llm")]
+                llm: Default::default(),
+                translation: Default::default(),
+                translate_task: None,
+                plan: Plan::default(),
+                upload: Default::default(),
+                upload_tasks: Vec::new(),
+            });
+        prompt.presets = load_yaml(Self::presets_path(), &mut load_errors).unwrap_or_default();
+        prompt.templates = load_yaml(Self::templates_path(), &mut load_errors).unwrap_or_default();
+        prompt.history = load_yaml(Self::history_path(), &mut load_errors).unwrap_or_default();
+        prompt.favorites = load_yaml(Self::favorites_path(), &mut load_errors).unwrap_or_default();
+        prompt.global_snippets = load_yaml(Self::global_snippets_path(), &mut load_errors)
+            .unwrap_or_else(snippets::default_library);
+        prompt.snippets = if project::current().is_some() {
+            let overrides = load_yaml(Self::snippets_path(), &mut load_errors).unwrap_or_default();
+            snippets::with_overrides(&prompt.global_snippets, overrides)
+        } else {
+            prompt.global_snippets.clone()
+        };
+        if let Some(secrets) = load_yaml(Self::secrets_path(), &mut load_errors) {
+            prompt.set_secrets(secrets);
+        }
+        prompt.projects = projects;
+        prompt.clipboard = platform::Clipboard::new(prompt.clipboard_backend);
+        prompt.pull_due = sync.is_on();
+        prompt.sync = sync;
+        prompt.artists = artists::bundled();
+        if let Some(artists) = load_yaml(Self::artists_path(), &mut load_errors) {
+            artists::merge(&mut prompt.artists, artists);
+        }
+        prompt.load_errors = load_errors;
+        if Self::portable_dir().is_none() {
+            if let Err(e) = platform::data_dir() {
+                prompt.report(format!("{e}, so data is saved in the current folder"));
+            }
+        }
+        prompt
+    }
+    /// The folder data files are kept in, which is the current folder if
+    /// there is no data folder
     fn dir() -> PathBuf {
-        dirs::data_local_dir().unwrap().join("midjourney_prompt")
+        Self::portable_dir()
+            .or_else(|| platform::data_dir().ok())
+            .unwrap_or_default()
+    }
+    /// The executable's directory, if the app is running in portable mode
+    ///
+    /// Portable mode is used when launched with `--portable` or when a prompt
+    /// file already exists next to the executable.
+    fn portable_dir() -> Option<PathBuf> {
+        let exe_dir = env::current_exe().ok()?.parent()?.to_path_buf();
+        let portable = env::args().skip(1).any(|arg| arg == "--portable")
+            || exe_dir.join("promt.yaml").exists();
+        portable.then_some(exe_dir)
+    }
+    /// The folder of the current project's data files
+    fn project_dir() -> PathBuf {
+        project::dir(&Self::dir())
+    }
+    fn projects_path() -> PathBuf {
+        Self::dir().join("projects.yaml")
     }
+    fn current_project_path() -> PathBuf {
+        Self::dir().join("current_project.yaml")
+    }
+    /// Load the projects, leaving out names that aren't safe folder names,
+    /// since the file can come from the sync folder
+    fn load_projects(load_errors: &mut Vec<LoadError>) -> project::Projects {
+        let mut projects: project::Projects =
+            load_yaml(Self::projects_path(), load_errors).unwrap_or_default();
+        projects
+            .names
+            .retain(|name| project::check_name(name).is_ok());
+        projects
+    }
+    /// The prompt file passed on the command line, if there is one
+    fn cli_path() -> Option<PathBuf> {
+        env::args_os()
+            .skip(1)
+            .find(|arg| !arg.to_string_lossy().starts_with("--"))
+            .map(PathBuf::from)
+    }
+    /// The prompt file passed on the command line, or the project's one
     fn path() -> PathBuf {
-        Self::dir().join("promt.yaml")
+        Self::cli_path().unwrap_or_else(|| Self::project_dir().join("promt.yaml"))
+    }
+    /// Save a data file unless it failed to load and overwriting it hasn't
+    /// been confirmed
+    fn save_file<T: Serialize>(&self, path: PathBuf, value: &T) -> Result<(), String> {
+        self.write_file(path, value, platform::write)
     }
-    #[allow(unused_must_use)]
-    fn command(&self) -> String {
-        let mut s = format!("/imagine prompt: {}", self.text.trim());
-        if let Some(style) = &self.style.curr {
-            write!(&mut s, ", {}", style.trim());
+    /// Like [`Prompt::save_file`], but only the user can read the file and
+    /// no backups are kept
+    fn save_private_file<T: Serialize>(&self, path: PathBuf, value: &T) -> Result<(), String> {
+        self.write_file(path, value, platform::write_private)
+    }
+    fn write_file<T: Serialize>(
+        &self,
+        path: PathBuf,
+        value: &T,
+        write: fn(&Path, &str) -> io::Result<()>,
+    ) -> Result<(), String> {
+        if self.load_errors.iter().any(|error| error.path == path) {
+            return Ok(());
         }
-        if let Some(body) = &self.body.curr {
-            write!(&mut s, ", {} body", body.trim());
+        serde_yaml::to_string(value)
+            .map_err(|e| e.to_string())
+            .and_then(|yaml| write(&path, &yaml).map_err(|e| e.to_string()))
+            .map_err(|e| {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                format!("failed to save {name}: {e}")
+            })
+    }
+    /// Show an error until it is dismissed, unless it is already shown
+    fn report(&mut self, error: String) {
+        if !self.errors.contains(&error) {
+            self.errors.push(error);
         }
-        if let Some(hair) = &self.hair.curr {
-            write!(&mut s, ", {} hair", hair.trim());
+    }
+    fn report_result(&mut self, result: Result<(), String>) {
+        if let Err(e) = result {
+            self.report(e);
         }
-        if let Some(pose) = &self.pose.curr {
-            write!(&mut s, ", {} pose", pose.trim());
+    }
+    fn save_all(&mut self) {
+        // Nothing can change while pulling, and the files may be being replaced
+        if self.pull_task.is_some() {
+            return;
         }
-        for (theme, enabled) in &self.themes {
-            if *enabled && !theme.trim().is_empty() {
-                write!(&mut s, ", {}", theme.trim());
-            }
+        self.report_result(self.save_file(Self::path(), &*self));
+        self.save_favorites();
+        self.save_snippets();
+        self.report_result(self.save_private_file(Self::secrets_path(), &self.secrets()));
+        self.report_result(self.save_file(Self::current_project_path(), &self.projects.current));
+        self.push_due = self.sync.is_on();
+    }
+    fn sync_path() -> PathBuf {
+        Self::dir().join("sync.yaml")
+    }
+    /// The credentials, which are shared by all projects and never synced
+    fn secrets_path() -> PathBuf {
+        Self::dir().join("secrets.yaml")
+    }
+    fn secrets(&self) -> Secrets {
+        Secrets {
+            discord_webhook: self.discord_webhook.clone(),
+            imgur_client_id: self.upload.imgur_client_id.clone(),
+            s3_access_key: self.upload.access_key.clone(),
+            s3_secret_key: self.upload.secret_key.clone(),
+            translation_api_key: self.translation.api_key.clone(),
+            #[cfg(feature = "llm")]
+            llm_api_key: self.llm.api_key.clone(),
         }
-        if let Some(color) = &self.color.curr {
-            write!(&mut s, ", {} colors", color.trim());
+    }
+    fn set_secrets(&mut self, secrets: Secrets) {
+        self.discord_webhook = secrets.discord_webhook;
+        self.upload.imgur_client_id = secrets.imgur_client_id;
+        self.upload.access_key = secrets.s3_access_key;
+        self.upload.secret_key = secrets.s3_secret_key;
+        self.translation.api_key = secrets.translation_api_key;
+        #[cfg(feature = "llm")]
+        {
+            self.llm.api_key = secrets.llm_api_key;
         }
-        if self.stylize != DEFAULT_STYLIZE {
-            write!(&mut s, " --stylize {}", self.stylize);
+    }
+    /// The data files that are synced, relative to the data folder
+    ///
+    /// History is left out since it grows separately on every machine.
+    fn synced_files(projects: &project::Projects) -> Vec<String> {
+        let mut files: Vec<String> = ["projects.yaml", "templates.yaml", "artists.yaml"]
+            .map(Into::into)
+            .into();
+        let dirs = iter::once(String::new()).chain(
+            projects
+                .names
+                .iter()
+                .map(|name| format!("projects/{name}/")),
+        );
+        for dir in dirs {
+            for name in [
+                "promt.yaml",
+                "presets.yaml",
+                "favorites.yaml",
+                "snippets.yaml",
+            ] {
+                files.push(format!("{dir}{name}"));
+            }
         }
-        if let Some([w, h]) = self.aspect.wh() {
-            write!(&mut s, " --ar {}:{}", w, h);
+        files
+    }
+    /// Pull the synced files, which can take a while with Git, returning
+    /// the sync state with the new hashes
+    fn pull_sync(mut sync: sync::State) -> PullResult {
+        let base = Self::dir();
+        let mut pulled = sync::Pulled::default();
+        // The projects file is synced first to know which projects there are
+        let result = sync
+            .fetch()
+            .and_then(|()| sync.pull(&base, &["projects.yaml".into()], &mut pulled))
+            .and_then(|()| {
+                let projects = Self::load_projects(&mut Vec::new());
+                sync.pull(&base, &Self::synced_files(&projects), &mut pulled)
+            });
+        (sync, pulled, result)
+    }
+    /// Reload if any files were pulled and show the conflicts
+    fn finish_pull(&mut self, (sync, pulled, result): PullResult) {
+        if !pulled.changed.is_empty() {
+            self.reload();
+            self.pull_due = false;
         }
-        if self.video {
-            s.push_str(" --video");
+        self.sync = sync;
+        for file in pulled.conflicts {
+            if !self.sync_conflicts.contains(&file) {
+                self.sync_conflicts.push(file);
+            }
         }
-        if self.use_seed {
-            write!(&mut s, " --sameseed {}", self.seed);
+        self.report_result(result);
+        self.report_result(self.save_file(Self::sync_path(), &self.sync));
+    }
+    /// Start pulling if it is due and show that it is running, returning
+    /// whether it still is
+    fn pulling(&mut self, ctx: &Context) -> bool {
+        // Pulling waits for a push so that they don't both run Git at once
+        if self.pull_due && self.push_task.is_none() {
+            self.pull_due = false;
+            let sync = self.sync.clone();
+            self.pull_task = Some(task::Task::spawn(ctx, move || Prompt::pull_sync(sync)));
         }
-        if self.algorithm != Algorithm::V3 {
-            write!(&mut s, " --{}", self.algorithm);
+        let Some(task) = &self.pull_task else {
+            return false;
+        };
+        if let Some(pull) = task.poll() {
+            self.pull_task = None;
+            self.finish_pull(pull);
+            return false;
         }
-        s
-    }
-}
-
-impl eframe::App for Prompt {
-    fn on_close_event(&mut self) -> bool {
-        let _ = fs::create_dir_all(Self::dir());
-        let _ = fs::write(Self::path(), serde_yaml::to_string(self).unwrap());
-        true
-    }
-    fn update(&mut self, ctx: &Context, _frame: &mut eframe::Frame) {
-        let old_command = self.command();
         CentralPanel::default().show(ctx, |ui| {
-            // Settings
-            CollapsingHeader::new("settings").show(ui, |ui| {
-                Grid::new("settings").show(ui, |ui| {
-                    let cot_hover_text = "copy command to clipboard when changed";
-                    ui.label("copy on change").on_hover_text(cot_hover_text);
-                    ui.checkbox(&mut self.copy_on_change, "")
-                        .on_hover_text(cot_hover_text);
-                    ui.end_row();
-                });
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("syncing");
             });
-            ui.separator();
-            ScrollArea::both()
-                .auto_shrink([false, true])
-                .show(ui, |ui| {
-                    // Prompt
-                    ui.label("prompt");
-                    TextEdit::multiline(&mut self.text)
-                        .show(ui)
-                        .response
-                        .changed();
-                    // Basic
-                    self.basic_ui(ui);
-                    // Character
-                    self.character_ui(ui);
-                    // Command
-                    ui.label("");
-                    ui.horizontal_wrapped(|ui| {
-                        ui.label(&self.copied_command);
-                    });
-                    let copy_to_clipboard = self.copy_on_change && self.command() != old_command
-                        || !self.copy_on_change
-                            && ui
-                                .add_enabled(!self.text.trim().is_empty(), Button::new("copy"))
-                                .clicked();
-                    if copy_to_clipboard && !self.text.trim().is_empty() {
-                        self.copied_command = match ClipboardContext::new()
-                            .unwrap()
-                            .set_contents(self.command())
-                        {
-                            Ok(()) => {
-                                format!("copied command:\n{}", self.command())
-                            }
-                            Err(e) => format!("error copying command: {e}"),
-                        };
-                    }
-                });
         });
+        true
     }
-}
-
-impl Prompt {
-    fn basic_ui(&mut self, ui: &mut Ui) {
-        Grid::new("basic").show(ui, |ui| {
-            // Algorithm
-            ui.label("algorithm");
-            ui.horizontal(|ui| {
-                for algo in [Algorithm::V3, Algorithm::Test, Algorithm::TestPhoto] {
-                    if ui
-                        .selectable_value(&mut self.algorithm, algo, algo.str())
-                        .clicked()
-                        && !self.algorithm.allowed_aspects().contains(&self.aspect)
-                    {
-                        self.aspect = match self.aspect {
-                            Aspect::Tall => Aspect::Portrait,
-                            Aspect::Wide | Aspect::UltraWide => Aspect::Landscape,
-                            _ => self.aspect,
-                        };
-                    }
-                }
-            });
-            ui.end_row();
-
-            // Aspect
-            ui.label("aspect");
-            ComboBox::from_id_source("aspect")
-                .selected_text(self.aspect.aspect_string())
-                .width(100.0)
-                .show_ui(ui, |ui| {
-                    for aspect in self.algorithm.allowed_aspects() {
-                        ui.selectable_value(&mut self.aspect, *aspect, aspect.aspect_string());
-                    }
-                });
-            ui.end_row();
-
-            // Stylize
-            ui.label("stylize");
-            Slider::new(&mut self.stylize, 625..=60000)
-                .logarithmic(true)
-                .show_value(false)
-                .ui(ui);
-            ui.horizontal(|ui| {
-                DragValue::new(&mut self.stylize)
-                    .clamp_range(625..=60000)
-                    .ui(ui);
-                if self.stylize != DEFAULT_STYLIZE && ui.button("reset").clicked() {
-                    self.stylize = DEFAULT_STYLIZE;
-                }
-            });
-            ui.end_row();
-
-            // Seed
-            ui.label("seed");
-            ui.horizontal(|ui| {
-                ui.checkbox(&mut self.use_seed, "");
-                if self.use_seed {
-                    DragValue::new(&mut self.seed).ui(ui);
-                }
-            });
-            ui.end_row();
-
-            // Video
-            ui.label("video");
-            ui.checkbox(&mut self.video, "");
-            ui.end_row();
-
-            // Style
-            self.style.row_ui(ui, "style");
-
-            // Color
-            self.color.row_ui(ui, "color");
-
-            // Themes
-            ui.label("themes");
-            let mut enabled_themes = String::new();
-            for (theme, enabled) in &self.themes {
-                if *enabled && !theme.trim().is_empty() {
-                    if !enabled_themes.is_empty() {
-                        enabled_themes.push_str(", ");
+    /// Copy changed data files to the sync folder, which can take a while
+    /// with Git
+    fn push_sync(sync: sync::State, files: Vec<String>) -> PushResult {
+        let mut pushed = sync.clone();
+        let result = pushed.push(&Self::dir(), &files);
+        (sync, pushed, result)
+    }
+    /// Record the new hashes and show the conflicts or the error
+    fn finish_push(&mut self, (before, after, result): PushResult) {
+        self.sync.merge(&before, after);
+        match result {
+            Ok(conflicts) => {
+                for file in conflicts {
+                    if !self.sync_conflicts.contains(&file) {
+                        self.sync_conflicts.push(file);
                     }
-                    enabled_themes.push_str(theme.trim());
                 }
             }
-            ui.horizontal_wrapped(|ui| ui.label(enabled_themes));
-            CollapsingHeader::new("edit")
-                .id_source("edit")
-                .show(ui, |ui| {
-                    for i in 0..self.themes.len() {
-                        let removed = ui
-                            .horizontal(|ui| {
-                                let (theme, enabled) = &mut self.themes[i];
-                                TextEdit::singleline(theme).desired_width(100.0).ui(ui);
-                                ui.checkbox(enabled, "");
-                                ui.button("-").clicked()
-                            })
-                            .inner;
-                        if removed {
-                            self.themes.remove(i);
-                            break;
-                        }
-                    }
-                    if ui.button("+").clicked() {
-                        self.themes.push((String::new(), true));
-                    }
-                });
-            ui.end_row();
-        });
+            Err(e) => self.toasts.error(e),
+        }
+        self.report_result(self.save_file(Self::sync_path(), &self.sync));
     }
-    fn character_ui(&mut self, ui: &mut Ui) {
-        CollapsingHeader::new("character")
-            .id_source("character")
-            .show(ui, |ui| {
-                Grid::new("character").show(ui, |ui| {
-                    self.body.row_ui(ui, "body");
-                    self.hair.row_ui(ui, "hair color");
-                    self.pose.row_ui(ui, "pose");
-                });
-            });
+    /// Finish a push that is done and start one if it is due
+    fn pushing(&mut self, ctx: &Context) {
+        if let Some(push) = self.push_task.as_ref().and_then(task::Task::poll) {
+            self.push_task = None;
+            self.finish_push(push);
+        }
+        if self.push_due && self.push_task.is_none() && self.pull_task.is_none() {
+            self.push_due = false;
+            let sync = self.sync.clone();
+            let files = Self::synced_files(&self.projects);
+            self.push_task = Some(task::Task::spawn(ctx, move || {
+                Prompt::push_sync(sync, files)
+            }));
+        }
     }
-}
-
-impl Choices {
-    fn new<'a>(choices: impl IntoIterator<Item = &'a str>) -> Self {
-        Choices {
-            curr: None,
-            choices: choices.into_iter().map(Into::into).collect(),
+    /// Wait for a running push and then push anything still due, for when
+    /// the app is about to exit
+    fn push_now(&mut self) {
+        if let Some(push) = self.push_task.take().and_then(task::Task::wait) {
+            self.finish_push(push);
+        }
+        if self.push_due {
+            self.push_due = false;
+            let push = Prompt::push_sync(self.sync.clone(), Self::synced_files(&self.projects));
+            self.finish_push(push);
+        }
+    }
+    /// Load everything again, keeping a push that is running or due
+    fn reload(&mut self) {
+        let push_task = self.push_task.take();
+        let push_due = self.push_due;
+        *self = Prompt::load();
+        self.push_task = push_task;
+        self.push_due |= push_due;
+    }
+    /// Save everything and open another project, or the default one
+    fn open_project(&mut self, name: Option<String>) {
+        self.save_all();
+        self.switch_project(name);
+    }
+    fn create_project(&mut self) {
+        let name = self.new_project.trim().to_string();
+        if let Err(e) = project::validate_name(&name, &self.projects) {
+            self.toasts.error(e);
+            return;
+        }
+        self.save_all();
+        self.projects.names.push(name.clone());
+        if let Err(e) = save_yaml(&Self::projects_path(), &self.projects) {
+            self.projects.names.pop();
+            self.report(format!("failed to save projects.yaml: {e}"));
+            return;
+        }
+        // The new project starts with the current settings and an empty prompt
+        project::set_current(Some(name.clone()));
+        self.text.clear();
+        self.report_result(self.save_file(Self::path(), &*self));
+        self.switch_project(Some(name));
+    }
+    /// Remove a project from the list, keeping its files
+    fn forget_project(&mut self, name: &str) {
+        self.projects.names.retain(|other| other != name);
+        if let Err(e) = save_yaml(&Self::projects_path(), &self.projects) {
+            self.report(format!("failed to save projects.yaml: {e}"));
+        }
+        if self.projects.current.as_deref() == Some(name) {
+            self.open_project(None);
         }
     }
+    /// Make a project current and load its data, without saving the
+    /// current data
+    fn switch_project(&mut self, name: Option<String>) {
+        if let Err(e) = save_yaml(&Self::current_project_path(), &name) {
+            project::set_current(self.projects.current.clone());
+            self.report(format!("failed to save current_project.yaml: {e}"));
+            return;
+        }
+        self.reload();
+        let name = self.projects.current.as_deref().unwrap_or("default");
+        self.toasts.info(format!("opened project {name}"));
+    }
+    /// Save the snippets, only writing a project's overrides to its folder
+    fn save_snippets(&mut self) {
+        let result = if project::current().is_some() {
+            let overrides = snippets::overrides(&self.global_snippets, &self.snippets);
+            self.save_file(Self::snippets_path(), &overrides)
+        } else {
+            self.global_snippets = self.snippets.clone();
+            self.save_file(Self::global_snippets_path(), &self.snippets)
+        };
+        self.report_result(result);
+    }
+    fn presets_path() -> PathBuf {
+        Self::project_dir().join("presets.yaml")
+    }
+    fn save_presets(&mut self) {
+        self.report_result(self.save_file(Self::presets_path(), &self.presets));
+    }
+    fn templates_path() -> PathBuf {
+        Self::dir().join("templates.yaml")
+    }
+    fn save_templates(&mut self) {
+        self.report_result(self.save_file(Self::templates_path(), &self.templates));
+    }
+    fn history_path() -> PathBuf {
+        Self::project_dir().join("history.yaml")
+    }
+    fn favorites_path() -> PathBuf {
+        Self::project_dir().join("favorites.yaml")
+    }
+    fn save_favorites(&mut self) {
+        self.report_result(self.save_file(Self::favorites_path(), &self.favorites));
+    }
+    fn snippets_path() -> PathBuf {
+        Self::project_dir().join("snippets.yaml")
+    }
+    fn global_snippets_path() -> PathBuf {
+        Self::dir().join("snippets.yaml")
+    }
+    /// Artists added by the user, which are never written by the app
+    fn artists_path() -> PathBuf {
+        Self::dir().join("artists.yaml")
+    }
+    /// The command for the current state, which is only rebuilt when the
+    /// text, variables, or parameters change
+    fn command(&self) -> Rc<str> {
+        let mut cache = self.command_cache.borrow_mut();
+        if cache.text != self.text
+            || cache.variables != self.variables
+            || cache.params.as_ref() != Some(&self.params)
+        {
+            *cache = CommandCache {
+                text: self.text.clone(),
+                variables: self.variables.clone(),
+                params: Some(self.params.clone()),
+                command: self.params.output(&self.filled_text()).into(),
+            };
+        }
+        cache.command.clone()
+    }
+    /// The prompt text with its variables filled in
+    fn filled_text(&self) -> String {
+        template::fill(&self.text, &self.variables)
+    }
+    fn import_command(&mut self, command: &str) {
+        let parsed = parse::parse(command, &self.params);
+        self.text = parsed.text;
+        self.params = parsed.params;
+        self.parse_errors = parsed.errors.iter().map(ToString::to_string).collect();
+    }
+    fn log_path() -> PathBuf {
+        Self::dir().join("copied.jsonl")
+    }
+    /// Record a copied command in the history and the log
+    fn record_history(&mut self) {
+        self.history_due = None;
+        let command = self.command();
+        if self.log_copies {
+            let _ = log::append(&Self::log_path(), &command);
+        }
+        if self
+            .history
+            .last()
+            .is_some_and(|entry| *entry.command == *command)
+        {
+            return;
+        }
+        self.history.push(HistoryEntry {
+            time: Local::now(),
+            command: command.to_string(),
+            text: self.text.clone(),
+            params: self.params.clone(),
+            jobs: Vec::new(),
+        });
+        // Entries with tracked jobs are kept as long as there are others to
+        // drop instead
+        while self.history.len() > MAX_HISTORY {
+            let oldest = self.history.iter().position(|entry| entry.jobs.is_empty());
+            self.history.remove(oldest.unwrap_or(0));
+        }
+        self.report_result(self.save_file(Self::history_path(), &self.history));
+    }
+    /// Import the prompt and the parameters that have an equivalent from an
+    /// image made with Stable Diffusion, or else upload the image to use it
+    /// as an image prompt if uploads are set up
+    fn import_image(&mut self, ctx: &Context, file: &DroppedFile) -> Result<String, String> {
+        let bytes = match (&file.bytes, &file.path) {
+            (Some(bytes), _) => bytes.to_vec(),
+            (None, Some(path)) => fs::read(path).map_err(|e| e.to_string())?,
+            (None, None) => return Err("the file could not be read".into()),
+        };
+        let prompt = match png::read_prompt(&bytes) {
+            Ok(prompt) => prompt,
+            Err(_) if self.upload.is_configured() => {
+                let name = match &file.path {
+                    Some(path) => path.file_name().unwrap_or_default().to_string_lossy(),
+                    None => file.name.as_str().into(),
+                };
+                let name = name.into_owned();
+                let settings = self.upload.clone();
+                self.upload_tasks.push(task::Task::spawn(ctx, move || {
+                    upload::upload(&settings, &name, &bytes)
+                }));
+                return Ok("uploading the image".into());
+            }
+            Err(e) => return Err(e),
+        };
+        self.text = prompt.apply(&mut self.params);
+        self.parse_errors.clear();
+        Ok("imported the prompt from the image".into())
+    }
+    fn sync_settings_ui(&mut self, ui: &mut Ui) {
+        ComboBox::from_id_source("sync mode")
+            .selected_text(self.sync.mode.str())
+            .show_ui(ui, |ui| {
+                for mode in sync::Mode::ALL {
+                    ui.selectable_value(&mut self.sync.mode, mode, mode.str());
+                }
+            });
+        if self.sync.mode == sync::Mode::Off {
+            return;
+        }
+        TextEdit::singleline(&mut self.sync.folder)
+            .hint_text("folder")
+            .desired_width(200.0)
+            .ui(ui);
+        let sync_now = ui
+            .add_enabled(self.sync.is_on(), Button::new("sync now"))
+            .on_hover_text("save and copy changes to the sync folder")
+            .clicked();
+        if sync_now {
+            self.save_all();
+            self.toasts.info("syncing");
+        }
+    }
+    fn upload_settings_ui(&mut self, ui: &mut Ui) {
+        let upload = &mut self.upload;
+        ComboBox::from_id_source("upload backend")
+            .selected_text(upload.backend.str())
+            .show_ui(ui, |ui| {
+                for backend in upload::Backend::ALL {
+                    ui.selectable_value(&mut upload.backend, backend, backend.str());
+                }
+            });
+        let field = |ui: &mut Ui, text: &mut String, hint: &str, secret: bool| {
+            TextEdit::singleline(text)
+                .hint_text(hint)
+                .password(secret)
+                .desired_width(100.0)
+                .ui(ui);
+        };
+        match upload.backend {
+            upload::Backend::None => {}
+            upload::Backend::Imgur => field(ui, &mut upload.imgur_client_id, "client id", true),
+            upload::Backend::S3 => {
+                field(ui, &mut upload.bucket, "bucket", false);
+                field(ui, &mut upload.region, "region", false);
+                field(ui, &mut upload.access_key, "access key", false);
+                field(ui, &mut upload.secret_key, "secret key", true);
+                field(ui, &mut upload.public_url, "public url (optional)", false);
+            }
+        }
+    }
+    /// Add the images that have finished uploading as image prompts
+    fn poll_uploads(&mut self) {
+        let mut i = 0;
+        while i < self.upload_tasks.len() {
+            let Some(result) = self.upload_tasks[i].poll() else {
+                i += 1;
+                continue;
+            };
+            self.upload_tasks.remove(i);
+            let result = result.map(|url| {
+                self.params.images.push(ImagePrompt {
+                    url: url.clone(),
+                    enabled: true,
+                    weight: None,
+                });
+                format!("added {url} as an image prompt")
+            });
+            self.toasts.result(result, "error uploading image");
+        }
+    }
+    /// Add the themes from a text file that aren't already in the list,
+    /// disabled, returning how many were added
+    fn import_themes(&mut self, path: &Path) -> Result<usize, String> {
+        let themes = &mut self.params.themes;
+        let mut added = 0;
+        for theme in export::read_list(path)? {
+            if !themes
+                .iter()
+                .any(|existing| existing.name.trim().eq_ignore_ascii_case(&theme))
+            {
+                themes.push(Theme::new(theme, false));
+                added += 1;
+            }
+        }
+        Ok(added)
+    }
+    fn visuals(&self) -> Visuals {
+        let mut visuals = if self.dark_mode {
+            Visuals::dark()
+        } else {
+            Visuals::light()
+        };
+        if let Some([r, g, b]) = self.accent_color {
+            let accent = Color32::from_rgb(r, g, b);
+            visuals.selection.bg_fill = accent;
+            visuals.hyperlink_color = accent;
+        }
+        visuals
+    }
+    fn export_settings(&self, path: &Path) -> Result<(), String> {
+        export::write(
+            path,
+            &SharedSettings {
+                params: self.params.clone(),
+                presets: self.presets.clone(),
+                templates: self.templates.clone(),
+            },
+        )
+    }
+    /// Import shared settings, replacing presets with the same names
+    fn import_settings(&mut self, path: &Path) -> Result<(), String> {
+        let settings: SharedSettings = export::read(path)?;
+        self.params = settings.params;
+        self.presets.extend(settings.presets);
+        self.templates.extend(settings.templates);
+        self.save_presets();
+        self.save_templates();
+        Ok(())
+    }
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            text: self.text.clone(),
+            params: self.params.clone(),
+        }
+    }
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.text = snapshot.text;
+        self.params = snapshot.params;
+    }
+    fn undo(&mut self) {
+        let mut snapshot = self.snapshot();
+        if self.undo.undo(&mut snapshot) {
+            self.restore(snapshot);
+        }
+    }
+    fn redo(&mut self) {
+        let mut snapshot = self.snapshot();
+        if self.undo.redo(&mut snapshot) {
+            self.restore(snapshot);
+        }
+    }
+}
+
+impl eframe::App for Prompt {
+    fn on_close_event(&mut self) -> bool {
+        if self.history_due.is_some() {
+            self.record_history();
+        }
+        self.save_all();
+        self.push_now();
+        true
+    }
+    fn update(&mut self, ctx: &Context, frame: &mut eframe::Frame) {
+        ctx.set_visuals(self.visuals());
+        let window = frame.info().window_info;
+        if cfg!(not(target_arch = "wasm32")) && !window.fullscreen {
+            self.window_size = Some(window.size.into());
+            self.window_pos = window.position.map(Into::into);
+        }
+        self.pushing(ctx);
+        if self.pulling(ctx) {
+            return;
+        }
+        let old_command = self.command();
+        let mut copy_shortcut = false;
+        for action in shortcuts::pressed(ctx) {
+            match action {
+                Action::Copy => copy_shortcut = true,
+                Action::NewTheme => self.params.themes.push(Theme::new("", true)),
+                Action::Save => {
+                    self.save_all();
+                    self.toasts.info("saved");
+                }
+                Action::Undo => self.undo(),
+                Action::Redo => self.redo(),
+                Action::ZoomIn => self.ui_scale += 0.25,
+                Action::ZoomOut => self.ui_scale -= 0.25,
+                Action::Preset(i) => {
+                    if let Some((name, params)) = self.presets.iter().nth(i) {
+                        self.params = params.clone();
+                        self.preset_name = name.clone();
+                    }
+                }
+                Action::CopySlot(i) => self.copy_slot(ctx, i),
+                Action::ShowShortcuts => self.show_shortcuts = !self.show_shortcuts,
+            }
+        }
+        self.ui_scale = self.ui_scale.clamp(MIN_UI_SCALE, MAX_UI_SCALE);
+        if ctx.pixels_per_point() != self.ui_scale {
+            ctx.set_pixels_per_point(self.ui_scale);
+        }
+        let before = self.snapshot();
+        let dropped = ctx.input().raw.dropped_files.clone();
+        for file in dropped {
+            let result = self.import_image(ctx, &file);
+            self.toasts.result(result, "error importing image");
+        }
+        self.poll_uploads();
+        if self.compact {
+            CentralPanel::default().show(ctx, |ui| self.compact_ui(ui, frame));
+            self.finish_update(ctx, before, &old_command, copy_shortcut);
+            return;
+        }
+        // The command stays visible while the parameters scroll
+        TopBottomPanel::bottom("command")
+            .resizable(true)
+            .show(ctx, |ui| {
+                ScrollArea::vertical()
+                    .id_source("command")
+                    .auto_shrink([false, true])
+                    .show(ui, |ui| self.command_panel_ui(ui));
+            });
+        if let Some(flag) = self.jump_to_flag.take() {
+            ctx.data().insert_temp(jump_id(), flag);
+        }
+        TopBottomPanel::top("project").show(ctx, |ui| self.project_ui(ui));
+        if !self.load_errors.is_empty() {
+            TopBottomPanel::top("load errors").show(ctx, |ui| self.load_errors_ui(ui));
+        }
+        if !self.sync_conflicts.is_empty() {
+            TopBottomPanel::top("sync conflicts").show(ctx, |ui| self.sync_conflicts_ui(ui));
+        }
+        if !self.errors.is_empty() {
+            TopBottomPanel::top("errors").show(ctx, |ui| self.errors_ui(ui));
+        }
+        if let Some(error) = self.clipboard.error() {
+            TopBottomPanel::top("clipboard error").show(ctx, |ui| {
+                ui.colored_label(
+                    Color32::RED,
+                    format!(
+                        "the clipboard is unavailable: {error}\n\
+                        copied commands will be printed to stdout instead, \
+                        or another clipboard can be chosen in the settings"
+                    ),
+                );
+            });
+        }
+        if self.watch_clipboard {
+            self.watch_clipboard(ctx);
+        }
+        if self.clipboard_offer.is_some() {
+            TopBottomPanel::top("clipboard offer").show(ctx, |ui| self.clipboard_offer_ui(ui));
+        }
+        if let Some(tab) = self.library_tab {
+            SidePanel::right("library")
+                .resizable(true)
+                .default_width(250.0)
+                .show(ctx, |ui| self.library_ui(ui, tab));
+        }
+        if self.show_shortcuts {
+            let mut open = true;
+            Window::new("shortcuts")
+                .open(&mut open)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    Grid::new("shortcuts").striped(true).show(ui, |ui| {
+                        let shortcuts = shortcuts::SHORTCUTS.iter().chain(&shortcuts::SLOTS);
+                        for shortcut in shortcuts.chain([&shortcuts::HELP]) {
+                            ui.monospace(shortcut.keys);
+                            ui.label(shortcut.description);
+                            ui.end_row();
+                        }
+                    });
+                });
+            self.show_shortcuts = open;
+        }
+        if self.show_compare {
+            let mut open = true;
+            Window::new("compare presets")
+                .open(&mut open)
+                .resizable(true)
+                .show(ctx, |ui| self.compare_ui(ui));
+            self.show_compare = open;
+        }
+        if self.show_share {
+            let mut open = true;
+            Window::new("share preset")
+                .open(&mut open)
+                .resizable(false)
+                .show(ctx, |ui| self.share_ui(ui));
+            self.show_share = open;
+        }
+        if self.show_qr {
+            let mut open = true;
+            Window::new("QR code")
+                .open(&mut open)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    qr::show(ui, &self.copied_command());
+                    ui.label("scan with your phone to paste it into Discord there");
+                });
+            self.show_qr = open;
+        }
+        if self.show_diff {
+            TopBottomPanel::bottom("diff")
+                .resizable(true)
+                .show(ctx, |ui| self.diff_ui(ui));
+        }
+        CentralPanel::default().show(ctx, |ui| {
+            // Settings
+            CollapsingHeader::new("settings").show(ui, |ui| {
+                Grid::new("settings").show(ui, |ui| {
+                    ui.label("theme");
+                    ui.horizontal(|ui| {
+                        ui.selectable_value(&mut self.dark_mode, true, "dark");
+                        ui.selectable_value(&mut self.dark_mode, false, "light");
+                        let mut custom_accent = self.accent_color.is_some();
+                        ui.checkbox(&mut custom_accent, "accent");
+                        match (custom_accent, &mut self.accent_color) {
+                            (true, Some(color)) => {
+                                ui.color_edit_button_srgb(color);
+                            }
+                            (true, None) => {
+                                let [r, g, b, _] = ui.visuals().selection.bg_fill.to_array();
+                                self.accent_color = Some([r, g, b]);
+                            }
+                            (false, _) => self.accent_color = None,
+                        }
+                    });
+                    ui.end_row();
+                    ui.label("ui scale");
+                    ui.horizontal(|ui| {
+                        Slider::new(&mut self.ui_scale, MIN_UI_SCALE..=MAX_UI_SCALE)
+                            .step_by(0.05)
+                            .ui(ui)
+                            .on_hover_text("ctrl+= and ctrl+- also zoom");
+                        if ui.button("reset").clicked() {
+                            self.ui_scale = default_ui_scale();
+                        }
+                    });
+                    ui.end_row();
+                    let cot_hover_text = "copy command to clipboard when changed";
+                    ui.label("copy on change").on_hover_text(cot_hover_text);
+                    ui.checkbox(&mut self.copy_on_change, "")
+                        .on_hover_text(cot_hover_text);
+                    ui.end_row();
+                    let blc_hover_text = "don't copy commands longer than Midjourney accepts";
+                    ui.label("block long commands")
+                        .on_hover_text(blc_hover_text);
+                    ui.checkbox(&mut self.block_long_copies, "")
+                        .on_hover_text(blc_hover_text);
+                    ui.end_row();
+                    toggle_list_row_ui(ui, "banned words", &mut self.banned_words);
+                    ui.label("log copies");
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut self.log_copies, "")
+                            .on_hover_text("write every copied command to copied.jsonl");
+                        if ui.button("open log").clicked() {
+                            platform::open_path(&Self::log_path());
+                        }
+                    });
+                    ui.end_row();
+                    ui.label("copy format");
+                    ComboBox::from_id_source("copy format")
+                        .selected_text(self.copy_format.str())
+                        .show_ui(ui, |ui| {
+                            for format in CopyFormat::ALL {
+                                ui.selectable_value(&mut self.copy_format, format, format.str());
+                            }
+                        });
+                    ui.end_row();
+                    ui.label("plan")
+                        .on_hover_text("the Midjourney plan that costs are estimated for");
+                    ComboBox::from_id_source("plan")
+                        .selected_text(self.plan.str())
+                        .show_ui(ui, |ui| {
+                            for plan in Plan::ALL {
+                                ui.selectable_value(&mut self.plan, plan, plan.str());
+                            }
+                        });
+                    ui.end_row();
+                    ui.label("normalize copies");
+                    ui.checkbox(&mut self.normalize_copies, "").on_hover_text(
+                        "strip emoji, turn smart quotes and dashes into plain ones, \
+                        and collapse repeated spaces and commas",
+                    );
+                    ui.end_row();
+                    if cfg!(not(target_arch = "wasm32")) {
+                        ui.label("show thumbnails");
+                        ui.checkbox(&mut self.show_thumbnails, "")
+                            .on_hover_text("download images from their urls to preview them");
+                        ui.end_row();
+                        let wc_hover_text = "offer to import /imagine commands copied elsewhere";
+                        ui.label("watch clipboard").on_hover_text(wc_hover_text);
+                        ui.checkbox(&mut self.watch_clipboard, "")
+                            .on_hover_text(wc_hover_text);
+                        ui.end_row();
+                    }
+                    ui.label("export/import");
+                    ui.horizontal(|ui| {
+                        TextEdit::singleline(&mut self.export_path)
+                            .hint_text("settings.json")
+                            .desired_width(150.0)
+                            .ui(ui);
+                        let path = PathBuf::from(self.export_path.trim());
+                        let enabled = !self.export_path.trim().is_empty();
+                        if ui.add_enabled(enabled, Button::new("export")).clicked() {
+                            let result = self
+                                .export_settings(&path)
+                                .map(|()| format!("exported to {}", path.display()));
+                            self.toasts.result(result, "error exporting");
+                        }
+                        if ui.add_enabled(enabled, Button::new("import")).clicked() {
+                            let result = self
+                                .import_settings(&path)
+                                .map(|()| format!("imported from {}", path.display()));
+                            self.toasts.result(result, "error importing");
+                        }
+                    });
+                    ui.end_row();
+                    ui.label("import themes");
+                    ui.horizontal(|ui| {
+                        TextEdit::singleline(&mut self.themes_path)
+                            .hint_text("themes.txt")
+                            .desired_width(150.0)
+                            .ui(ui)
+                            .on_hover_text("one theme per line or separated by commas");
+                        let path = PathBuf::from(self.themes_path.trim());
+                        let enabled = !self.themes_path.trim().is_empty();
+                        if ui.add_enabled(enabled, Button::new("import")).clicked() {
+                            let result = self
+                                .import_themes(&path)
+                                .map(|added| format!("added {added} themes"));
+                            self.toasts.result(result, "error importing");
+                        }
+                    });
+                    ui.end_row();
+                    ui.label("discord webhook");
+                    TextEdit::singleline(&mut self.discord_webhook)
+                        .hint_text("https://discord.com/api/webhooks/...")
+                        .password(true)
+                        .ui(ui);
+                    ui.end_row();
+                    ui.label("discord channel");
+                    ui.horizontal(|ui| {
+                        TextEdit::singleline(&mut self.discord_guild)
+                            .hint_text("server id")
+                            .desired_width(150.0)
+                            .ui(ui);
+                        TextEdit::singleline(&mut self.discord_channel)
+                            .hint_text("channel id")
+                            .desired_width(150.0)
+                            .ui(ui);
+                        ui.checkbox(&mut self.discord_link_app, "open in app")
+                            .on_hover_text(
+                                "open the channel in the Discord app instead of the browser",
+                            );
+                    });
+                    ui.end_row();
+                    ui.label("translation");
+                    ui.horizontal(|ui| {
+                        let translation = &mut self.translation;
+                        ComboBox::from_id_source("translation backend")
+                            .selected_text(translation.backend.str())
+                            .show_ui(ui, |ui| {
+                                for backend in translate::Backend::ALL {
+                                    ui.selectable_value(
+                                        &mut translation.backend,
+                                        backend,
+                                        backend.str(),
+                                    );
+                                }
+                            });
+                        if translation.backend == translate::Backend::LibreTranslate {
+                            TextEdit::singleline(&mut translation.url)
+                                .hint_text("server url")
+                                .desired_width(150.0)
+                                .ui(ui);
+                        }
+                        TextEdit::singleline(&mut translation.api_key)
+                            .hint_text("api key")
+                            .password(true)
+                            .desired_width(100.0)
+                            .ui(ui);
+                    });
+                    ui.end_row();
+                    if cfg!(not(target_arch = "wasm32")) {
+                        ui.label("image uploads")
+                            .on_hover_text("where dropped images are uploaded for image prompts");
+                        ui.horizontal(|ui| self.upload_settings_ui(ui));
+                        ui.end_row();
+                        ui.label("sync").on_hover_text(
+                            "keep presets and other data in a shared folder or a Git repo",
+                        );
+                        ui.horizontal(|ui| self.sync_settings_ui(ui));
+                        ui.end_row();
+                        ui.label("clipboard").on_hover_text(
+                            "how commands are copied, for when the system clipboard doesn't work",
+                        );
+                        let backend = self.clipboard_backend;
+                        ComboBox::from_id_source("clipboard backend")
+                            .selected_text(backend.str())
+                            .show_ui(ui, |ui| {
+                                for backend in platform::ClipboardBackend::ALL {
+                                    ui.selectable_value(
+                                        &mut self.clipboard_backend,
+                                        backend,
+                                        backend.str(),
+                                    );
+                                }
+                            });
+                        if self.clipboard_backend != backend {
+                            self.clipboard = platform::Clipboard::new(self.clipboard_backend);
+                        }
+                        ui.end_row();
+                    }
+                    #[cfg(feature = "llm")]
+                    {
+                        ui.label("LLM API key");
+                        TextEdit::singleline(&mut self.llm.api_key)
+                            .password(true)
+                            .ui(ui)
+                            .on_hover_text("used to ask a language model for suggestions");
+                        ui.end_row();
+                        ui.label("LLM model");
+                        ui.horizontal(|ui| {
+                            TextEdit::singleline(&mut self.llm.model)
+                                .hint_text("model")
+                                .desired_width(100.0)
+                                .ui(ui);
+                            TextEdit::singleline(&mut self.llm.url)
+                                .hint_text("chat completions url")
+                                .desired_width(200.0)
+                                .ui(ui)
+                                .on_hover_text("any OpenAI-compatible API works");
+                        });
+                        ui.end_row();
+                    }
+                    ui.label("commands file");
+                    TextEdit::singleline(&mut self.commands_path)
+                        .hint_text("commands.txt or commands.csv")
+                        .ui(ui)
+                        .on_hover_text("where exported commands are written");
+                    ui.end_row();
+                    ui.label("data folder");
+                    ui.horizontal(|ui| {
+                        let dir = Self::dir();
+                        ui.label(dir.display().to_string());
+                        if ui.button("open").clicked() {
+                            platform::open_path(&dir);
+                        }
+                    });
+                    ui.end_row();
+                });
+            });
+            // Presets
+            ui.horizontal(|ui| {
+                self.presets_ui(ui);
+                if ui
+                    .selectable_label(self.library_tab.is_some(), "library")
+                    .on_hover_text("presets, favorites, history, snippets, and stats")
+                    .clicked()
+                {
+                    self.library_tab = match self.library_tab {
+                        Some(_) => None,
+                        None => Some(LibraryTab::Presets),
+                    };
+                }
+                if ui
+                    .selectable_label(self.show_diff, "diff")
+                    .on_hover_text("compare with the last copied command")
+                    .clicked()
+                {
+                    self.show_diff = !self.show_diff;
+                }
+                if ui
+                    .button("compact")
+                    .on_hover_text(
+                        "show only the prompt in a small window\n\
+                        it stays on top of other windows after a restart",
+                    )
+                    .clicked()
+                {
+                    self.set_compact(frame, true);
+                }
+                if ui
+                    .selectable_label(self.show_shortcuts, "⌨")
+                    .on_hover_text("keyboard shortcuts (f1)")
+                    .clicked()
+                {
+                    self.show_shortcuts = !self.show_shortcuts;
+                }
+            });
+            ui.separator();
+            ScrollArea::both()
+                .auto_shrink([false, true])
+                .show(ui, |ui| {
+                    // Prompt
+                    ui.horizontal(|ui| {
+                        ui.label("prompt");
+                        if ui.button("paste command").clicked() {
+                            match self.clipboard.paste() {
+                                Ok(command) => self.import_command(&command),
+                                Err(e) => {
+                                    self.parse_errors =
+                                        vec![format!("error reading clipboard: {e}")];
+                                }
+                            }
+                        }
+                        if ui
+                            .button("surprise me")
+                            .on_hover_text("make a random prompt from the snippets")
+                            .clicked()
+                        {
+                            self.text =
+                                snippets::random_prompt(&self.snippets, &self.surprise_template);
+                        }
+                        self.translate_ui(ui);
+                    });
+                    for error in &self.parse_errors {
+                        ui.colored_label(Color32::RED, error);
+                    }
+                    let candidates = self.completions.get(&self.params, &self.history);
+                    let output = completing_text_edit(ui, &mut self.text, candidates);
+                    synonyms_menu(&output, &mut self.text);
+                    self.variables_ui(ui);
+                    CollapsingHeader::new("notes")
+                        .default_open(!self.params.notes.is_empty())
+                        .show(ui, |ui| {
+                            TextEdit::multiline(&mut self.params.notes)
+                                .hint_text("what worked, job ids, ...")
+                                .ui(ui);
+                        });
+                    CollapsingHeader::new("camera")
+                        .show(ui, |ui| self.camera_ui(ui))
+                        .header_response
+                        .on_hover_text("photography terms for the photorealistic models");
+                    CollapsingHeader::new("colors").show(ui, |ui| self.palette_ui(ui));
+                    let banned =
+                        banned_words(&self.command(), &self.params.prefix, &self.banned_words);
+                    if !banned.is_empty() {
+                        ui.colored_label(
+                            Color32::RED,
+                            format!("likely to be rejected for: {}", banned.join(", ")),
+                        );
+                    }
+                    let text = self.filled_text();
+                    let duplicates = duplicate_themes(&self.params, &text).join(", ");
+                    if !duplicates.is_empty() {
+                        ui.horizontal(|ui| {
+                            ui.colored_label(
+                                Color32::YELLOW,
+                                format!("repeated themes: {duplicates}"),
+                            );
+                            if ui.button("remove duplicates").clicked() {
+                                remove_duplicate_themes(&mut self.params, &text);
+                            }
+                        });
+                    }
+                    // Suggestions
+                    #[cfg(feature = "llm")]
+                    self.suggestions_ui(ui);
+                    // Multi-prompt
+                    self.params.segments_ui(ui);
+                    // Images
+                    let formatter = self.params.profile.formatter();
+                    if formatter.has("images") {
+                        let thumbnails = self.show_thumbnails.then_some(&mut self.thumbnails);
+                        self.params.images_ui(ui, thumbnails);
+                    }
+                    // Basic
+                    self.params.basic_ui(ui, &self.recent_seeds);
+                    // References
+                    if formatter.has("references") {
+                        self.params.references_ui(ui);
+                    }
+                    // Character
+                    self.params.character_ui(ui);
+                    // Permutations
+                    self.permutations_preview_ui(ui);
+                    // Batch
+                    self.batch_ui(ui);
+                    // Discord
+                    self.discord_ui(ui);
+                });
+        });
+        ctx.data().remove::<String>(jump_id());
+        self.finish_update(ctx, before, &old_command, copy_shortcut);
+    }
+}
+
+impl Prompt {
+    /// Copy the command if needed and record changes for undo
+    ///
+    /// This is done after everything has been shown so that changes made this
+    /// frame are included.
+    fn finish_update(
+        &mut self,
+        ctx: &Context,
+        before: Snapshot,
+        old_command: &str,
+        copy_shortcut: bool,
+    ) {
+        if copy_shortcut {
+            self.copy_command(ctx, true);
+        } else if self.copy_on_change && *self.command() != *old_command {
+            self.copy_command(ctx, false);
+        }
+        if let Some(due) = self.history_due {
+            let now = ctx.input().time;
+            if now >= due {
+                self.record_history();
+            } else {
+                ctx.request_repaint_after(Duration::from_secs_f64(due - now));
+            }
+        }
+        if self.snapshot() != before {
+            self.undo.record(before, ctx.input().time);
+            // The web has no close event, so save whenever something changes
+            #[cfg(target_arch = "wasm32")]
+            self.save_all();
+        }
+        self.thumbnails.evict(ctx.input().time);
+        self.toasts.show(ctx);
+    }
+    /// The command the way it is copied
+    fn copied_command(&self) -> String {
+        let command = self.command();
+        let command = if self.normalize_copies {
+            normalize(&command)
+        } else {
+            command.to_string()
+        };
+        self.copy_format.format(&command, &self.params.prefix)
+    }
+    /// Copy the command, recording it in the history right away if it was
+    /// copied on purpose, or once it stops changing if it was copied on change
+    fn copy_command(&mut self, ctx: &Context, explicit: bool) {
+        // Typing into another window on every change would type into whatever
+        // has focus
+        if self.text.trim().is_empty() || !explicit && self.clipboard.types() {
+            return;
+        }
+        if self.copy_blocked() {
+            self.toasts.error("the command is too long to copy");
+            return;
+        }
+        match self.clipboard.copy(ctx, self.copied_command()) {
+            Ok(()) => {
+                self.remember_seed();
+                if explicit {
+                    self.record_history();
+                } else {
+                    self.history_due = Some(ctx.input().time + HISTORY_SETTLE_SECS);
+                }
+                self.toasts.info(format!("copied {}", self.copy_format));
+            }
+            Err(e) => self.toasts.error(format!("error copying command: {e}")),
+        }
+    }
+    fn set_compact(&mut self, frame: &mut eframe::Frame, compact: bool) {
+        if compact == self.compact {
+            return;
+        }
+        if compact {
+            self.expanded_size = self.window_size;
+            frame.set_window_size(COMPACT_WINDOW_SIZE.into());
+        } else {
+            let size = self.expanded_size.take().unwrap_or(DEFAULT_WINDOW_SIZE);
+            frame.set_window_size(size.into());
+        }
+        self.compact = compact;
+    }
+    /// Show just the prompt text and a copy button
+    fn compact_ui(&mut self, ui: &mut Ui, frame: &mut eframe::Frame) {
+        ui.horizontal(|ui| {
+            if ui.button("⛶").on_hover_text("show everything").clicked() {
+                self.set_compact(frame, false);
+            }
+            let can_copy = !self.text.trim().is_empty() && !self.copy_blocked();
+            if ui.add_enabled(can_copy, Button::new("copy")).clicked() {
+                self.copy_command(ui.ctx(), true);
+            }
+            TextEdit::singleline(&mut self.text)
+                .hint_text("prompt")
+                .desired_width(f32::INFINITY)
+                .ui(ui);
+        });
+    }
+    /// Show the command along with the buttons for what to do with it
+    fn command_panel_ui(&mut self, ui: &mut Ui) {
+        self.command_preview_ui(ui);
+        ui.horizontal(|ui| {
+            let can_copy = !self.text.trim().is_empty() && !self.copy_blocked();
+            if !self.copy_on_change && ui.add_enabled(can_copy, Button::new("copy")).clicked() {
+                self.copy_command(ui.ctx(), true);
+            }
+            if ui
+                .add_enabled(!self.text.trim().is_empty(), Button::new("☆ star"))
+                .clicked()
+            {
+                self.star();
+            }
+            if ui
+                .add_enabled(
+                    !self.text.trim().is_empty() && !self.commands_path.trim().is_empty(),
+                    Button::new("export"),
+                )
+                .on_hover_text("add the command to the commands file")
+                .clicked()
+            {
+                let path = PathBuf::from(self.commands_path.trim());
+                let text = self.filled_text();
+                let row = export::CommandRow {
+                    text: &text,
+                    params: &self.params,
+                    command: self.command().to_string(),
+                };
+                let result = export::append_commands(&path, &[row])
+                    .map(|()| format!("exported to {}", path.display()));
+                self.toasts.result(result, "error exporting");
+            }
+            if ui
+                .add_enabled(
+                    !self.text.trim().is_empty(),
+                    SelectableLabel::new(self.show_qr, "QR"),
+                )
+                .on_hover_text("show the command as a QR code")
+                .clicked()
+            {
+                self.show_qr = !self.show_qr;
+            }
+            ui.menu_button("other tools", |ui| self.other_tools_ui(ui));
+        });
+        self.slots_ui(ui);
+    }
+    fn slots_ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label("slots");
+            for (i, name) in SLOT_NAMES.into_iter().enumerate() {
+                let stored = &self.slots[i];
+                let hover_text = if stored.is_empty() { "empty" } else { stored };
+                if ui
+                    .add_enabled(!stored.is_empty(), Button::new(format!("copy {name}")))
+                    .on_hover_text(hover_text)
+                    .clicked()
+                {
+                    self.copy_slot(ui.ctx(), i);
+                }
+                if ui
+                    .add_enabled(!self.text.trim().is_empty(), Button::new("⬇").small())
+                    .on_hover_text(format!("put the command in slot {name}"))
+                    .clicked()
+                {
+                    self.slots[i] = self.copied_command();
+                }
+            }
+        });
+    }
+    fn copy_slot(&mut self, ctx: &Context, i: usize) {
+        let Some(command) = self.slots.get(i).filter(|command| !command.is_empty()) else {
+            return;
+        };
+        if self.clipboard.types() {
+            self.toasts
+                .error("slots can't be copied while the clipboard types into windows");
+            return;
+        }
+        let result = (self.clipboard.copy(ctx, command.clone()))
+            .map(|()| format!("copied slot {}", SLOT_NAMES[i]));
+        self.toasts.result(result, "error copying");
+    }
+    /// Show the ways to convert the prompt for tools other than Midjourney
+    fn other_tools_ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label("size");
+            for size in sd::BASE_SIZES {
+                ui.selectable_value(&mut self.params.sd_size, size, size.to_string());
+            }
+        })
+        .response
+        .on_hover_text("the size of a square image, which other aspect ratios keep the area of");
+        let sd_prompt = SdPrompt::new(&self.params, &self.filled_text(), self.params.sd_size);
+        if ui
+            .button("copy for AUTOMATIC1111")
+            .on_hover_text("paste into the prompt box and click the button below Generate")
+            .clicked()
+        {
+            let result = self
+                .clipboard
+                .copy(ui.ctx(), sd_prompt.automatic1111())
+                .map(|()| "copied for AUTOMATIC1111".into());
+            self.toasts.result(result, "error copying");
+            ui.close_menu();
+        }
+        ui.separator();
+        TextEdit::singleline(&mut self.comfyui_checkpoint)
+            .hint_text("checkpoint")
+            .ui(ui)
+            .on_hover_text("the checkpoint file name in ComfyUI's models folder");
+        TextEdit::singleline(&mut self.comfyui_path)
+            .hint_text("workflow.json")
+            .ui(ui)
+            .on_hover_text("where the ComfyUI workflow is saved");
+        if ui
+            .add_enabled(
+                !self.comfyui_checkpoint.trim().is_empty() && !self.comfyui_path.trim().is_empty(),
+                Button::new("save ComfyUI workflow"),
+            )
+            .on_hover_text("load the file in ComfyUI to generate the prompt there")
+            .clicked()
+        {
+            let path = PathBuf::from(self.comfyui_path.trim());
+            let result =
+                export::write_comfyui_workflow(&path, &sd_prompt, &self.comfyui_checkpoint)
+                    .map(|()| format!("saved workflow to {}", path.display()));
+            self.toasts.result(result, "error saving workflow");
+            ui.close_menu();
+        }
+    }
+    /// Show the button for translating the prompt text, if a translation
+    /// service is set up
+    fn translate_ui(&mut self, ui: &mut Ui) {
+        if let Some(result) = self.translate_task.as_ref().and_then(task::Task::poll) {
+            self.translate_task = None;
+            match result {
+                Ok(text) => self.text = text,
+                Err(e) => self.toasts.error(format!("error translating: {e}")),
+            }
+        }
+        if !self.translation.is_configured() {
+            return;
+        }
+        let translating = self.translate_task.is_some();
+        if ui
+            .add_enabled(
+                !translating && !self.text.trim().is_empty(),
+                Button::new("translate to English"),
+            )
+            .on_hover_text("Midjourney understands English best")
+            .clicked()
+        {
+            let settings = self.translation.clone();
+            let text = self.text.clone();
+            self.translate_task = Some(task::Task::spawn(ui.ctx(), move || {
+                translate::to_english(&settings, &text)
+            }));
+        }
+        if translating {
+            ui.spinner();
+        }
+    }
+    /// Show the buttons for asking a language model about the prompt, and
+    /// the suggestions it gave, which can be clicked to use them
+    #[cfg(feature = "llm")]
+    fn suggestions_ui(&mut self, ui: &mut Ui) {
+        if let Err(e) = self.llm.poll() {
+            self.toasts.error(format!("error getting suggestions: {e}"));
+        }
+        CollapsingHeader::new("suggestions").show(ui, |ui| {
+            if self.llm.api_key.trim().is_empty() {
+                ui.weak("add an LLM API key in the settings");
+                return;
+            }
+            ui.horizontal(|ui| {
+                let enabled = !self.llm.is_busy() && !self.text.trim().is_empty();
+                for request in llm::Request::ALL {
+                    if ui
+                        .add_enabled(enabled, Button::new(request.str()))
+                        .clicked()
+                    {
+                        self.llm.ask(ui.ctx(), request, &self.filled_text());
+                    }
+                }
+                if self.llm.is_busy() {
+                    ui.spinner();
+                }
+            });
+            let keywords = self.llm.request == Some(llm::Request::Keywords);
+            let hover = if keywords {
+                "click to add as a theme"
+            } else {
+                "click to use as the prompt"
+            };
+            let mut used = None;
+            for (i, suggestion) in self.llm.suggestions.iter().enumerate() {
+                if ui
+                    .add(Label::new(suggestion).sense(Sense::click()))
+                    .on_hover_text(hover)
+                    .clicked()
+                {
+                    used = Some(i);
+                }
+            }
+            if let Some(i) = used {
+                let suggestion = self.llm.suggestions.remove(i);
+                if keywords {
+                    self.params.themes.push(Theme::new(suggestion, true));
+                } else {
+                    self.text = suggestion;
+                }
+            }
+        });
+    }
+    /// Show the command as it currently is, with each part colored
+    fn command_preview_ui(&mut self, ui: &mut Ui) {
+        let command = self.command();
+        let length = prompt_length(&command, &self.params.prefix);
+        Frame::group(ui.style()).show(ui, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                ui.spacing_mut().item_spacing.x = 0.0;
+                let text = self.filled_text();
+                for span in highlight(&command, &text, &self.params.prefix) {
+                    let text = RichText::new(span.text);
+                    let text = match span.kind {
+                        SpanKind::Prefix => text.weak(),
+                        SpanKind::Images => text.color(Color32::LIGHT_BLUE),
+                        SpanKind::Text => text.strong(),
+                        SpanKind::Suffixes => text.color(Color32::LIGHT_GREEN),
+                        SpanKind::Flag => text.color(Color32::GOLD),
+                    };
+                    match span.flag() {
+                        Some(flag) => {
+                            if ui
+                                .add(Label::new(text).sense(Sense::click()))
+                                .on_hover_text("jump to this setting")
+                                .clicked()
+                            {
+                                self.jump_to_flag = Some(flag.into());
+                                ui.ctx().request_repaint();
+                            }
+                        }
+                        None => {
+                            ui.label(text);
+                        }
+                    }
+                }
+            });
+        });
+        let counter = format!("{length}/{MAX_PROMPT_LENGTH} characters");
+        if length > MAX_PROMPT_LENGTH {
+            ui.colored_label(
+                Color32::RED,
+                format!("{counter}, which is too long for Midjourney"),
+            );
+        } else {
+            ui.weak(counter);
+        }
+        if self.params.profile.formatter().has("quality") {
+            self.estimate_ui(ui);
+        }
+        for warning in lint(&self.params) {
+            ui.colored_label(Color32::YELLOW, format!("⚠ {warning}"));
+        }
+    }
+    /// Show the GPU time and cost the command will use
+    fn estimate_ui(&self, ui: &mut Ui) {
+        let estimate = self.params.estimate(&self.filled_text());
+        let jobs = if estimate.jobs == 1 {
+            String::new()
+        } else {
+            format!("{} jobs, ", estimate.jobs)
+        };
+        let text = if estimate.fast_minutes > 0.0 {
+            format!(
+                "{jobs}≈ {:.1} fast GPU minutes, worth ≈ ${:.2} on the {} plan",
+                estimate.fast_minutes,
+                estimate.dollars(self.plan),
+                self.plan
+            )
+        } else {
+            format!(
+                "{jobs}≈ {:.1} relaxed GPU minutes, which use no fast time",
+                estimate.relaxed_minutes
+            )
+        };
+        let response = if estimate.fast_minutes > HEAVY_FAST_MINUTES {
+            ui.colored_label(Color32::YELLOW, text)
+        } else {
+            ui.weak(text)
+        };
+        response.on_hover_text("a rough estimate, from the quality, speed, version, and repeats");
+    }
+    /// Whether the command is too long to copy
+    fn copy_blocked(&self) -> bool {
+        self.block_long_copies
+            && prompt_length(&self.command(), &self.params.prefix) > MAX_PROMPT_LENGTH
+    }
+    /// Check the clipboard for a newly copied command
+    fn watch_clipboard(&mut self, ctx: &Context) {
+        ctx.request_repaint_after(Duration::from_secs(1));
+        let now = ctx.input().time;
+        if self
+            .clipboard_polled
+            .is_some_and(|polled| now - polled < 1.0)
+        {
+            return;
+        }
+        self.clipboard_polled = Some(now);
+        let contents = match self.clipboard.paste() {
+            Ok(contents) => contents,
+            Err(_) => return,
+        };
+        if self.clipboard_seen.as_ref() == Some(&contents) {
+            return;
+        }
+        let first = self.clipboard_seen.is_none();
+        self.clipboard_seen = Some(contents.clone());
+        // Don't offer whatever was already there or what was copied from here
+        if first || contents.trim() == &*self.command() {
+            return;
+        }
+        let prefix = self.params.prefix.trim();
+        let contents_start = contents.trim_start();
+        if contents_start.starts_with("/imagine prompt:")
+            || !prefix.is_empty() && contents_start.starts_with(prefix)
+        {
+            self.clipboard_offer = Some(contents);
+        }
+    }
+    fn clipboard_offer_ui(&mut self, ui: &mut Ui) {
+        let command = match self.clipboard_offer.clone() {
+            Some(command) => command,
+            None => return,
+        };
+        ui.horizontal(|ui| {
+            ui.label("a command was copied to the clipboard");
+            if ui.button("import").clicked() {
+                self.import_command(&command);
+                self.clipboard_offer = None;
+            }
+            if ui.button("dismiss").clicked() {
+                self.clipboard_offer = None;
+            }
+        });
+        ui.label(RichText::new(command.trim()).weak());
+    }
+    fn load_errors_ui(&mut self, ui: &mut Ui) {
+        let mut confirmed = None;
+        for (i, error) in self.load_errors.iter().enumerate() {
+            let name = error.path.file_name().unwrap_or_default().to_string_lossy();
+            ui.colored_label(
+                Color32::RED,
+                format!("failed to load {name}: {}", error.message),
+            );
+            ui.horizontal(|ui| {
+                ui.label(if error.recovered {
+                    "loaded the newest valid backup instead"
+                } else {
+                    "using defaults instead"
+                });
+                if ui.button("open folder").clicked() {
+                    platform::open_path(&Prompt::dir());
+                }
+                if ui
+                    .button("allow overwrite")
+                    .on_hover_text("save over the broken file from now on")
+                    .clicked()
+                {
+                    confirmed = Some(i);
+                }
+            });
+        }
+        if let Some(i) = confirmed {
+            self.load_errors.remove(i);
+        }
+    }
+    fn sync_conflicts_ui(&mut self, ui: &mut Ui) {
+        let mut resolved = None;
+        for file in &self.sync_conflicts {
+            ui.horizontal(|ui| {
+                ui.colored_label(
+                    Color32::YELLOW,
+                    format!("{file} was changed here and in the sync folder"),
+                );
+                if ui
+                    .button("use synced")
+                    .on_hover_text("replace the file here and reload")
+                    .clicked()
+                {
+                    resolved = Some((file.clone(), true));
+                }
+                if ui
+                    .button("keep this one")
+                    .on_hover_text("replace the synced file on the next save")
+                    .clicked()
+                {
+                    resolved = Some((file.clone(), false));
+                }
+            });
+        }
+        let Some((file, use_synced)) = resolved else {
+            return;
+        };
+        let result = self.sync.resolve(&Self::dir(), &file, use_synced);
+        self.sync_conflicts.retain(|other| *other != file);
+        self.report_result(self.save_file(Self::sync_path(), &self.sync));
+        match result {
+            Ok(()) if use_synced => self.reload(),
+            Ok(()) => {}
+            Err(e) => self.report(e),
+        }
+    }
+    fn project_ui(&mut self, ui: &mut Ui) {
+        if Self::cli_path().is_some() {
+            ui.horizontal(|ui| {
+                ui.label("project");
+                ui.weak("projects are off while a prompt file is open from the command line");
+            });
+            return;
+        }
+        let current = self.projects.current.clone();
+        let mut open = None;
+        let mut forget = false;
+        ui.horizontal(|ui| {
+            ui.label("project");
+            ComboBox::from_id_source("project")
+                .selected_text(current.as_deref().unwrap_or("default"))
+                .show_ui(ui, |ui| {
+                    if ui.selectable_label(current.is_none(), "default").clicked() {
+                        open = Some(None);
+                    }
+                    for name in &self.projects.names {
+                        let selected = current.as_ref() == Some(name);
+                        if ui.selectable_label(selected, name).clicked() {
+                            open = Some(Some(name.clone()));
+                        }
+                    }
+                });
+            if current.is_some() {
+                forget = ui
+                    .button("forget")
+                    .on_hover_text("remove the project from the list, keeping its files")
+                    .clicked();
+            }
+            TextEdit::singleline(&mut self.new_project)
+                .hint_text("new project")
+                .desired_width(120.0)
+                .show(ui);
+            let enabled = !self.new_project.trim().is_empty();
+            if ui.add_enabled(enabled, Button::new("create")).clicked() {
+                self.create_project();
+            }
+        });
+        if let Some(name) = current.as_deref().filter(|_| forget) {
+            self.forget_project(name);
+        } else if let Some(name) = open.filter(|name| *name != current) {
+            self.open_project(name);
+        }
+    }
+    fn errors_ui(&mut self, ui: &mut Ui) {
+        self.errors.retain(|error| {
+            ui.horizontal(|ui| {
+                ui.colored_label(Color32::RED, error);
+                !ui.button("dismiss").clicked()
+            })
+            .inner
+        });
+    }
+    fn library_ui(&mut self, ui: &mut Ui, tab: LibraryTab) {
+        ui.horizontal_wrapped(|ui| {
+            for tab in LibraryTab::ALL {
+                ui.selectable_value(&mut self.library_tab, Some(tab), tab.str());
+            }
+        });
+        ui.separator();
+        match tab {
+            LibraryTab::Presets => self.preset_browser_ui(ui),
+            LibraryTab::Favorites => self.favorites_ui(ui),
+            LibraryTab::History => self.history_ui(ui),
+            LibraryTab::Snippets => self.snippets_ui(ui),
+            LibraryTab::Artists => self.artists_ui(ui),
+            LibraryTab::Stats => self.stats_ui(ui),
+            LibraryTab::Messages => toast::history_ui(ui, &mut self.toasts),
+        }
+    }
+    fn preset_browser_ui(&mut self, ui: &mut Ui) {
+        if self.presets.is_empty() {
+            ui.label("no presets have been saved yet");
+            return;
+        }
+        let mut load = None;
+        let mut removed = None;
+        ScrollArea::vertical()
+            .id_source("preset browser")
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for (name, params) in &self.presets {
+                    ui.horizontal(|ui| {
+                        ui.strong(name);
+                        if ui.button("load").clicked() {
+                            load = Some(name.clone());
+                        }
+                        if ui.button("delete").clicked() {
+                            removed = Some(name.clone());
+                        }
+                    });
+                    let command = params.command("");
+                    let flags = command.find(" --").map_or("", |i| &command[i + 1..]);
+                    ui.horizontal_wrapped(|ui| ui.weak(flags));
+                    ui.separator();
+                }
+            });
+        if let Some(name) = load {
+            self.params = self.presets[&name].clone();
+            self.preset_name = name;
+        }
+        if let Some(name) = removed {
+            self.presets.remove(&name);
+            self.save_presets();
+        }
+    }
+    fn favorites_ui(&mut self, ui: &mut Ui) {
+        TextEdit::singleline(&mut self.favorites_search)
+            .hint_text("search")
+            .ui(ui);
+        ui.separator();
+        let mut restore = None;
+        let mut removed = None;
+        ScrollArea::vertical()
+            .id_source("favorites")
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for (i, favorite) in self.favorites.iter_mut().enumerate() {
+                    if !favorite.matches(&self.favorites_search) {
+                        continue;
+                    }
+                    TextEdit::singleline(&mut favorite.name)
+                        .hint_text("name")
+                        .ui(ui);
+                    TextEdit::singleline(&mut favorite.tags)
+                        .hint_text("tags")
+                        .ui(ui);
+                    ui.horizontal_wrapped(|ui| ui.label(&favorite.command));
+                    ui.horizontal(|ui| {
+                        if ui.button("restore").clicked() {
+                            restore = Some(i);
+                        }
+                        if ui.button("remove").clicked() {
+                            removed = Some(i);
+                        }
+                    });
+                    ui.separator();
+                }
+            });
+        if let Some(i) = restore {
+            let favorite = &self.favorites[i];
+            self.text = favorite.text.clone();
+            self.params = favorite.params.clone();
+        }
+        if let Some(i) = removed {
+            self.favorites.remove(i);
+            self.save_favorites();
+        }
+    }
+    /// Show word counts and the terms and themes used most in the history
+    fn stats_ui(&mut self, ui: &mut Ui) {
+        const TOP: usize = 10;
+        let text = self.filled_text();
+        ui.label(format!(
+            "this prompt: {} words, {} characters",
+            stats::word_count(&text),
+            prompt_length(&self.command(), &self.params.prefix)
+        ));
+        if self.history.is_empty() {
+            ui.label("nothing has been copied yet");
+            return;
+        }
+        let total_words: usize = self
+            .history
+            .iter()
+            .map(|entry| stats::word_count(&entry.text))
+            .sum();
+        ui.label(format!(
+            "{} copied commands, {:.1} words on average",
+            self.history.len(),
+            total_words as f32 / self.history.len() as f32
+        ));
+        ScrollArea::vertical()
+            .id_source("stats")
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                let texts = self.history.iter().map(|entry| entry.text.as_str());
+                let terms = stats::top_terms(texts, TOP);
+                let params = self.history.iter().map(|entry| &entry.params);
+                let themes = stats::top_themes(params, TOP);
+                for (heading, counts) in [
+                    (
+                        "most used terms",
+                        terms.iter().map(|(t, n)| (t.as_str(), *n)).collect(),
+                    ),
+                    ("most enabled themes", themes),
+                ] {
+                    ui.separator();
+                    ui.strong(heading);
+                    if counts.is_empty() {
+                        ui.weak("none");
+                    }
+                    Grid::new(heading).show(ui, |ui| {
+                        for (name, count) in counts {
+                            ui.label(name);
+                            ui.label(count.to_string());
+                            ui.end_row();
+                        }
+                    });
+                }
+            });
+    }
+    /// Show what changed since the last copied command
+    fn diff_ui(&mut self, ui: &mut Ui) {
+        ui.heading("changes since last copy");
+        let Some(last) = self.history.last() else {
+            ui.label("nothing has been copied yet");
+            return;
+        };
+        let command = self.command();
+        ScrollArea::vertical()
+            .id_source("diff")
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    for change in diff_words(&last.command, &command) {
+                        match change {
+                            Change::Same(word) => ui.label(word),
+                            Change::Added(word) => {
+                                ui.colored_label(Color32::GREEN, RichText::new(word).strong())
+                            }
+                            Change::Removed(word) => {
+                                ui.colored_label(Color32::RED, RichText::new(word).strikethrough())
+                            }
+                        };
+                    }
+                });
+            });
+    }
+    fn snippets_ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            TextEdit::singleline(&mut self.snippets_path)
+                .hint_text("snippets.yaml")
+                .desired_width(150.0)
+                .ui(ui);
+            let path = PathBuf::from(self.snippets_path.trim());
+            let enabled = !self.snippets_path.trim().is_empty();
+            if ui.add_enabled(enabled, Button::new("export")).clicked() {
+                let result = export::write(&path, &self.snippets)
+                    .map(|()| format!("exported to {}", path.display()));
+                self.toasts.result(result, "error exporting");
+            }
+            if ui.add_enabled(enabled, Button::new("import")).clicked() {
+                let result = export::read(&path).map(|library| {
+                    snippets::merge(&mut self.snippets, library);
+                    format!("imported from {}", path.display())
+                });
+                self.toasts.result(result, "error importing");
+            }
+        });
+        ui.label("random prompt template");
+        TextEdit::singleline(&mut self.surprise_template)
+            .hint_text("{subjects}, {lighting}")
+            .ui(ui)
+            .on_hover_text("each {category} is replaced with a random snippet from it");
+        ui.separator();
+        let mut added = None;
+        ScrollArea::vertical()
+            .id_source("snippets")
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for (category, snippets) in &self.snippets {
+                    CollapsingHeader::new(category).show(ui, |ui| {
+                        ui.horizontal_wrapped(|ui| {
+                            for snippet in snippets {
+                                if ui.button(snippet).on_hover_text("add as a theme").clicked() {
+                                    added = Some(snippet.clone());
+                                }
+                            }
+                        });
+                    });
+                }
+            });
+        if let Some(snippet) = added {
+            self.enable_theme(snippet);
+        }
+    }
+    fn artists_ui(&mut self, ui: &mut Ui) {
+        let filter = &mut self.artist_filter;
+        TextEdit::singleline(&mut filter.search)
+            .hint_text("search")
+            .ui(ui);
+        let tag_combo =
+            |ui: &mut Ui, name: &str, tags: Vec<&str>, selected: &mut Option<String>| {
+                ComboBox::from_label(name)
+                    .selected_text(selected.as_deref().unwrap_or("any"))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(selected, None, "any");
+                        for tag in tags {
+                            ui.selectable_value(selected, Some(tag.into()), tag);
+                        }
+                    });
+            };
+        ui.horizontal(|ui| {
+            let mediums = artists::tags(&self.artists, |artist| &artist.medium);
+            tag_combo(ui, "medium", mediums, &mut filter.medium);
+            let eras = artists::tags(&self.artists, |artist| &artist.era);
+            tag_combo(ui, "era", eras, &mut filter.era);
+        });
+        ui.weak(format!(
+            "add more to {} with a name, medium, and era for each",
+            Self::artists_path().display()
+        ));
+        ui.separator();
+        let mut added = None;
+        ScrollArea::vertical()
+            .id_source("artists")
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for artist in self.artists.iter().filter(|artist| filter.matches(artist)) {
+                    ui.horizontal(|ui| {
+                        if ui
+                            .button(&artist.name)
+                            .on_hover_text("add as a theme")
+                            .clicked()
+                        {
+                            added = Some(artist.suffix());
+                        }
+                        ui.weak(format!("{}, {}", artist.medium, artist.era));
+                    });
+                }
+            });
+        if let Some(suffix) = added {
+            self.enable_theme(suffix);
+        }
+    }
+    /// Show the shot, lens, and film choices and a button to add their terms
+    fn camera_ui(&mut self, ui: &mut Ui) {
+        fn combo<T: Clone + PartialEq>(
+            ui: &mut Ui,
+            name: &str,
+            options: &[T],
+            selected: &mut Option<T>,
+            text: impl Fn(&T) -> String,
+        ) {
+            ComboBox::from_label(name)
+                .selected_text(selected.as_ref().map_or("none".into(), &text))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(selected, None, "none");
+                    for option in options {
+                        ui.selectable_value(selected, Some(option.clone()), text(option));
+                    }
+                });
+        }
+        let camera = &mut self.camera;
+        let shots: Vec<String> = camera::SHOTS.iter().map(|&s| s.into()).collect();
+        combo(ui, "shot", &shots, &mut camera.shot, Clone::clone);
+        combo(
+            ui,
+            "focal length",
+            camera::FOCAL_LENGTHS,
+            &mut camera.focal_length,
+            |&mm| camera::lens(mm),
+        );
+        combo(
+            ui,
+            "aperture",
+            camera::APERTURES,
+            &mut camera.aperture,
+            |&f| camera::f_number(f),
+        );
+        let films: Vec<String> = camera::FILM_STOCKS.iter().map(|&s| s.into()).collect();
+        combo(ui, "film", &films, &mut camera.film, Clone::clone);
+        let terms = camera.terms();
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(!terms.is_empty(), Button::new("add"))
+                .on_hover_text("add each term as a theme")
+                .clicked()
+            {
+                for term in terms {
+                    self.enable_theme(term);
+                }
+            }
+            if ui.button("clear").clicked() {
+                self.camera = Camera::default();
+            }
+        });
+    }
+    /// Show the color picker and the saved palettes
+    fn palette_ui(&mut self, ui: &mut Ui) {
+        ui.horizontal_wrapped(|ui| {
+            let mut removed = None;
+            for (i, color) in self.palette.colors.iter_mut().enumerate() {
+                if ui
+                    .color_edit_button_srgb(color)
+                    .on_hover_text("right-click to remove")
+                    .secondary_clicked()
+                {
+                    removed = Some(i);
+                }
+            }
+            if let Some(i) = removed {
+                self.palette.colors.remove(i);
+            }
+            if ui.button("+").clicked() {
+                let last = self.palette.colors.last().copied();
+                self.palette.colors.push(last.unwrap_or([128; 3]));
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.color_names, true, "names");
+            ui.radio_value(&mut self.color_names, false, "hex codes");
+        });
+        let terms = self.palette.terms(self.color_names);
+        ui.horizontal_wrapped(|ui| {
+            if ui
+                .add_enabled(!terms.is_empty(), Button::new("add"))
+                .on_hover_text("add each color as a theme")
+                .clicked()
+            {
+                for term in &terms {
+                    self.enable_theme(term.clone());
+                }
+            }
+            ui.weak(terms.join(", "));
+        });
+        ui.horizontal(|ui| {
+            TextEdit::singleline(&mut self.palette.name)
+                .hint_text("palette name")
+                .desired_width(100.0)
+                .ui(ui);
+            let name = self.palette.name.trim();
+            if ui
+                .add_enabled(!name.is_empty(), Button::new("save"))
+                .clicked()
+            {
+                let palette = self.palette.clone();
+                match self.palettes.iter_mut().find(|p| p.name == palette.name) {
+                    Some(saved) => *saved = palette,
+                    None => self.palettes.push(palette),
+                }
+            }
+        });
+        let mut removed = None;
+        for (i, palette) in self.palettes.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.button(&palette.name).on_hover_text("load").clicked() {
+                    self.palette = palette.clone();
+                }
+                for &[r, g, b] in &palette.colors {
+                    let (rect, _) = ui.allocate_exact_size(vec2(12.0, 12.0), Sense::hover());
+                    ui.painter()
+                        .rect_filled(rect, 2.0, Color32::from_rgb(r, g, b));
+                }
+                if ui.small_button("-").clicked() {
+                    removed = Some(i);
+                }
+            });
+        }
+        if let Some(i) = removed {
+            self.palettes.remove(i);
+        }
+    }
+    /// Enable a theme, adding it if it isn't in the list yet
+    fn enable_theme(&mut self, name: String) {
+        let themes = &mut self.params.themes;
+        match themes.iter_mut().find(|theme| theme.name == name) {
+            Some(theme) => theme.enabled = true,
+            None => themes.push(Theme::new(name, true)),
+        }
+    }
+    fn discord_ui(&mut self, ui: &mut Ui) {
+        let link = discord::channel_link(
+            &self.discord_guild,
+            &self.discord_channel,
+            self.discord_link_app,
+        );
+        if self.discord_webhook.trim().is_empty() && link.is_none() {
+            return;
+        }
+        if let Some(result) = self.discord_task.as_ref().and_then(task::Task::poll) {
+            self.discord_task = None;
+            self.toasts.result(result, "error sending to Discord");
+        }
+        ui.horizontal(|ui| {
+            let sending = self.discord_task.is_some();
+            let enabled = !sending && !self.text.trim().is_empty();
+            if !self.discord_webhook.trim().is_empty()
+                && ui
+                    .add_enabled(enabled, Button::new("send to Discord"))
+                    .clicked()
+            {
+                let url = self.discord_webhook.clone();
+                let command = self.command().to_string();
+                self.discord_task = Some(task::Task::spawn(ui.ctx(), move || {
+                    discord::send_webhook(&url, &command).map(|()| "sent to Discord".into())
+                }));
+            }
+            if let Some(link) = link {
+                let can_copy = !self.text.trim().is_empty() && !self.copy_blocked();
+                if ui
+                    .add_enabled(can_copy, Button::new("copy and open channel"))
+                    .on_hover_text("copy the command and open the channel to paste it in")
+                    .clicked()
+                {
+                    self.copy_command(ui.ctx(), true);
+                    ui.ctx().output().open_url(link);
+                }
+            }
+            if sending {
+                ui.spinner();
+            }
+        });
+    }
+    fn star(&mut self) {
+        self.favorites.push(Favorite {
+            name: self.text.trim().into(),
+            tags: String::new(),
+            command: self.command().to_string(),
+            text: self.text.clone(),
+            params: self.params.clone(),
+        });
+        self.save_favorites();
+        self.library_tab = Some(LibraryTab::Favorites);
+    }
+    fn history_ui(&mut self, ui: &mut Ui) {
+        TextEdit::singleline(&mut self.history_search)
+            .hint_text("search commands, job ids, and image urls")
+            .ui(ui);
+        TextEdit::singleline(&mut self.new_jobs)
+            .hint_text("job ids or image urls to track")
+            .ui(ui)
+            .on_hover_text("paste here, then add them to the command that made them");
+        ui.separator();
+        let mut restore = None;
+        let mut add_jobs = None;
+        let mut removed_job = None;
+        let can_add = !self.new_jobs.trim().is_empty();
+        let mut thumbnails = self.show_thumbnails.then_some(&mut self.thumbnails);
+        ScrollArea::vertical()
+            .id_source("history")
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                let entries = self.history.iter().enumerate().rev();
+                for (i, entry) in entries.filter(|(_, entry)| entry.matches(&self.history_search)) {
+                    ui.horizontal(|ui| {
+                        ui.label(entry.time.format("%Y-%m-%d %H:%M").to_string());
+                        if ui.button("restore").clicked() {
+                            restore = Some(i);
+                        }
+                        if ui
+                            .add_enabled(can_add, Button::new("add jobs"))
+                            .on_hover_text("track the pasted job ids or image urls")
+                            .clicked()
+                        {
+                            add_jobs = Some(i);
+                        }
+                    });
+                    ui.horizontal_wrapped(|ui| ui.label(&entry.command));
+                    for (j, job) in entry.jobs.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            if ui.small_button("-").clicked() {
+                                removed_job = Some((i, j));
+                            }
+                            if is_url(job) {
+                                if let Some(thumbnails) = &mut thumbnails {
+                                    thumbnails.show(ui, job, 64.0);
+                                }
+                                ui.hyperlink(job);
+                            } else {
+                                ui.label(job);
+                            }
+                        });
+                    }
+                    ui.separator();
+                }
+            });
+        if let Some(i) = restore {
+            let entry = &self.history[i];
+            self.text = entry.text.clone();
+            self.params = entry.params.clone();
+        }
+        if let Some(i) = add_jobs {
+            let jobs = &mut self.history[i].jobs;
+            for job in self.new_jobs.split_whitespace() {
+                if !jobs.iter().any(|existing| existing == job) {
+                    jobs.push(job.into());
+                }
+            }
+            self.new_jobs.clear();
+        }
+        if let Some((i, j)) = removed_job {
+            self.history[i].jobs.remove(j);
+        }
+        if add_jobs.is_some() || removed_job.is_some() {
+            self.report_result(self.save_file(Self::history_path(), &self.history));
+        }
+    }
+    fn presets_ui(&mut self, ui: &mut Ui) {
+        ui.label("preset");
+        ComboBox::from_id_source("presets")
+            .selected_text("load")
+            .show_ui(ui, |ui| {
+                for (name, params) in &self.presets {
+                    if ui
+                        .selectable_label(*name == self.preset_name, name)
+                        .clicked()
+                    {
+                        self.params = params.clone();
+                        self.preset_name = name.clone();
+                    }
+                }
+            });
+        TextEdit::singleline(&mut self.preset_name)
+            .hint_text("name")
+            .desired_width(100.0)
+            .ui(ui);
+        let name = self.preset_name.trim().to_string();
+        if ui
+            .add_enabled(!name.is_empty(), Button::new("save"))
+            .clicked()
+        {
+            self.presets.insert(name.clone(), self.params.clone());
+            self.save_presets();
+        }
+        if ui
+            .add_enabled(self.presets.contains_key(&name), Button::new("delete"))
+            .clicked()
+        {
+            self.presets.remove(&name);
+            self.save_presets();
+        }
+        if ui
+            .selectable_label(self.show_compare, "compare")
+            .on_hover_text("compare two presets side by side")
+            .clicked()
+        {
+            self.show_compare = !self.show_compare;
+        }
+        if ui
+            .selectable_label(self.show_share, "share")
+            .on_hover_text("share a preset as a file or a string")
+            .clicked()
+        {
+            self.show_share = !self.show_share;
+        }
+    }
+    /// Export the current preset or import someone else's
+    fn share_ui(&mut self, ui: &mut Ui) {
+        let name = self.preset_name.trim().to_string();
+        let path = PathBuf::from(self.share_path.trim());
+        let has_path = !self.share_path.trim().is_empty();
+        let bundle = (self.presets.get(&name)).map(|params| bundle::Bundle {
+            name: name.clone(),
+            params: params.clone(),
+        });
+        match &bundle {
+            Some(_) => ui.label(format!("sharing {name}")),
+            None => ui.label("save or load a preset to share it"),
+        };
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(bundle.is_some(), Button::new("copy string"))
+                .on_hover_text("copy the preset as text that can be pasted anywhere")
+                .clicked()
+            {
+                if let Some(bundle) = &bundle {
+                    let ctx = ui.ctx().clone();
+                    let result = (bundle.encode())
+                        .and_then(|encoded| self.clipboard.copy(&ctx, encoded))
+                        .map(|()| format!("copied {name}"));
+                    self.toasts.result(result, "error copying the preset");
+                }
+            }
+            TextEdit::singleline(&mut self.share_path)
+                .hint_text("preset.yaml")
+                .desired_width(150.0)
+                .ui(ui);
+            let enabled = bundle.is_some() && has_path;
+            if ui.add_enabled(enabled, Button::new("save file")).clicked() {
+                if let Some(bundle) = &bundle {
+                    let result = export::write(&path, bundle)
+                        .map(|()| format!("saved {name} to {}", path.display()));
+                    self.toasts.result(result, "error saving the preset");
+                }
+            }
+        });
+        ui.separator();
+        TextEdit::multiline(&mut self.share_text)
+            .hint_text("paste a preset string")
+            .desired_rows(2)
+            .desired_width(f32::INFINITY)
+            .ui(ui);
+        let mut imported = None;
+        ui.horizontal(|ui| {
+            let enabled = !self.share_text.trim().is_empty();
+            if ui
+                .add_enabled(enabled, Button::new("import string"))
+                .clicked()
+            {
+                imported = Some(bundle::Bundle::decode(&self.share_text));
+            }
+            if ui
+                .add_enabled(has_path, Button::new("import file"))
+                .clicked()
+            {
+                imported = Some(export::read(&path));
+            }
+        });
+        let Some(imported) = imported else {
+            return;
+        };
+        let result = imported.map(|bundle| {
+            let name = bundle.import(&mut self.presets);
+            self.share_text.clear();
+            format!("imported the preset as {name}")
+        });
+        if result.is_ok() {
+            self.save_presets();
+        }
+        self.toasts.result(result, "error importing the preset");
+    }
+    /// Show two presets next to each other with the parts that differ
+    /// highlighted
+    fn compare_ui(&mut self, ui: &mut Ui) {
+        let text = self.filled_text();
+        let mut parts = Vec::new();
+        ui.horizontal(|ui| {
+            for (i, name) in self.compare.iter_mut().enumerate() {
+                ComboBox::from_id_source(("compare", i))
+                    .selected_text(name.as_str())
+                    .show_ui(ui, |ui| {
+                        for preset in self.presets.keys() {
+                            ui.selectable_value(name, preset.clone(), preset);
+                        }
+                    });
+                parts.push(
+                    self.presets
+                        .get(name)
+                        .map(|params| command_parts(&params.command(&text), &text, &params.prefix)),
+                );
+            }
+        });
+        let [Some(a), Some(b)] = [&parts[0], &parts[1]] else {
+            ui.label("choose two presets to compare");
+            return;
+        };
+        let mut names: Vec<&str> = a.iter().map(|(name, _)| name.as_str()).collect();
+        for (name, _) in b {
+            if !names.contains(&name.as_str()) {
+                names.push(name);
+            }
+        }
+        let value = |parts: &[(String, String)], name: &str| {
+            parts
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, value)| value.clone())
+        };
+        ui.separator();
+        Grid::new("compare").striped(true).show(ui, |ui| {
+            ui.label("");
+            ui.strong(&self.compare[0]);
+            ui.strong(&self.compare[1]);
+            ui.end_row();
+            for name in names {
+                let [a, b] = [value(a, name), value(b, name)];
+                let differs = a != b;
+                let cell = |ui: &mut Ui, value: Option<String>| {
+                    let text = RichText::new(value.unwrap_or_else(|| "-".into()));
+                    if differs {
+                        ui.colored_label(Color32::YELLOW, text.strong());
+                    } else {
+                        ui.label(text);
+                    }
+                };
+                ui.label(name);
+                cell(ui, a);
+                cell(ui, b);
+                ui.end_row();
+            }
+            ui.label("");
+            for i in 0..2 {
+                if ui.button("load").clicked() {
+                    if let Some(params) = self.presets.get(&self.compare[i]) {
+                        self.params = params.clone();
+                        self.preset_name = self.compare[i].clone();
+                    }
+                }
+            }
+            ui.end_row();
+        });
+    }
+    fn variables_ui(&mut self, ui: &mut Ui) {
+        let names = template::variables(&self.text);
+        if !names.is_empty() {
+            Grid::new("variables").show(ui, |ui| {
+                for name in names {
+                    ui.label(name);
+                    let value = self.variables.entry(name.into()).or_default();
+                    TextEdit::singleline(value).hint_text(name).ui(ui);
+                    ui.end_row();
+                }
+            });
+        }
+        ui.horizontal(|ui| {
+            ui.label("template");
+            ComboBox::from_id_source("templates")
+                .selected_text("load")
+                .show_ui(ui, |ui| {
+                    for (name, text) in &self.templates {
+                        if ui
+                            .selectable_label(*name == self.template_name, name)
+                            .clicked()
+                        {
+                            self.text = text.clone();
+                            self.template_name = name.clone();
+                        }
+                    }
+                });
+            TextEdit::singleline(&mut self.template_name)
+                .hint_text("name")
+                .desired_width(100.0)
+                .ui(ui);
+            let name = self.template_name.trim().to_string();
+            if ui
+                .add_enabled(!name.is_empty(), Button::new("save"))
+                .on_hover_text("save the prompt text, with {variable}s, as a template")
+                .clicked()
+            {
+                self.templates.insert(name.clone(), self.text.clone());
+                self.save_templates();
+            }
+            if ui
+                .add_enabled(self.templates.contains_key(&name), Button::new("delete"))
+                .clicked()
+            {
+                self.templates.remove(&name);
+                self.save_templates();
+            }
+        });
+    }
+    fn permutations_preview_ui(&self, ui: &mut Ui) {
+        let command = self.command();
+        let count = permute::count(&command);
+        if count <= 1 {
+            return;
+        }
+        CollapsingHeader::new(format!("{count} prompts"))
+            .id_source("permutations preview")
+            .show(ui, |ui| {
+                if count > MAX_PERMUTATION_JOBS {
+                    ui.colored_label(
+                        Color32::YELLOW,
+                        format!("Midjourney allows at most {MAX_PERMUTATION_JOBS} jobs"),
+                    );
+                }
+                for expanded in permute::expand(&command, MAX_PERMUTATION_JOBS) {
+                    ui.label(expanded);
+                }
+                if count > MAX_PERMUTATION_JOBS {
+                    ui.label(format!("and {} more", count - MAX_PERMUTATION_JOBS));
+                }
+            });
+    }
+    /// A command with the current parameters for each batch subject
+    fn batch_commands(&self) -> Vec<String> {
+        self.batch_subjects()
+            .map(|subject| self.params.output(subject))
+            .collect()
+    }
+    fn batch_subjects(&self) -> impl Iterator<Item = &str> {
+        self.batch_subjects
+            .lines()
+            .filter(|subject| !subject.trim().is_empty())
+    }
+    fn batch_ui(&mut self, ui: &mut Ui) {
+        CollapsingHeader::new("batch").show(ui, |ui| {
+            TextEdit::multiline(&mut self.batch_subjects)
+                .hint_text("one subject per line")
+                .ui(ui);
+            let commands = self.batch_commands();
+            ui.horizontal(|ui| {
+                let enabled = !commands.is_empty();
+                if ui.add_enabled(enabled, Button::new("copy all")).clicked() {
+                    let result = self
+                        .clipboard
+                        .copy(ui.ctx(), commands.join("\n"))
+                        .map(|()| format!("copied {} commands", commands.len()));
+                    self.toasts.result(result, "error copying commands");
+                }
+                let path = PathBuf::from(self.commands_path.trim());
+                if ui
+                    .add_enabled(
+                        enabled && !self.commands_path.trim().is_empty(),
+                        Button::new("export"),
+                    )
+                    .on_hover_text("add the commands to the commands file")
+                    .clicked()
+                {
+                    let rows: Vec<_> = self
+                        .batch_subjects()
+                        .zip(&commands)
+                        .map(|(text, command)| export::CommandRow {
+                            text,
+                            params: &self.params,
+                            command: command.clone(),
+                        })
+                        .collect();
+                    let result = export::append_commands(&path, &rows)
+                        .map(|()| format!("exported to {}", path.display()));
+                    self.toasts.result(result, "error exporting");
+                }
+            });
+            for command in &commands {
+                ui.horizontal_wrapped(|ui| ui.label(command));
+            }
+        });
+    }
+    fn remember_seed(&mut self) {
+        if !self.params.use_seed {
+            return;
+        }
+        let seed = self.params.seed;
+        self.recent_seeds.retain(|&recent| recent != seed);
+        self.recent_seeds.insert(0, seed);
+        self.recent_seeds.truncate(MAX_RECENT_SEEDS);
+    }
+}
+
+trait ParamsUi {
+    fn basic_ui(&mut self, ui: &mut Ui, recent_seeds: &[u32]);
+    fn theme_groups_row_ui(&mut self, ui: &mut Ui);
+    fn theme_weights_row_ui(&mut self, ui: &mut Ui);
+    fn segments_ui(&mut self, ui: &mut Ui);
+    fn images_ui(&mut self, ui: &mut Ui, thumbnails: Option<&mut Thumbnails>);
+    fn references_ui(&mut self, ui: &mut Ui);
+    fn character_ui(&mut self, ui: &mut Ui);
+}
+
+impl ParamsUi for Params {
+    fn basic_ui(&mut self, ui: &mut Ui, recent_seeds: &[u32]) {
+        let formatter = self.profile.formatter();
+        Grid::new("basic").show(ui, |ui| {
+            // Profile
+            ui.label("profile")
+                .on_hover_text("the image generator the prompt is written for");
+            ComboBox::from_id_source("profile")
+                .selected_text(self.profile.str())
+                .width(100.0)
+                .show_ui(ui, |ui| {
+                    for profile in Profile::ALL {
+                        if ui
+                            .selectable_label(self.profile == profile, profile.str())
+                            .clicked()
+                        {
+                            self.set_profile(profile);
+                        }
+                    }
+                });
+            ui.end_row();
+
+            // Prefix
+            if formatter.has("prefix") {
+                ui.label("prefix")
+                    .on_hover_text("what the command starts with, for bots other than Midjourney");
+                ui.horizontal(|ui| {
+                    TextEdit::singleline(&mut self.prefix)
+                        .hint_text("none")
+                        .desired_width(150.0)
+                        .ui(ui);
+                    if self.prefix != default_prefix() && ui.button("reset").clicked() {
+                        self.prefix = default_prefix();
+                    }
+                });
+                ui.end_row();
+            }
+            if formatter.has("separator") {
+                ui.label("separator")
+                    .on_hover_text("what the style, themes, and other suffixes are joined with");
+                ui.horizontal(|ui| {
+                    TextEdit::singleline(&mut self.separator)
+                        .desired_width(150.0)
+                        .ui(ui);
+                    if self.separator != default_separator() && ui.button("reset").clicked() {
+                        self.separator = default_separator();
+                    }
+                });
+                ui.end_row();
+            }
+            if formatter.has("flag order") {
+                ui.label("flag order");
+                ui.collapsing("flags", |ui| flag_order_ui(ui, &mut self.flag_order));
+                ui.end_row();
+            }
+
+            // Algorithm
+            let algorithms = formatter.algorithms();
+            if !algorithms.is_empty() {
+                param_label(ui, "algorithm", self.algorithm);
+                ComboBox::from_id_source("algorithm")
+                    .selected_text(self.algorithm.str())
+                    .width(100.0)
+                    .show_ui(ui, |ui| {
+                        for algo in algorithms {
+                            if ui
+                                .selectable_label(self.algorithm == algo, algo.str())
+                                .clicked()
+                            {
+                                self.set_algorithm(algo);
+                            }
+                        }
+                    });
+                ui.end_row();
+            }
+
+            // Image size
+            if formatter.has("image size") {
+                ui.label("image size").on_hover_text(
+                    "the size of a square image, which other aspect ratios keep the area of",
+                );
+                ui.horizontal(|ui| {
+                    for size in sd::BASE_SIZES {
+                        ui.selectable_value(&mut self.sd_size, size, size.to_string());
+                    }
+                });
+                ui.end_row();
+            }
+
+            // Model style
+            let allowed_styles = self.algorithm.allowed_styles();
+            if formatter.has("model style") && !allowed_styles.is_empty() {
+                param_label(ui, "model style", self.algorithm);
+                ComboBox::from_id_source("model style")
+                    .selected_text(self.model_style.map_or("none", |style| style.str()))
+                    .width(100.0)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.model_style, None, "none");
+                        for style in allowed_styles {
+                            ui.selectable_value(&mut self.model_style, Some(*style), style.str());
+                        }
+                    });
+                ui.end_row();
+            }
+
+            if formatter.has("aspect") {
+                // Aspect
+                param_label(ui, "aspect", self.algorithm);
+                let custom = self.custom_aspects.iter().find(|(name, [w, h])| {
+                    !name.trim().is_empty() && Aspect::from_wh(*w, *h) == self.aspect
+                });
+                let selected_text = match custom {
+                    Some((name, [w, h])) => format!("{} {w}:{h}", name.trim()),
+                    None => self.aspect.aspect_string(),
+                };
+                ComboBox::from_id_source("aspect")
+                    .selected_text(selected_text)
+                    .width(100.0)
+                    .show_ui(ui, |ui| {
+                        for aspect in self.algorithm.allowed_aspects() {
+                            ui.selectable_value(&mut self.aspect, *aspect, aspect.aspect_string());
+                        }
+                        for (name, [w, h]) in &self.custom_aspects {
+                            let aspect = Aspect::from_wh(*w, *h);
+                            if self.algorithm.supports_aspect(aspect) {
+                                let text = format!("{} {w}:{h}", name.trim());
+                                ui.selectable_value(&mut self.aspect, aspect, text.trim());
+                            }
+                        }
+                    });
+                aspect_preview_ui(ui, &mut self.aspect, self.algorithm.allowed_aspects());
+                ui.end_row();
+
+                // Custom aspects
+                ui.label("custom aspects");
+                CollapsingHeader::new("edit")
+                    .id_source("custom aspects")
+                    .show(ui, |ui| {
+                        let mut removed = None;
+                        for (i, (name, [w, h])) in self.custom_aspects.iter_mut().enumerate() {
+                            ui.horizontal(|ui| {
+                                TextEdit::singleline(name)
+                                    .hint_text("name")
+                                    .desired_width(100.0)
+                                    .ui(ui);
+                                ui.label(format!("{w}:{h}"));
+                                if ui.button("-").clicked() {
+                                    removed = Some(i);
+                                }
+                            });
+                        }
+                        if let Some(i) = removed {
+                            self.custom_aspects.remove(i);
+                        }
+                        if let Some(wh) = self.aspect.wh() {
+                            if ui.button("+ add current").clicked() {
+                                self.custom_aspects.push((String::new(), wh));
+                            }
+                        }
+                    });
+                ui.end_row();
+
+                // Aspect from pixel dimensions
+                ui.label("from pixels");
+                ui.horizontal(|ui| {
+                    let id = Id::new("aspect pixels");
+                    let mut pixels = ui.data().get_temp(id).unwrap_or([1920, 1080]);
+                    DragValue::new(&mut pixels[0])
+                        .clamp_range(1..=100_000)
+                        .ui(ui);
+                    ui.label("×");
+                    DragValue::new(&mut pixels[1])
+                        .clamp_range(1..=100_000)
+                        .ui(ui);
+                    ComboBox::from_id_source("common sizes")
+                        .selected_text("common")
+                        .show_ui(ui, |ui| {
+                            for (name, size) in COMMON_SIZES {
+                                if ui.selectable_label(pixels == size, name).clicked() {
+                                    pixels = size;
+                                }
+                            }
+                        });
+                    let aspect = Aspect::from_wh(pixels[0], pixels[1]);
+                    let [w, h] = aspect.wh().unwrap_or([1, 1]);
+                    ui.label(format!("= {w}:{h}"));
+                    aspect_shape_ui(ui, [w, h], 20.0);
+                    if ui
+                        .add_enabled(aspect != self.aspect, Button::new("set"))
+                        .clicked()
+                    {
+                        self.aspect = aspect;
+                    }
+                    ui.data().insert_temp(id, pixels);
+                });
+                ui.end_row();
+            }
+
+            if formatter.has("quality") {
+                // Quality
+                param_label(ui, "quality", self.algorithm);
+                ComboBox::from_id_source("quality")
+                    .selected_text(self.quality.str())
+                    .width(100.0)
+                    .show_ui(ui, |ui| {
+                        for quality in Quality::ALL {
+                            ui.selectable_value(&mut self.quality, quality, quality.str());
+                        }
+                    });
+                ui.end_row();
+            }
+
+            if formatter.has("speed") {
+                // Speed
+                param_label(ui, "speed", self.algorithm);
+                ComboBox::from_id_source("speed")
+                    .selected_text(self.speed.map_or("none", |speed| speed.str()))
+                    .width(100.0)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.speed, None, "none");
+                        for speed in Speed::ALL {
+                            ui.selectable_value(&mut self.speed, Some(speed), speed.str());
+                        }
+                    });
+                ui.end_row();
+            }
+
+            // Numbers
+            for (info, number) in info::numbers() {
+                if info.section == Section::Basic && formatter.has(info.name) {
+                    number_row_ui(ui, self, info, number);
+                }
+            }
+
+            if formatter.has("seed") {
+                // Seed
+                param_label(ui, "seed", self.algorithm);
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.use_seed, "");
+                    if self.use_seed {
+                        for mode in [SeedMode::Seed, SeedMode::SameSeed] {
+                            ui.selectable_value(&mut self.seed_mode, mode, mode.str());
+                        }
+                        DragValue::new(&mut self.seed).ui(ui);
+                        if ui.button("randomize").clicked() {
+                            self.seed = rand::random();
+                        }
+                        if !recent_seeds.is_empty() {
+                            ComboBox::from_id_source("recent seeds")
+                                .selected_text("recent")
+                                .show_ui(ui, |ui| {
+                                    for &seed in recent_seeds {
+                                        if ui
+                                            .selectable_label(self.seed == seed, seed.to_string())
+                                            .clicked()
+                                        {
+                                            self.seed = seed;
+                                        }
+                                    }
+                                });
+                        }
+                    }
+                });
+                ui.end_row();
+            }
+
+            // Personalization
+            if formatter.has("personalize") {
+                self.personalization.row_ui(ui, self.algorithm);
+            }
+
+            if formatter.has("video") {
+                // Video
+                param_label(ui, "video", self.algorithm);
+                ui.checkbox(&mut self.video, "");
+                ui.end_row();
+            }
+
+            // Tile
+            if formatter.has("tile") && self.algorithm.supports_tile() {
+                param_label(ui, "tile", self.algorithm);
+                ui.checkbox(&mut self.tile, "");
+                ui.end_row();
+            }
+
+            // Style
+            self.style.row_ui(ui, "style");
+
+            // Color
+            self.color.row_ui(ui, "color");
+
+            // Themes
+            toggle_list_row_ui(ui, "themes", &mut self.themes);
+
+            // Theme groups
+            self.theme_groups_row_ui(ui);
+
+            // Theme weights
+            self.theme_weights_row_ui(ui);
+
+            // Permutations
+            if formatter.has("permutations") {
+                let permutations = toggle_list_row_ui(ui, "permutations", &mut self.permutations);
+                param_help(permutations, "permutations", self.algorithm);
+            }
+
+            // Negative
+            if formatter.has("exclude") {
+                let exclude = toggle_list_row_ui(ui, "exclude", &mut self.negatives);
+                param_help(exclude, "exclude", self.algorithm);
+            }
+        });
+    }
+    fn theme_groups_row_ui(&mut self, ui: &mut Ui) {
+        ui.label("theme groups");
+        let enabled_groups: Vec<&str> = self
+            .theme_groups
+            .iter()
+            .filter(|group| group.enabled && !group.name.trim().is_empty())
+            .map(|group| group.name.trim())
+            .collect();
+        ui.horizontal_wrapped(|ui| ui.label(enabled_groups.join(", ")));
+        CollapsingHeader::new("edit")
+            .id_source("theme groups")
+            .show(ui, |ui| {
+                for i in 0..self.theme_groups.len() {
+                    let group = &mut self.theme_groups[i];
+                    let id = ui.make_persistent_id(("theme group", i));
+                    let mut removed = false;
+                    collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, false)
+                        .show_header(ui, |ui| {
+                            ui.checkbox(&mut group.enabled, "");
+                            TextEdit::singleline(&mut group.name)
+                                .hint_text("name")
+                                .desired_width(80.0)
+                                .ui(ui);
+                            removed = ui.button("-").clicked();
+                        })
+                        .body(|ui| toggle_list_edit_ui(ui, &mut group.themes));
+                    if removed {
+                        self.theme_groups.remove(i);
+                        break;
+                    }
+                }
+                if ui.button("+").clicked() {
+                    self.theme_groups.push(ThemeGroup {
+                        name: String::new(),
+                        enabled: true,
+                        themes: Vec::new(),
+                    });
+                }
+            });
+        ui.end_row();
+    }
+    fn theme_weights_row_ui(&mut self, ui: &mut Ui) {
+        ui.label("theme weights")
+            .on_hover_text("weighted themes are added as ::weight multi-prompt segments");
+        let weighted: Vec<String> = self
+            .enabled_themes()
+            .filter_map(|(theme, weight)| Some(format!("{theme}::{}", weight?)))
+            .collect();
+        ui.horizontal_wrapped(|ui| ui.label(weighted.join(", ")));
+        let grouped = (self.theme_groups.iter_mut())
+            .filter(|group| group.enabled)
+            .flat_map(|group| &mut group.themes);
+        let mut themes: Vec<&mut Theme> = (self.themes.iter_mut())
+            .chain(grouped)
+            .filter(|theme| theme.enabled && !theme.name.trim().is_empty())
+            .collect();
+        CollapsingHeader::new("edit")
+            .id_source("theme weights")
+            .show(ui, |ui| {
+                if themes.is_empty() {
+                    ui.weak("no themes are enabled");
+                }
+                for theme in &mut themes {
+                    ui.horizontal(|ui| {
+                        let mut is_weighted = theme.weight.is_some();
+                        if ui.checkbox(&mut is_weighted, theme.name.trim()).changed() {
+                            theme.weight = is_weighted.then_some(1.0);
+                        } else if let Some(weight) = &mut theme.weight {
+                            DragValue::new(weight).speed(0.1).ui(ui);
+                        }
+                    });
+                }
+            });
+        ui.end_row();
+    }
+    fn segments_ui(&mut self, ui: &mut Ui) {
+        CollapsingHeader::new("multi-prompt")
+            .id_source("multi-prompt")
+            .show(ui, |ui| {
+                for i in 0..self.segments.len() {
+                    let removed = ui
+                        .horizontal(|ui| {
+                            let segment = &mut self.segments[i];
+                            TextEdit::singleline(&mut segment.text)
+                                .desired_width(200.0)
+                                .ui(ui);
+                            ui.label("::");
+                            DragValue::new(&mut segment.weight).speed(0.1).ui(ui);
+                            ui.button("-").clicked()
+                        })
+                        .inner;
+                    if removed {
+                        self.segments.remove(i);
+                        break;
+                    }
+                }
+                if ui.button("+").clicked() {
+                    self.segments.push(Segment {
+                        text: String::new(),
+                        weight: 1.0,
+                    });
+                }
+            });
+    }
+    fn images_ui(&mut self, ui: &mut Ui, mut thumbnails: Option<&mut Thumbnails>) {
+        let jump = jump_requested(ui.ctx(), &["iw"]);
+        let response = CollapsingHeader::new("images")
+            .id_source("images")
+            .open(jump.then_some(true))
+            .show(ui, |ui| {
+                for i in 0..self.images.len() {
+                    let removed = ui
+                        .horizontal(|ui| {
+                            let image = &mut self.images[i];
+                            let url_response = TextEdit::singleline(&mut image.url)
+                                .hint_text("https://")
+                                .desired_width(200.0)
+                                .ui(ui);
+                            ui.checkbox(&mut image.enabled, "");
+                            let mut weighted = image.weight.is_some();
+                            if ui.checkbox(&mut weighted, "weight").changed() {
+                                image.weight = weighted.then_some(1.0);
+                            }
+                            if let Some(weight) = &mut image.weight {
+                                DragValue::new(weight)
+                                    .speed(0.05)
+                                    .clamp_range(0.0..=3.0)
+                                    .ui(ui);
+                            }
+                            if is_url(&image.url) {
+                                // Only fetch urls once they are done being typed
+                                if let Some(thumbnails) = &mut thumbnails {
+                                    if !url_response.has_focus() {
+                                        thumbnails.show(ui, &image.url, 32.0);
+                                    }
+                                }
+                            } else if !image.url.trim().is_empty() {
+                                ui.colored_label(Color32::RED, "invalid url");
+                            }
+                            ui.button("-").clicked()
+                        })
+                        .inner;
+                    if removed {
+                        self.images.remove(i);
+                        break;
+                    }
+                }
+                if ui.button("+").clicked() {
+                    self.images.push(ImagePrompt {
+                        url: String::new(),
+                        enabled: true,
+                        weight: None,
+                    });
+                }
+            });
+        if jump {
+            response.header_response.scroll_to_me(Some(Align::Center));
+        }
+    }
+    fn references_ui(&mut self, ui: &mut Ui) {
+        CollapsingHeader::new("references")
+            .id_source("references")
+            .open(jump_requested(ui.ctx(), &["sref", "sw", "cref", "cw"]).then_some(true))
+            .show(ui, |ui| {
+                Grid::new("references").show(ui, |ui| {
+                    // Style
+                    let style_refs = toggle_list_row_ui(ui, "style refs", &mut self.style_refs);
+                    param_help(style_refs, "style refs", self.algorithm);
+                    ui.end_row();
+
+                    // Character
+                    let character_refs =
+                        toggle_list_row_ui(ui, "character refs", &mut self.character_refs);
+                    param_help(character_refs, "character refs", self.algorithm);
+                    ui.end_row();
+
+                    // Weights
+                    for (info, number) in info::numbers() {
+                        if info.section == Section::References {
+                            number_row_ui(ui, self, info, number);
+                        }
+                    }
+                });
+            });
+    }
+    fn character_ui(&mut self, ui: &mut Ui) {
+        CollapsingHeader::new("character")
+            .id_source("character")
+            .show(ui, |ui| {
+                Grid::new("character").show(ui, |ui| {
+                    self.body.row_ui(ui, "body");
+                    self.hair.row_ui(ui, "hair color");
+                    self.pose.row_ui(ui, "pose");
+                });
+            });
+    }
+}
+
+enum ListAction {
+    Up,
+    Down,
+    Remove,
+    /// Enable only this item
+    Solo,
+}
+
+/// Split a command into named parts that can be compared: the text added
+/// after the prompt and each flag's value
+fn command_parts(command: &str, text: &str, prefix: &str) -> Vec<(String, String)> {
+    highlight(command, text, prefix)
+        .into_iter()
+        .filter_map(|span| match span.kind {
+            SpanKind::Suffixes => {
+                let suffixes = span.text.trim().trim_start_matches(',').trim();
+                Some(("suffixes".into(), suffixes.into()))
+            }
+            SpanKind::Flag => {
+                let flag = span.flag()?;
+                let value = span.text.trim().trim_start_matches("--")[flag.len()..].trim();
+                let value = if value.is_empty() { "on" } else { value };
+                Some((format!("--{flag}"), value.into()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Phrases to complete the prompt text with: the choices and themes, then
+/// the parts of prompts in the history, newest first
+///
+/// They are only rebuilt when the lists or the history change.
+#[derive(Default)]
+struct Completions {
+    /// The choices and themes the phrases were built from
+    names: Vec<String>,
+    /// The length of the history and the time of its newest entry when the
+    /// phrases were built
+    newest: Option<(usize, DateTime<Local>)>,
+    phrases: Vec<String>,
+}
+
+impl Completions {
+    fn get(&mut self, params: &Params, history: &[HistoryEntry]) -> &[String] {
+        let choices = [
+            &params.style,
+            &params.color,
+            &params.body,
+            &params.hair,
+            &params.pose,
+        ]
+        .into_iter()
+        .flat_map(|choices| &choices.choices);
+        let themes = (params.themes.iter())
+            .chain(params.theme_groups.iter().flat_map(|group| &group.themes))
+            .map(|theme| &theme.name);
+        let names: Vec<&String> = choices.chain(themes).collect();
+        let newest = history.last().map(|entry| (history.len(), entry.time));
+        if self.newest != newest || !self.names.iter().eq(names.iter().copied()) {
+            self.names = names.into_iter().cloned().collect();
+            self.newest = newest;
+            let history = (history.iter().rev()).flat_map(|entry| entry.text.split([',', '\n']));
+            self.phrases = (self.names.iter().map(String::as_str))
+                .chain(history)
+                .map(Into::into)
+                .collect();
+        }
+        &self.phrases
+    }
+}
+
+/// Show a multiline text edit that suggests the rest of the phrase being
+/// typed, which Tab accepts
+fn completing_text_edit(ui: &mut Ui, text: &mut String, candidates: &[String]) -> TextEditOutput {
+    let id = ui.id().with("completing text edit");
+    // The byte index of the cursor, if nothing is selected
+    let cursor_index = |text: &str, range: CCursorRange| {
+        (range.primary == range.secondary).then(|| {
+            text.char_indices()
+                .nth(range.primary.index)
+                .map_or(text.len(), |(i, _)| i)
+        })
+    };
+    let state = TextEditState::load(ui.ctx(), id).filter(|_| ui.memory().has_focus(id));
+    let pending = state.as_ref().and_then(|state| {
+        let cursor = cursor_index(text, state.ccursor_range()?)?;
+        Some((cursor, autocomplete::completion(text, cursor, candidates)?))
+    });
+    if let (Some(mut state), Some((cursor, rest))) = (state, pending) {
+        if ui.ctx().input_mut().consume_key(Modifiers::NONE, Key::Tab) {
+            text.insert_str(cursor, rest);
+            let end = text[..cursor + rest.len()].chars().count();
+            state.set_ccursor_range(Some(CCursorRange::one(CCursor::new(end))));
+            state.store(ui.ctx(), id);
+        }
+    }
+    // Tab normally moves the focus, so it is locked while there is a
+    // completion to accept
+    let output = TextEdit::multiline(text)
+        .id(id)
+        .lock_focus(pending.is_some())
+        .show(ui);
+    let completion = (output.cursor_range)
+        .filter(|_| output.response.has_focus())
+        .and_then(|range| {
+            let cursor = cursor_index(text, range.as_ccursor_range())?;
+            Some((
+                range.primary,
+                autocomplete::completion(text, cursor, candidates)?,
+            ))
+        });
+    if let Some((cursor, rest)) = completion {
+        let rect =
+            (output.galley.pos_from_cursor(&cursor)).translate(output.text_draw_pos.to_vec2());
+        ui.painter().text(
+            rect.left_top(),
+            Align2::LEFT_TOP,
+            rest,
+            TextStyle::Body.resolve(ui.style()),
+            ui.visuals().weak_text_color(),
+        );
+    }
+    output
+}
+
+/// Offer synonyms for the word that was right-clicked in a text edit
+fn synonyms_menu(output: &TextEditOutput, text: &mut String) {
+    let response = &output.response;
+    let id = response.id.with("synonyms");
+    if response.secondary_clicked() {
+        // The menu stays open as the pointer moves, so the word is found once
+        let word = response.interact_pointer_pos().and_then(|pos| {
+            let cursor = output.galley.cursor_from_pos(pos - output.text_draw_pos);
+            let index = text
+                .char_indices()
+                .nth(cursor.ccursor.index)
+                .map_or(text.len(), |(i, _)| i);
+            thesaurus::word_at(text, index)
+        });
+        response.ctx.data().insert_temp(id, word);
+    }
+    response.clone().context_menu(|ui| {
+        let word: Option<Range<usize>> = ui.data().get_temp(id).flatten();
+        let Some(range) = word.filter(|range| text.get(range.clone()).is_some()) else {
+            ui.weak("right-click a word for synonyms");
+            return;
+        };
+        let synonyms = thesaurus::synonyms(&text[range.clone()]);
+        if synonyms.is_empty() {
+            ui.weak(format!("no synonyms for {}", &text[range]));
+            return;
+        }
+        for synonym in synonyms {
+            if ui.button(&synonym).clicked() {
+                text.replace_range(range.clone(), &synonym);
+                ui.close_menu();
+            }
+        }
+    });
+}
+
+/// Show a grid row for a list of entries that can each be toggled on or off
+fn toggle_list_row_ui<T: ToggleItem>(ui: &mut Ui, name: &str, items: &mut Vec<T>) -> Response {
+    let response = ui.label(name);
+    let mut enabled_items = String::new();
+    for item in &mut *items {
+        let (item, enabled) = item.parts();
+        if *enabled && !item.trim().is_empty() {
+            if !enabled_items.is_empty() {
+                enabled_items.push_str(", ");
+            }
+            enabled_items.push_str(item.trim());
+        }
+    }
+    ui.horizontal_wrapped(|ui| ui.label(enabled_items));
+    CollapsingHeader::new("edit")
+        .id_source(name)
+        .show(ui, |ui| toggle_list_edit_ui(ui, items));
+    ui.end_row();
+    response
+}
+
+/// Pixel sizes of common screens and images
+const COMMON_SIZES: [(&str, [u32; 2]); 6] = [
+    ("1080p", [1920, 1080]),
+    ("1440p", [2560, 1440]),
+    ("4k", [3840, 2160]),
+    ("ultrawide monitor", [3440, 1440]),
+    ("phone", [1080, 2340]),
+    ("instagram portrait", [1080, 1350]),
+];
+
+/// Draw a rectangle with the shape of an aspect ratio that fits in a square
+fn aspect_shape_ui(ui: &mut Ui, [w, h]: [u32; 2], size: f32) -> Response {
+    let scale = size / w.max(h) as f32;
+    let (rect, response) =
+        ui.allocate_exact_size(vec2(w as f32 * scale, h as f32 * scale), Sense::hover());
+    ui.painter()
+        .rect_stroke(rect, 0.0, ui.visuals().widgets.inactive.fg_stroke);
+    response
+}
+
+/// Show the shape of the current aspect ratio, with the outlines of the
+/// presets as guides that can be clicked to select them
+fn aspect_preview_ui(ui: &mut Ui, aspect: &mut Aspect, presets: &[Aspect]) {
+    let size = 60.0;
+    let (rect, response) = ui.allocate_exact_size(Vec2::splat(size), Sense::click());
+    let shape = |aspect: Aspect| {
+        let [w, h] = aspect.wh().unwrap_or([1, 1]);
+        let scale = size / w.max(h) as f32;
+        Rect::from_center_size(rect.center(), vec2(w as f32 * scale, h as f32 * scale))
+    };
+    let hovered = response.hover_pos().and_then(|pos| {
+        presets
+            .iter()
+            .copied()
+            .min_by(|a, b| edge_distance(shape(*a), pos).total_cmp(&edge_distance(shape(*b), pos)))
+    });
+    let visuals = ui.visuals();
+    let painter = ui.painter();
+    painter.rect_filled(shape(*aspect), 0.0, visuals.selection.bg_fill);
+    for &preset in presets {
+        let stroke = if Some(preset) == hovered {
+            visuals.widgets.hovered.fg_stroke
+        } else {
+            visuals.widgets.noninteractive.bg_stroke
+        };
+        painter.rect_stroke(shape(preset), 0.0, stroke);
+    }
+    if let Some(preset) = hovered {
+        if response.clicked() {
+            *aspect = preset;
+        }
+        response.on_hover_text(preset.aspect_string());
+    }
+}
+
+/// The distance from a point to the nearest edge of a rectangle
+fn edge_distance(rect: Rect, pos: Pos2) -> f32 {
+    let dx = (rect.min.x - pos.x).max(pos.x - rect.max.x);
+    let dy = (rect.min.y - pos.y).max(pos.y - rect.max.y);
+    if dx < 0.0 && dy < 0.0 {
+        -dx.max(dy)
+    } else {
+        dx.max(0.0).hypot(dy.max(0.0))
+    }
+}
+
+/// The id under which the flag clicked in the command preview is stored for
+/// a frame
+fn jump_id() -> Id {
+    Id::new("jump to flag")
+}
+
+/// Whether one of `flags` was clicked in the command preview
+fn jump_requested(ctx: &Context, flags: &[&str]) -> bool {
+    ctx.data()
+        .get_temp::<String>(jump_id())
+        .is_some_and(|flag| flags.contains(&flag.as_str()))
+}
+
+/// Show a grid row for a number parameter
+fn number_row_ui(ui: &mut Ui, params: &mut Params, info: &ParamInfo, number: &Number) {
+    param_label(ui, info.name, params.algorithm);
+    let range = (number.range)(&params.algorithm);
+    let default = (number.default)(&params.algorithm);
+    let shown = ui
+        .horizontal(|ui| {
+            let shown = match number.toggle {
+                Some(toggle) => {
+                    let toggle = toggle(params);
+                    ui.checkbox(toggle, "");
+                    *toggle
+                }
+                None => true,
+            };
+            if shown {
+                Slider::new((number.get_mut)(params), range.clone())
+                    .logarithmic(number.logarithmic)
+                    .show_value(false)
+                    .ui(ui);
+            }
+            shown
+        })
+        .inner;
+    ui.horizontal(|ui| {
+        if !shown {
+            return;
+        }
+        let value = (number.get_mut)(params);
+        DragValue::new(value).clamp_range(range).ui(ui);
+        if number.toggle.is_none() && *value != default && ui.button("reset").clicked() {
+            *value = default;
+        }
+    });
+    ui.end_row();
+}
+
+/// Show the label for a parameter, which can be clicked to open its
+/// documentation
+fn param_label(ui: &mut Ui, name: &str, algorithm: Algorithm) -> Response {
+    let response = ui.add(Label::new(name).sense(Sense::click()));
+    param_help(response, name, algorithm)
+}
+
+/// Add help from the parameter metadata to a parameter's label, and scroll
+/// to it when its flag is clicked in the command preview
+fn param_help(response: Response, name: &str, algorithm: Algorithm) -> Response {
+    let Some(info) = info::find(name) else {
+        return response;
+    };
+    jump_target(&response, info.flags);
+    if response.clicked() {
+        response.ctx.output().open_url(info.docs);
+    }
+    response.on_hover_text(format!("{}\n\nmore at {}", info.help(algorithm), info.docs))
+}
+
+/// Scroll to the control for `flags` if one of them was clicked in the
+/// command preview
+fn jump_target(response: &Response, flags: &[&str]) {
+    if jump_requested(&response.ctx, flags) {
+        response.scroll_to_me(Some(Align::Center));
+    }
+}
+
+/// Show the editor for the order flags are written in
+fn flag_order_ui(ui: &mut Ui, flag_order: &mut Vec<String>) {
+    // Start from the full default order so that every flag can be moved, and
+    // add keys that are missing from orders saved by older versions
+    let mut order = flag_order.clone();
+    for key in command::flag_keys() {
+        if !order.iter().any(|ordered| ordered == key) {
+            order.push(key.into());
+        }
+    }
+    let len = order.len();
+    let mut swap = None;
+    for (i, key) in order.iter().enumerate() {
+        ui.horizontal(|ui| {
+            if ui.add_enabled(i > 0, Button::new("▲")).clicked() {
+                swap = Some((i, i - 1));
+            }
+            if ui.add_enabled(i + 1 < len, Button::new("▼")).clicked() {
+                swap = Some((i, i + 1));
+            }
+            ui.label(key);
+        });
+    }
+    if let Some((a, b)) = swap {
+        order.swap(a, b);
+        *flag_order = order;
+    }
+    if !flag_order.is_empty() && ui.button("reset").clicked() {
+        flag_order.clear();
+    }
+}
+
+/// The number of items above which a list gets a filter box
+const MIN_FILTERED_ITEMS: usize = 10;
+
+/// An entry of a list edited with [`toggle_list_edit_ui`]
+trait ToggleItem {
+    /// A new enabled entry
+    fn new(text: String) -> Self;
+    fn parts(&mut self) -> (&mut String, &mut bool);
+}
+
+impl ToggleItem for (String, bool) {
+    fn new(text: String) -> Self {
+        (text, true)
+    }
+    fn parts(&mut self) -> (&mut String, &mut bool) {
+        (&mut self.0, &mut self.1)
+    }
+}
+
+impl ToggleItem for Theme {
+    fn new(text: String) -> Self {
+        Theme::new(text, true)
+    }
+    fn parts(&mut self) -> (&mut String, &mut bool) {
+        (&mut self.name, &mut self.enabled)
+    }
+}
+
+/// Show the editor for a list of entries that can each be toggled on or off
+fn toggle_list_edit_ui<T: ToggleItem>(ui: &mut Ui, items: &mut Vec<T>) {
+    let len = items.len();
+    let filter_id = ui.id().with("filter");
+    let mut filter: String = ui.data().get_temp(filter_id).unwrap_or_default();
+    if len > MIN_FILTERED_ITEMS {
+        TextEdit::singleline(&mut filter)
+            .hint_text("filter")
+            .desired_width(100.0)
+            .ui(ui);
+        ui.data().insert_temp(filter_id, filter.clone());
+    } else {
+        filter.clear();
+    }
+    let filter = filter.trim().to_lowercase();
+    let shown = |item: &str| filter.is_empty() || item.to_lowercase().contains(&filter);
+    for i in 0..len {
+        // Items are filtered in place so that moving them still swaps with
+        // their actual neighbors
+        if !shown(items[i].parts().0) {
+            continue;
+        }
+        let action = ui
+            .horizontal(|ui| {
+                let (item, enabled) = items[i].parts();
+                TextEdit::singleline(item).desired_width(100.0).ui(ui);
+                ui.checkbox(enabled, "");
+                let up = ui.add_enabled(i > 0, Button::new("▲")).clicked();
+                let down = ui.add_enabled(i + 1 < len, Button::new("▼")).clicked();
+                let remove = ui.button("-").clicked();
+                let solo = ui
+                    .small_button("solo")
+                    .on_hover_text("enable only this one")
+                    .clicked();
+                if up {
+                    Some(ListAction::Up)
+                } else if down {
+                    Some(ListAction::Down)
+                } else if remove {
+                    Some(ListAction::Remove)
+                } else if solo {
+                    Some(ListAction::Solo)
+                } else {
+                    None
+                }
+            })
+            .inner;
+        match action {
+            Some(ListAction::Up) => items.swap(i, i - 1),
+            Some(ListAction::Down) => items.swap(i, i + 1),
+            Some(ListAction::Remove) => {
+                items.remove(i);
+            }
+            Some(ListAction::Solo) => {
+                for (j, item) in items.iter_mut().enumerate() {
+                    *item.parts().1 = i == j;
+                }
+            }
+            None => continue,
+        }
+        break;
+    }
+    ui.horizontal(|ui| {
+        if ui.button("+").clicked() {
+            items.push(T::new(String::new()));
+        }
+        // Bulk toggles only affect the items that pass the filter
+        let all = ui.button("all").on_hover_text("enable all").clicked();
+        let none = ui.button("none").on_hover_text("disable all").clicked();
+        if all || none {
+            for item in items.iter_mut() {
+                let (item, enabled) = item.parts();
+                if shown(item) {
+                    *enabled = all;
+                }
+            }
+        }
+    });
+}
+
+trait ChoicesUi {
+    fn row_ui(&mut self, ui: &mut Ui, name: &str);
+}
+
+impl ChoicesUi for Choices {
     fn row_ui(&mut self, ui: &mut Ui, name: &str) {
         ui.label(name);
         ComboBox::from_id_source(name)
@@ -0,0 +1,558 @@
+//! Functionality that differs between the native and web builds
+//!
+//! Natively, data files are real files and the clipboard is accessed
+//! directly. On the web, data files are `localStorage` entries and copying
+//! goes through the browser.
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use native::*;
+#[cfg(target_arch = "wasm32")]
+pub use web::*;
+
+use serde::{Deserialize, Serialize};
+
+/// How the clipboard is accessed natively
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ClipboardBackend {
+    /// The first of the others that works
+    #[default]
+    Auto,
+    System,
+    /// The `arboard` library, which also supports Wayland
+    Arboard,
+    /// `wl-copy` and `wl-paste`, for Wayland
+    WlClipboard,
+    Xclip,
+    /// Typing the text into the focused window with `xdotool` or `wtype`,
+    /// for when no clipboard works
+    ///
+    /// This is never chosen automatically, and only commands that are copied
+    /// on purpose are typed.
+    Type,
+}
+
+impl ClipboardBackend {
+    pub const ALL: [ClipboardBackend; 6] = [
+        ClipboardBackend::Auto,
+        ClipboardBackend::System,
+        ClipboardBackend::Arboard,
+        ClipboardBackend::WlClipboard,
+        ClipboardBackend::Xclip,
+        ClipboardBackend::Type,
+    ];
+    pub fn str(&self) -> &'static str {
+        match self {
+            ClipboardBackend::Auto => "auto",
+            ClipboardBackend::System => "system",
+            ClipboardBackend::Arboard => "arboard",
+            ClipboardBackend::WlClipboard => "wl-clipboard",
+            ClipboardBackend::Xclip => "xclip",
+            ClipboardBackend::Type => "type into window",
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use std::{
+        env, fs,
+        io::{self, Read, Write},
+        path::{Path, PathBuf},
+        process::{self, Command, Stdio},
+        thread,
+        time::{Duration, SystemTime},
+    };
+
+    use clipboard::{ClipboardContext, ClipboardProvider};
+    use eframe::egui::Context;
+
+    use super::ClipboardBackend;
+
+    /// The number of previous versions of each file to keep
+    const BACKUPS: usize = 3;
+    /// How old the newest backup has to be before another is made, so that
+    /// frequent saves don't push out the older versions
+    const BACKUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+    pub fn data_dir() -> Result<PathBuf, String> {
+        dirs::data_local_dir()
+            .map(|dir| dir.join("midjourney_prompt"))
+            .ok_or_else(|| "no local data folder was found".into())
+    }
+
+    fn backup_path(path: &Path, n: usize) -> PathBuf {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(".bak{n}"));
+        path.with_file_name(name)
+    }
+
+    pub fn read(path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    /// Read the previous versions of a file, newest first
+    pub fn read_backups(path: &Path) -> impl Iterator<Item = Vec<u8>> + '_ {
+        (1..=BACKUPS).filter_map(|n| fs::read(backup_path(path, n)).ok())
+    }
+
+    /// Whether the newest backup of a file is old enough to make another
+    fn backup_due(path: &Path) -> bool {
+        fs::metadata(backup_path(path, 1))
+            .and_then(|metadata| metadata.modified())
+            .map_or(true, |modified| {
+                SystemTime::now()
+                    .duration_since(modified)
+                    .map_or(true, |age| age >= BACKUP_INTERVAL)
+            })
+    }
+
+    pub fn write(path: &Path, contents: &str) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        if path.exists() && backup_due(path) {
+            for n in (1..BACKUPS).rev() {
+                let _ = fs::rename(backup_path(path, n), backup_path(path, n + 1));
+            }
+            let _ = fs::copy(path, backup_path(path, 1));
+        }
+        // Write to a temporary file and rename it so that a crash mid-write
+        // can't leave a partial file behind
+        let temp = path.with_extension("yaml.tmp");
+        fs::write(&temp, contents)?;
+        fs::rename(temp, path)
+    }
+
+    /// Write a file that only the user can read, like [`write`] but without
+    /// backups
+    pub fn write_private(path: &Path, contents: &str) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        // Backups made before the file was private may be readable by anyone
+        for n in 1..=BACKUPS {
+            let _ = fs::remove_file(backup_path(path, n));
+        }
+        // A leftover temporary file would keep its old permissions
+        let temp = path.with_extension("yaml.tmp");
+        let _ = fs::remove_file(&temp);
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        options.open(&temp)?.write_all(contents.as_bytes())?;
+        fs::rename(temp, path)
+    }
+
+    /// Open a folder in the system file manager, or a file in its default
+    /// program
+    pub fn open_path(path: &Path) {
+        let program = if cfg!(windows) {
+            "explorer"
+        } else if cfg!(target_os = "macos") {
+            "open"
+        } else {
+            "xdg-open"
+        };
+        let _ = process::Command::new(program).arg(path).spawn();
+    }
+
+    /// Send a JSON request and return the response body
+    pub fn post_json(url: &str, authorization: Option<&str>, body: &str) -> Result<String, String> {
+        let mut headers = vec![("Content-Type", "application/json")];
+        if let Some(authorization) = authorization {
+            headers.push(("Authorization", authorization));
+        }
+        send("POST", url, &headers, body.as_bytes())
+    }
+
+    /// Send a request with any method and headers and return the response
+    /// body
+    pub fn send(
+        method: &str,
+        url: &str,
+        headers: &[(&str, &str)],
+        body: &[u8],
+    ) -> Result<String, String> {
+        let mut request = ureq::request(method, url);
+        for (name, value) in headers {
+            request = request.set(name, value);
+        }
+        request
+            .send_bytes(body)
+            .map_err(|e| e.to_string())?
+            .into_string()
+            .map_err(|e| e.to_string())
+    }
+
+    /// Download the body of a url
+    pub fn get(url: &str) -> Result<Vec<u8>, String> {
+        let mut bytes = Vec::new();
+        ureq::get(url)
+            .call()
+            .map_err(|e| e.to_string())?
+            .into_reader()
+            .read_to_end(&mut bytes)
+            .map_err(|e| e.to_string())?;
+        Ok(bytes)
+    }
+
+    /// The time to switch to another window before text is typed into it
+    const TYPE_DELAY: Duration = Duration::from_secs(2);
+
+    /// A way of getting text to and from the clipboard
+    trait Backend {
+        fn set(&mut self, text: String) -> Result<(), String>;
+        fn get(&mut self) -> Result<String, String>;
+        /// Whether setting the text types it rather than copying it
+        fn types(&self) -> bool {
+            false
+        }
+    }
+
+    impl Backend for ClipboardContext {
+        fn set(&mut self, text: String) -> Result<(), String> {
+            self.set_contents(text).map_err(|e| e.to_string())
+        }
+        fn get(&mut self) -> Result<String, String> {
+            self.get_contents().map_err(|e| e.to_string())
+        }
+    }
+
+    impl Backend for arboard::Clipboard {
+        fn set(&mut self, text: String) -> Result<(), String> {
+            self.set_text(text).map_err(|e| e.to_string())
+        }
+        fn get(&mut self) -> Result<String, String> {
+            self.get_text().map_err(|e| e.to_string())
+        }
+    }
+
+    /// Programs that copy text from stdin and paste it to stdout
+    struct Programs {
+        copy: &'static [&'static str],
+        paste: &'static [&'static str],
+    }
+
+    impl Backend for Programs {
+        fn set(&mut self, text: String) -> Result<(), String> {
+            let mut child = Command::new(self.copy[0])
+                .args(&self.copy[1..])
+                .stdin(Stdio::piped())
+                .spawn()
+                .map_err(|e| format!("failed to run {}: {e}", self.copy[0]))?;
+            let written = child
+                .stdin
+                .take()
+                .map(|mut stdin| stdin.write_all(text.as_bytes()));
+            let status = child.wait().map_err(|e| e.to_string())?;
+            match written {
+                Some(Err(e)) => Err(e.to_string()),
+                _ if !status.success() => Err(format!("{} failed", self.copy[0])),
+                _ => Ok(()),
+            }
+        }
+        fn get(&mut self) -> Result<String, String> {
+            let output = Command::new(self.paste[0])
+                .args(&self.paste[1..])
+                .output()
+                .map_err(|e| format!("failed to run {}: {e}", self.paste[0]))?;
+            if output.status.success() {
+                Ok(String::from_utf8_lossy(&output.stdout).into())
+            } else {
+                Err(format!("{} failed", self.paste[0]))
+            }
+        }
+    }
+
+    /// A program that types text into the focused window
+    struct Typer {
+        command: &'static [&'static str],
+    }
+
+    impl Backend for Typer {
+        /// Type the text after a delay, which gives time to focus the window
+        /// it is typed into
+        fn set(&mut self, text: String) -> Result<(), String> {
+            let command = self.command;
+            thread::spawn(move || {
+                thread::sleep(TYPE_DELAY);
+                let _ = Command::new(command[0])
+                    .args(&command[1..])
+                    .arg(text)
+                    .status();
+            });
+            Ok(())
+        }
+        fn get(&mut self) -> Result<String, String> {
+            Err("typing into windows can't read the clipboard".into())
+        }
+        fn types(&self) -> bool {
+            true
+        }
+    }
+
+    fn is_wayland() -> bool {
+        env::var_os("WAYLAND_DISPLAY").is_some()
+    }
+
+    /// Whether a program can be found on the `PATH`
+    fn has_program(name: &str) -> bool {
+        env::var_os("PATH")
+            .is_some_and(|paths| env::split_paths(&paths).any(|dir| dir.join(name).is_file()))
+    }
+
+    fn connect(backend: ClipboardBackend) -> Result<Box<dyn Backend>, String> {
+        let require = |name: &str| {
+            has_program(name)
+                .then_some(())
+                .ok_or_else(|| format!("{name} is not installed"))
+        };
+        Ok(match backend {
+            ClipboardBackend::Auto | ClipboardBackend::System => {
+                Box::new(ClipboardContext::new().map_err(|e| e.to_string())?)
+            }
+            ClipboardBackend::Arboard => {
+                Box::new(arboard::Clipboard::new().map_err(|e| e.to_string())?)
+            }
+            ClipboardBackend::WlClipboard => {
+                require("wl-copy")?;
+                Box::new(Programs {
+                    copy: &["wl-copy"],
+                    paste: &["wl-paste", "--no-newline"],
+                })
+            }
+            ClipboardBackend::Xclip => {
+                require("xclip")?;
+                Box::new(Programs {
+                    copy: &["xclip", "-selection", "clipboard"],
+                    paste: &["xclip", "-selection", "clipboard", "-o"],
+                })
+            }
+            ClipboardBackend::Type if is_wayland() => {
+                require("wtype")?;
+                Box::new(Typer {
+                    command: &["wtype", "--"],
+                })
+            }
+            ClipboardBackend::Type => {
+                require("xdotool")?;
+                Box::new(Typer {
+                    command: &["xdotool", "type", "--delay", "1", "--"],
+                })
+            }
+        })
+    }
+
+    /// The clipboard, which is only connected to once because connecting can
+    /// fail or be slow on some X11 and Wayland setups
+    pub struct Clipboard {
+        backend: Result<Box<dyn Backend>, String>,
+    }
+
+    /// A clipboard that isn't connected, to be replaced by [`Clipboard::new`]
+    impl Default for Clipboard {
+        fn default() -> Self {
+            Clipboard {
+                backend: Err("the clipboard is not connected yet".into()),
+            }
+        }
+    }
+
+    impl Clipboard {
+        /// Connect to the clipboard, trying each backend in turn for
+        /// [`ClipboardBackend::Auto`]
+        pub fn new(backend: ClipboardBackend) -> Self {
+            let backends = match backend {
+                ClipboardBackend::Auto if is_wayland() => &[
+                    ClipboardBackend::WlClipboard,
+                    ClipboardBackend::Arboard,
+                    ClipboardBackend::System,
+                    ClipboardBackend::Xclip,
+                ][..],
+                ClipboardBackend::Auto => &[
+                    ClipboardBackend::System,
+                    ClipboardBackend::Arboard,
+                    ClipboardBackend::Xclip,
+                ],
+                _ => &[backend][..],
+            };
+            let mut first_error = None;
+            for &backend in backends {
+                match connect(backend) {
+                    Ok(backend) => {
+                        return Clipboard {
+                            backend: Ok(backend),
+                        }
+                    }
+                    Err(e) => {
+                        first_error.get_or_insert(e);
+                    }
+                }
+            }
+            Clipboard {
+                backend: Err(first_error.unwrap_or_default()),
+            }
+        }
+        /// Why the clipboard can't be used, if it can't
+        pub fn error(&self) -> Option<&str> {
+            self.backend.as_ref().err().map(String::as_str)
+        }
+        /// Whether copied text is typed into a window instead
+        pub fn types(&self) -> bool {
+            self.backend.as_ref().is_ok_and(|backend| backend.types())
+        }
+        pub fn set_contents(&mut self, text: String) -> Result<(), String> {
+            match &mut self.backend {
+                Ok(backend) => backend.set(text),
+                Err(e) => Err(e.clone()),
+            }
+        }
+        /// Copy text, printing it to stdout if the clipboard can't be used
+        pub fn copy(&mut self, _ctx: &Context, text: String) -> Result<(), String> {
+            self.set_contents(text.clone()).map_err(|e| {
+                println!("{text}");
+                format!("{e} (printed to stdout instead)")
+            })
+        }
+        pub fn paste(&mut self) -> Result<String, String> {
+            match &mut self.backend {
+                Ok(backend) => backend.get(),
+                Err(e) => Err(e.clone()),
+            }
+        }
+    }
+
+    #[cfg(all(test, unix))]
+    mod tests {
+        use std::os::unix::fs::PermissionsExt;
+
+        use super::*;
+
+        fn mode(path: &Path) -> u32 {
+            fs::metadata(path).unwrap().permissions().mode() & 0o777
+        }
+
+        #[test]
+        fn private_file_is_only_readable_by_the_user() {
+            let dir = env::temp_dir().join(format!("mjp-private-{}", process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            let path = dir.join("secrets.yaml");
+            write(&path, "old").unwrap();
+            write(&path, "older").unwrap();
+            assert!(backup_path(&path, 1).exists());
+            write_private(&path, "new").unwrap();
+            assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+            assert_eq!(mode(&path), 0o600);
+            assert!(!backup_path(&path, 1).exists());
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod web {
+    use std::{
+        io, iter,
+        path::{Path, PathBuf},
+    };
+
+    use eframe::egui::Context;
+
+    pub fn data_dir() -> Result<PathBuf, String> {
+        Ok(PathBuf::from("localStorage"))
+    }
+
+    fn local_storage() -> io::Result<web_sys::Storage> {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "localStorage is unavailable"))
+    }
+
+    /// The entry for a path in the data folder, which includes the folders
+    /// of projects
+    fn key(path: &Path) -> String {
+        let path = path.strip_prefix("localStorage").unwrap_or(path);
+        let path = path.to_string_lossy().replace('\\', "/");
+        format!("midjourney_prompt/{path}")
+    }
+
+    pub fn read(path: &Path) -> io::Result<Vec<u8>> {
+        match local_storage()?.get_item(&key(path)) {
+            Ok(Some(contents)) => Ok(contents.into_bytes()),
+            Ok(None) => Err(io::ErrorKind::NotFound.into()),
+            Err(_) => Err(io::Error::new(
+                io::ErrorKind::Other,
+                "error reading localStorage",
+            )),
+        }
+    }
+
+    /// `localStorage` writes can't be interrupted, so there are no backups
+    pub fn read_backups(_path: &Path) -> impl Iterator<Item = Vec<u8>> {
+        iter::empty()
+    }
+
+    pub fn write(path: &Path, contents: &str) -> io::Result<()> {
+        local_storage()?
+            .set_item(&key(path), contents)
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "error writing localStorage"))
+    }
+
+    pub fn write_private(path: &Path, contents: &str) -> io::Result<()> {
+        write(path, contents)
+    }
+
+    pub fn open_path(_path: &Path) {}
+
+    pub fn post_json(
+        _url: &str,
+        _authorization: Option<&str>,
+        _body: &str,
+    ) -> Result<String, String> {
+        Err("sending requests is not supported in the browser".into())
+    }
+
+    pub fn send(
+        _method: &str,
+        _url: &str,
+        _headers: &[(&str, &str)],
+        _body: &[u8],
+    ) -> Result<String, String> {
+        Err("sending requests is not supported in the browser".into())
+    }
+
+    pub fn get(_url: &str) -> Result<Vec<u8>, String> {
+        Err("downloading is not supported in the browser".into())
+    }
+
+    /// The browser's clipboard, accessed through egui
+    #[derive(Default)]
+    pub struct Clipboard;
+
+    impl Clipboard {
+        /// The browser's clipboard is always used
+        pub fn new(_backend: super::ClipboardBackend) -> Self {
+            Clipboard
+        }
+        pub fn error(&self) -> Option<&str> {
+            None
+        }
+        pub fn types(&self) -> bool {
+            false
+        }
+        pub fn copy(&mut self, ctx: &Context, text: String) -> Result<(), String> {
+            ctx.output().copied_text = text;
+            Ok(())
+        }
+        pub fn paste(&mut self) -> Result<String, String> {
+            Err("the browser doesn't allow reading the clipboard, \
+                so paste into the prompt instead"
+                .into())
+        }
+    }
+}
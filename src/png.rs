@@ -0,0 +1,128 @@
+//! Reading the prompts Stable Diffusion tools save in PNG files
+
+use std::collections::BTreeMap;
+
+use midjourney_prompt_core::sd::SdPrompt;
+use serde::{de::IgnoredAny, Deserialize};
+
+const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Read the prompt an image was generated with, from the metadata that
+/// AUTOMATIC1111 or ComfyUI saved in it
+pub fn read_prompt(bytes: &[u8]) -> Result<SdPrompt, String> {
+    let chunks = text_chunks(bytes)?;
+    if let Some(parameters) = chunks.get("parameters") {
+        Ok(SdPrompt::from_automatic1111(parameters))
+    } else if let Some(workflow) = chunks.get("prompt") {
+        comfyui_prompt(workflow)
+    } else {
+        Err("the image has no Stable Diffusion prompt".into())
+    }
+}
+
+/// The uncompressed text chunks of a PNG file, by keyword
+fn text_chunks(bytes: &[u8]) -> Result<BTreeMap<String, String>, String> {
+    let mut rest = bytes
+        .strip_prefix(SIGNATURE)
+        .ok_or("the file is not a PNG image")?;
+    let mut chunks = BTreeMap::new();
+    while rest.len() >= 12 {
+        let len = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+        let kind = &rest[4..8];
+        let Some(data) = rest.get(8..8 + len) else {
+            return Err("the image is cut off".into());
+        };
+        // Skip the CRC too
+        rest = rest.get(12 + len..).unwrap_or_default();
+        match kind {
+            b"tEXt" => {
+                if let Some((keyword, text)) = split_nul(data) {
+                    // tEXt is Latin-1
+                    let text = text.iter().map(|&b| char::from(b)).collect();
+                    chunks.insert(String::from_utf8_lossy(keyword).into(), text);
+                }
+            }
+            b"iTXt" => {
+                // Keyword, compression flag and method, language, and
+                // translated keyword come before the UTF-8 text
+                let Some((keyword, data)) = split_nul(data) else {
+                    continue;
+                };
+                let [0, _, data @ ..] = data else {
+                    continue;
+                };
+                let Some((_language, data)) = split_nul(data) else {
+                    continue;
+                };
+                let Some((_translated, text)) = split_nul(data) else {
+                    continue;
+                };
+                chunks.insert(
+                    String::from_utf8_lossy(keyword).into(),
+                    String::from_utf8_lossy(text).into(),
+                );
+            }
+            b"IEND" => break,
+            _ => {}
+        }
+    }
+    Ok(chunks)
+}
+
+fn split_nul(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    let i = data.iter().position(|&b| b == 0)?;
+    Some((&data[..i], &data[i + 1..]))
+}
+
+/// A node of a ComfyUI workflow in the API format
+#[derive(Deserialize)]
+struct Node {
+    class_type: String,
+    inputs: BTreeMap<String, Input>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Input {
+    /// The node ID and output index of another node's output
+    Link(String, u32),
+    Text(String),
+    Number(f64),
+    Other(IgnoredAny),
+}
+
+/// Find the prompt of the first sampler in a ComfyUI workflow
+fn comfyui_prompt(workflow: &str) -> Result<SdPrompt, String> {
+    let nodes: BTreeMap<String, Node> =
+        serde_json::from_str(workflow).map_err(|e| e.to_string())?;
+    let sampler = nodes
+        .values()
+        .find(|node| node.class_type.starts_with("KSampler"))
+        .ok_or("the workflow has no sampler")?;
+    // Text encoders and latent images have one output
+    let linked = |input: &str| match sampler.inputs.get(input) {
+        Some(Input::Link(id, 0)) => nodes.get(id),
+        _ => None,
+    };
+    let text = |input: &str| {
+        let node = linked(input)?;
+        match node.inputs.get("text").or(node.inputs.get("text_g")) {
+            Some(Input::Text(text)) => Some(text.clone()),
+            _ => None,
+        }
+    };
+    let number = |node: &Node, input: &str| match node.inputs.get(input) {
+        Some(Input::Number(n)) if *n >= 0.0 && *n <= f64::from(u32::MAX) => Some(*n as u32),
+        _ => None,
+    };
+    let latent = linked("latent_image");
+    Ok(SdPrompt {
+        positive: text("positive").ok_or("the workflow's prompt is not plain text")?,
+        negative: text("negative").unwrap_or_default(),
+        width: latent.and_then(|node| number(node, "width")).unwrap_or(512),
+        height: latent
+            .and_then(|node| number(node, "height"))
+            .unwrap_or(512),
+        seed: number(sampler, "seed").or_else(|| number(sampler, "noise_seed")),
+    })
+}
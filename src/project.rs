@@ -0,0 +1,70 @@
+//! Projects, which each keep their own prompt, presets, history, favorites,
+//! and snippet overrides in a folder of their own
+//!
+//! The default project uses the data folder itself, so data from before
+//! projects existed belongs to it.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// The current project, or `None` for the default one
+///
+/// This is global so that the data file paths can depend on it.
+static CURRENT: Mutex<Option<String>> = Mutex::new(None);
+
+/// The known projects and which one is open
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Projects {
+    /// Saved in current_project.yaml, which isn't synced so that each
+    /// machine can have a different project open
+    ///
+    /// This is still read from projects.yaml, where older versions saved it.
+    #[serde(default, skip_serializing)]
+    pub current: Option<String>,
+    #[serde(default)]
+    pub names: Vec<String>,
+}
+
+pub fn current() -> Option<String> {
+    CURRENT.lock().unwrap().clone()
+}
+
+pub fn set_current(name: Option<String>) {
+    *CURRENT.lock().unwrap() = name;
+}
+
+/// The folder of the current project's data files
+pub fn dir(base: &Path) -> PathBuf {
+    match current() {
+        Some(name) => base.join("projects").join(name),
+        None => base.to_path_buf(),
+    }
+}
+
+/// Check that a name can be used as a new project's folder name
+pub fn validate_name(name: &str, projects: &Projects) -> Result<(), String> {
+    check_name(name)?;
+    let taken = |other: &str| other.eq_ignore_ascii_case(name);
+    if taken("default") || projects.names.iter().any(|other| taken(other)) {
+        return Err(format!("there is already a project called {name}"));
+    }
+    Ok(())
+}
+
+/// Check that a name is a single folder name that is safe on every system
+pub fn check_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("the project needs a name".into());
+    }
+    if name.trim() != name || name.starts_with('.') {
+        return Err("project names can't start or end with spaces or start with a dot".into());
+    }
+    if name.contains(['/', '\\', ':', '*', '?', '"', '<', '>', '|']) {
+        return Err("project names can't contain / \\ : * ? \" < > |".into());
+    }
+    Ok(())
+}
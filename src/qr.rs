@@ -0,0 +1,41 @@
+//! Commands shown as QR codes, so they can be scanned with a phone
+
+use eframe::egui::*;
+use qrcode::QrCode;
+
+/// The size of the code, including the margin
+const SIZE: f32 = 300.0;
+/// The blank modules around the code that scanners need
+const MARGIN: usize = 4;
+
+/// Show text as a QR code
+pub fn show(ui: &mut Ui, text: &str) {
+    let code = match QrCode::new(text) {
+        Ok(code) => code,
+        Err(e) => {
+            ui.colored_label(
+                Color32::RED,
+                format!("no QR code can hold the command: {e}"),
+            );
+            return;
+        }
+    };
+    let width = code.width();
+    let module = (SIZE / (width + 2 * MARGIN) as f32).floor().max(1.0);
+    let size = module * (width + 2 * MARGIN) as f32;
+    let (rect, _) = ui.allocate_exact_size(Vec2::splat(size), Sense::hover());
+    let painter = ui.painter_at(rect);
+    // The code is always dark on light, which scanners expect
+    painter.rect_filled(rect, 0.0, Color32::WHITE);
+    for (i, color) in code.to_colors().into_iter().enumerate() {
+        if color == qrcode::Color::Dark {
+            let (x, y) = (i % width + MARGIN, i / width + MARGIN);
+            let min = rect.min + vec2(x as f32, y as f32) * module;
+            painter.rect_filled(
+                Rect::from_min_size(min, Vec2::splat(module)),
+                0.0,
+                Color32::BLACK,
+            );
+        }
+    }
+}
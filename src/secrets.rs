@@ -0,0 +1,24 @@
+//! Credentials, which are kept out of the prompt file so that they are never
+//! synced
+//!
+//! The settings that hold them still read them from the prompt file, where
+//! older versions saved them, but never write them there.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Secrets {
+    #[serde(default)]
+    pub discord_webhook: String,
+    #[serde(default)]
+    pub imgur_client_id: String,
+    #[serde(default)]
+    pub s3_access_key: String,
+    #[serde(default)]
+    pub s3_secret_key: String,
+    #[serde(default)]
+    pub translation_api_key: String,
+    #[cfg(feature = "llm")]
+    #[serde(default)]
+    pub llm_api_key: String,
+}
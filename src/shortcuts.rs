@@ -0,0 +1,130 @@
+//! Keyboard shortcuts, which are handled before any widgets see the input
+
+use eframe::egui::{Context, Event, InputState, Key, Modifiers};
+
+/// Something a shortcut does
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Copy,
+    NewTheme,
+    Save,
+    Undo,
+    Redo,
+    ZoomIn,
+    ZoomOut,
+    /// Load the preset at this index
+    Preset(usize),
+    /// Copy the clipboard slot at this index
+    CopySlot(usize),
+    ShowShortcuts,
+}
+
+/// What presses a shortcut
+#[derive(Clone, Copy)]
+pub enum Trigger {
+    Key(Key),
+    /// A typed character, for keys that egui has no [`Key`] for
+    ///
+    /// macOS doesn't send text while cmd is held, so these only work
+    /// elsewhere.
+    Text(&'static str),
+}
+
+pub struct Shortcut {
+    pub trigger: Trigger,
+    /// How the key combination is written in the shortcuts list
+    pub keys: &'static str,
+    pub action: Action,
+    pub description: &'static str,
+}
+
+impl Shortcut {
+    /// Remove the shortcut's key press from the input, returning whether
+    /// there was one
+    fn consume(&self, input: &mut InputState, modifiers: Modifiers) -> bool {
+        match self.trigger {
+            Trigger::Key(key) => input.consume_key(modifiers, key),
+            Trigger::Text(text) => {
+                if input.modifiers != modifiers {
+                    return false;
+                }
+                let len = input.events.len();
+                input
+                    .events
+                    .retain(|event| !matches!(event, Event::Text(t) if t == text));
+                input.events.len() != len
+            }
+        }
+    }
+}
+
+const fn shortcut(
+    key: Key,
+    keys: &'static str,
+    action: Action,
+    description: &'static str,
+) -> Shortcut {
+    Shortcut {
+        trigger: Trigger::Key(key),
+        keys,
+        action,
+        description,
+    }
+}
+
+/// Every shortcut, all of which are used with ctrl (or cmd on macOS)
+pub const SHORTCUTS: [Shortcut; 16] = [
+    shortcut(Key::Enter, "ctrl+enter", Action::Copy, "copy the command"),
+    shortcut(Key::N, "ctrl+n", Action::NewTheme, "add a theme"),
+    shortcut(Key::S, "ctrl+s", Action::Save, "save now"),
+    shortcut(Key::Z, "ctrl+z", Action::Undo, "undo"),
+    shortcut(Key::Y, "ctrl+y", Action::Redo, "redo"),
+    Shortcut {
+        trigger: Trigger::Text("="),
+        keys: "ctrl+=",
+        action: Action::ZoomIn,
+        description: "zoom in",
+    },
+    Shortcut {
+        trigger: Trigger::Text("-"),
+        keys: "ctrl+-",
+        action: Action::ZoomOut,
+        description: "zoom out",
+    },
+    shortcut(Key::Num1, "ctrl+1", Action::Preset(0), "load preset 1"),
+    shortcut(Key::Num2, "ctrl+2", Action::Preset(1), "load preset 2"),
+    shortcut(Key::Num3, "ctrl+3", Action::Preset(2), "load preset 3"),
+    shortcut(Key::Num4, "ctrl+4", Action::Preset(3), "load preset 4"),
+    shortcut(Key::Num5, "ctrl+5", Action::Preset(4), "load preset 5"),
+    shortcut(Key::Num6, "ctrl+6", Action::Preset(5), "load preset 6"),
+    shortcut(Key::Num7, "ctrl+7", Action::Preset(6), "load preset 7"),
+    shortcut(Key::Num8, "ctrl+8", Action::Preset(7), "load preset 8"),
+    shortcut(Key::Num9, "ctrl+9", Action::Preset(8), "load preset 9"),
+];
+
+/// The shortcut that opens the list of shortcuts, which doesn't need ctrl
+pub const HELP: Shortcut = shortcut(Key::F1, "f1", Action::ShowShortcuts, "list shortcuts");
+
+/// The shortcuts that copy clipboard slots, which don't need ctrl either
+pub const SLOTS: [Shortcut; 3] = [
+    shortcut(Key::F2, "f2", Action::CopySlot(0), "copy slot A"),
+    shortcut(Key::F3, "f3", Action::CopySlot(1), "copy slot B"),
+    shortcut(Key::F4, "f4", Action::CopySlot(2), "copy slot C"),
+];
+
+/// Take the shortcuts that were pressed this frame out of the input, so that
+/// text fields don't also handle them
+pub fn pressed(ctx: &Context) -> Vec<Action> {
+    let mut input = ctx.input_mut();
+    let mut actions: Vec<Action> = SHORTCUTS
+        .iter()
+        .filter(|shortcut| shortcut.consume(&mut input, Modifiers::COMMAND))
+        .map(|shortcut| shortcut.action)
+        .collect();
+    for shortcut in SLOTS.iter().chain([&HELP]) {
+        if shortcut.consume(&mut input, Modifiers::NONE) {
+            actions.push(shortcut.action);
+        }
+    }
+    actions
+}
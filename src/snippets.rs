@@ -0,0 +1,134 @@
+//! The library of prompt snippets, grouped by category
+
+use std::collections::BTreeMap;
+
+use rand::seq::SliceRandom;
+
+/// Snippets by category
+pub type Library = BTreeMap<String, Vec<String>>;
+
+/// The library used when there is no snippets file yet
+pub fn default_library() -> Library {
+    [
+        (
+            "subjects",
+            &[
+                "a lighthouse on a cliff",
+                "a fox in the snow",
+                "an abandoned space station",
+                "a bustling night market",
+                "a knight resting under a tree",
+                "a floating island city",
+            ][..],
+        ),
+        (
+            "lighting",
+            &[
+                "golden hour",
+                "volumetric lighting",
+                "rim lighting",
+                "soft studio lighting",
+                "neon glow",
+                "candlelight",
+            ],
+        ),
+        (
+            "artists",
+            &[
+                "in the style of Alphonse Mucha",
+                "in the style of Hokusai",
+                "in the style of Moebius",
+                "in the style of Studio Ghibli",
+            ],
+        ),
+        (
+            "mediums",
+            &[
+                "oil painting",
+                "watercolor",
+                "charcoal sketch",
+                "3d render",
+                "photograph",
+                "pixel art",
+            ],
+        ),
+        (
+            "camera",
+            &[
+                "35mm lens",
+                "85mm portrait lens",
+                "wide angle",
+                "macro",
+                "shallow depth of field",
+                "aerial view",
+            ],
+        ),
+        (
+            "moods",
+            &["serene", "ominous", "whimsical", "melancholic", "epic"],
+        ),
+    ]
+    .into_iter()
+    .map(|(category, snippets)| {
+        let snippets = snippets.iter().map(|&s| s.into()).collect();
+        (category.into(), snippets)
+    })
+    .collect()
+}
+
+/// Add the snippets from another library that this one doesn't have
+pub fn merge(library: &mut Library, other: Library) {
+    for (category, snippets) in other {
+        let existing = library.entry(category).or_default();
+        for snippet in snippets {
+            if !existing.contains(&snippet) {
+                existing.push(snippet);
+            }
+        }
+    }
+}
+
+/// The categories of a library that differ from the global library
+pub fn overrides(global: &Library, library: &Library) -> Library {
+    library
+        .iter()
+        .filter(|(category, snippets)| global.get(*category) != Some(*snippets))
+        .map(|(category, snippets)| (category.clone(), snippets.clone()))
+        .collect()
+}
+
+/// The global library with some categories replaced
+pub fn with_overrides(global: &Library, overrides: Library) -> Library {
+    let mut library = global.clone();
+    library.extend(overrides);
+    library
+}
+
+/// The template used for random prompts until it is changed
+pub fn default_template() -> String {
+    "{subjects}, {artists}, {lighting}, {mediums}".into()
+}
+
+/// Replace each `{category}` in a template with a random snippet from that
+/// category
+///
+/// Braces that don't name a category are left alone, so permutations still
+/// work.
+pub fn random_prompt(library: &Library, template: &str) -> String {
+    let mut rng = rand::thread_rng();
+    let mut prompt = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        prompt.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest.find('}').map_or(rest.len(), |i| i + 1);
+        let category = rest[1..end].trim_end_matches('}').trim();
+        match library.get(category).and_then(|s| s.choose(&mut rng)) {
+            Some(snippet) => prompt.push_str(snippet),
+            None => prompt.push_str(&rest[..end]),
+        }
+        rest = &rest[end..];
+    }
+    prompt.push_str(rest);
+    prompt
+}
@@ -0,0 +1,328 @@
+//! Syncing data files through a folder, like one kept by Dropbox or
+//! OneDrive, or through a Git repository
+//!
+//! The hash of each file is recorded when it is synced, so a file that
+//! changed both here and in the sync folder since then is a conflict.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::platform;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    #[default]
+    Off,
+    Folder,
+    Git,
+}
+
+impl Mode {
+    pub const ALL: [Mode; 3] = [Mode::Off, Mode::Folder, Mode::Git];
+    pub fn str(&self) -> &'static str {
+        match self {
+            Mode::Off => "off",
+            Mode::Folder => "folder",
+            Mode::Git => "Git repo",
+        }
+    }
+}
+
+/// What pulling changed
+#[derive(Default)]
+pub struct Pulled {
+    /// Files that were replaced by the synced ones
+    pub changed: Vec<String>,
+    /// Files that changed both here and in the sync folder
+    pub conflicts: Vec<String>,
+}
+
+/// The sync settings and the hashes of the files as of the last sync
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct State {
+    #[serde(default)]
+    pub mode: Mode,
+    #[serde(default)]
+    pub folder: String,
+    /// Hashes by path relative to the data folder
+    #[serde(default)]
+    hashes: BTreeMap<String, String>,
+}
+
+impl State {
+    pub fn is_on(&self) -> bool {
+        self.mode != Mode::Off && !self.folder.trim().is_empty()
+    }
+    fn folder(&self) -> PathBuf {
+        PathBuf::from(self.folder.trim())
+    }
+    /// Update a Git repo from its remote, if it has one
+    pub fn fetch(&self) -> Result<(), String> {
+        let folder = self.folder();
+        if self.mode == Mode::Git && remote(&folder).is_some() {
+            git(&folder, &["pull", "--ff-only"])?;
+        }
+        Ok(())
+    }
+    /// Bring in the files that only changed in the sync folder, adding what
+    /// changed to `pulled`
+    ///
+    /// `files` are relative to `base`, the data folder.
+    pub fn pull(
+        &mut self,
+        base: &Path,
+        files: &[String],
+        pulled: &mut Pulled,
+    ) -> Result<(), String> {
+        let folder = self.folder();
+        for file in files {
+            let Ok(synced) = fs::read(folder.join(file)) else {
+                continue;
+            };
+            let synced_hash = hash(&synced);
+            let base_hash = self.hashes.get(file);
+            if base_hash == Some(&synced_hash) {
+                continue;
+            }
+            let local_hash = fs::read(base.join(file)).ok().map(|local| hash(&local));
+            if local_hash.is_none() || local_hash.as_ref() == base_hash {
+                write(&base.join(file), &synced)?;
+                pulled.changed.push(file.clone());
+            } else if local_hash.as_ref() != Some(&synced_hash) {
+                pulled.conflicts.push(file.clone());
+                continue;
+            }
+            self.hashes.insert(file.clone(), synced_hash);
+        }
+        Ok(())
+    }
+    /// Copy the files that changed here to the sync folder, returning the
+    /// ones that also changed there
+    pub fn push(&mut self, base: &Path, files: &[String]) -> Result<Vec<String>, String> {
+        let folder = self.folder();
+        if self.mode == Mode::Git && !folder.join(".git").exists() {
+            fs::create_dir_all(&folder).map_err(|e| format!("failed to create the repo: {e}"))?;
+            git(&folder, &["init"])?;
+        }
+        let mut conflicts = Vec::new();
+        for file in files {
+            let Ok(local) = fs::read(base.join(file)) else {
+                continue;
+            };
+            let local_hash = hash(&local);
+            let base_hash = self.hashes.get(file);
+            let synced_hash = fs::read(folder.join(file)).ok().map(|synced| hash(&synced));
+            if synced_hash.as_ref() == Some(&local_hash) {
+                self.hashes.insert(file.clone(), local_hash);
+                continue;
+            }
+            if synced_hash.is_some() && synced_hash.as_ref() != base_hash {
+                conflicts.push(file.clone());
+                continue;
+            }
+            fs::create_dir_all(folder.join(file).parent().unwrap_or(&folder))
+                .and_then(|_| fs::write(folder.join(file), &local))
+                .map_err(|e| format!("failed to sync {file}: {e}"))?;
+            self.hashes.insert(file.clone(), local_hash);
+        }
+        if self.mode == Mode::Git {
+            if !git(&folder, &["status", "--porcelain"])?.is_empty() {
+                git(&folder, &["add", "-A"])?;
+                git(&folder, &["commit", "-m", "Update prompt data"])?;
+            }
+            // Commits are pushed until they reach the remote, so ones whose
+            // push failed go with the next one
+            if let Some(remote) = remote(&folder) {
+                match git(&folder, &["rev-list", "@{u}..HEAD"]) {
+                    Ok(ahead) if ahead.is_empty() => {}
+                    Ok(_) => {
+                        git(&folder, &["push"])?;
+                    }
+                    // The branch has no upstream yet
+                    Err(_) if git(&folder, &["rev-parse", "HEAD"]).is_ok() => {
+                        git(&folder, &["push", "-u", &remote, "HEAD"])?;
+                    }
+                    Err(_) => {}
+                }
+            }
+        }
+        Ok(conflicts)
+    }
+    /// Take the hashes that changed between `before` and `after`, copies of
+    /// this state from around a push that ran in the background, unless the
+    /// sync folder has changed since
+    pub fn merge(&mut self, before: &State, after: State) {
+        if self.mode != after.mode || self.folder != after.folder {
+            return;
+        }
+        for (file, hash) in after.hashes {
+            if before.hashes.get(&file) != Some(&hash) {
+                self.hashes.insert(file, hash);
+            }
+        }
+    }
+    /// Settle a conflict by taking either the synced file or the one here
+    ///
+    /// The file here is kept as a backup if it is replaced. A file that is
+    /// kept is copied to the sync folder on the next push.
+    pub fn resolve(&mut self, base: &Path, file: &str, use_synced: bool) -> Result<(), String> {
+        let synced = fs::read(self.folder().join(file))
+            .map_err(|e| format!("failed to read the synced {file}: {e}"))?;
+        if use_synced {
+            write(&base.join(file), &synced)?;
+        }
+        self.hashes.insert(file.into(), hash(&synced));
+        Ok(())
+    }
+}
+
+fn write(path: &Path, contents: &[u8]) -> Result<(), String> {
+    platform::write(path, &String::from_utf8_lossy(contents)).map_err(|e| {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        format!("failed to write the synced {name}: {e}")
+    })
+}
+
+fn hash(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+fn git(folder: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(folder)
+        .args(args)
+        .output()
+        .map_err(|e| format!("failed to run git: {e}"))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().into())
+    } else {
+        Err(format!(
+            "git {} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// The first remote of a Git repo, if it has any
+fn remote(folder: &Path) -> Option<String> {
+    let remotes = git(folder, &["remote"]).ok()?;
+    remotes.lines().next().map(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A data folder and a sync folder that have both been synced with
+    /// `a.yaml` containing "one"
+    fn synced(name: &str) -> (State, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("mjp-sync-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let base = dir.join("data");
+        let mut state = State {
+            mode: Mode::Folder,
+            folder: dir.join("synced").to_string_lossy().into(),
+            hashes: BTreeMap::new(),
+        };
+        fs::create_dir_all(&base).unwrap();
+        fs::write(base.join("a.yaml"), "one").unwrap();
+        assert!(state.push(&base, &files()).unwrap().is_empty());
+        (state, base)
+    }
+
+    fn files() -> Vec<String> {
+        vec!["a.yaml".into()]
+    }
+
+    fn read(path: PathBuf) -> String {
+        fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn local_change_is_pushed() {
+        let (mut state, base) = synced("local");
+        fs::write(base.join("a.yaml"), "two").unwrap();
+        let mut pulled = Pulled::default();
+        state.pull(&base, &files(), &mut pulled).unwrap();
+        assert!(pulled.changed.is_empty() && pulled.conflicts.is_empty());
+        assert_eq!(read(base.join("a.yaml")), "two");
+        assert!(state.push(&base, &files()).unwrap().is_empty());
+        assert_eq!(read(state.folder().join("a.yaml")), "two");
+        let _ = fs::remove_dir_all(base.parent().unwrap());
+    }
+
+    #[test]
+    fn synced_change_is_pulled() {
+        let (mut state, base) = synced("synced");
+        fs::write(state.folder().join("a.yaml"), "two").unwrap();
+        let mut pulled = Pulled::default();
+        state.pull(&base, &files(), &mut pulled).unwrap();
+        assert_eq!(pulled.changed, files());
+        assert!(pulled.conflicts.is_empty());
+        assert_eq!(read(base.join("a.yaml")), "two");
+        assert!(state.push(&base, &files()).unwrap().is_empty());
+        let _ = fs::remove_dir_all(base.parent().unwrap());
+    }
+
+    #[test]
+    fn change_in_both_is_a_conflict() {
+        let (mut state, base) = synced("both");
+        fs::write(base.join("a.yaml"), "two").unwrap();
+        fs::write(state.folder().join("a.yaml"), "three").unwrap();
+        let mut pulled = Pulled::default();
+        state.pull(&base, &files(), &mut pulled).unwrap();
+        assert!(pulled.changed.is_empty());
+        assert_eq!(pulled.conflicts, files());
+        assert_eq!(state.push(&base, &files()).unwrap(), files());
+        assert_eq!(read(base.join("a.yaml")), "two");
+        assert_eq!(read(state.folder().join("a.yaml")), "three");
+        state.resolve(&base, "a.yaml", true).unwrap();
+        assert_eq!(read(base.join("a.yaml")), "three");
+        let _ = fs::remove_dir_all(base.parent().unwrap());
+    }
+
+    #[test]
+    fn failed_git_push_is_retried() {
+        let dir = std::env::temp_dir().join(format!("mjp-sync-git-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let base = dir.join("data");
+        let repo = dir.join("synced");
+        let remote = dir.join("remote.git");
+        let mut state = State {
+            mode: Mode::Git,
+            folder: repo.to_string_lossy().into(),
+            hashes: BTreeMap::new(),
+        };
+        fs::create_dir_all(&base).unwrap();
+        fs::create_dir_all(&repo).unwrap();
+        fs::write(base.join("a.yaml"), "one").unwrap();
+        git(&repo, &["init"]).unwrap();
+        git(&repo, &["config", "user.name", "test"]).unwrap();
+        git(&repo, &["config", "user.email", "test@example.com"]).unwrap();
+        let remote_url = remote.to_string_lossy();
+        git(&repo, &["remote", "add", "origin", &remote_url]).unwrap();
+        // The remote doesn't exist yet, so the commit is made but not pushed
+        assert!(state.push(&base, &files()).is_err());
+        git(&dir, &["init", "--bare", &remote_url]).unwrap();
+        // Nothing changed here, but the commit is still pushed
+        assert!(state.push(&base, &files()).unwrap().is_empty());
+        assert_eq!(
+            git(&remote, &["rev-parse", "HEAD"]),
+            git(&repo, &["rev-parse", "HEAD"])
+        );
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,36 @@
+//! Work that runs in the background without blocking the UI
+
+use std::sync::mpsc;
+
+use eframe::egui::Context;
+
+/// A result being computed in the background
+pub struct Task<T> {
+    receiver: mpsc::Receiver<T>,
+}
+
+impl<T: Send + 'static> Task<T> {
+    /// Start running `f`, repainting the UI when it finishes
+    pub fn spawn(ctx: &Context, f: impl FnOnce() -> T + Send + 'static) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let ctx = ctx.clone();
+        let run = move || {
+            let _ = sender.send(f());
+            ctx.request_repaint();
+        };
+        // Threads aren't available on the web, where nothing spawned blocks
+        #[cfg(not(target_arch = "wasm32"))]
+        std::thread::spawn(run);
+        #[cfg(target_arch = "wasm32")]
+        run();
+        Task { receiver }
+    }
+    /// Get the result if it is ready
+    pub fn poll(&self) -> Option<T> {
+        self.receiver.try_recv().ok()
+    }
+    /// Block until the result is ready
+    pub fn wait(self) -> Option<T> {
+        self.receiver.recv().ok()
+    }
+}
@@ -0,0 +1,86 @@
+//! Thumbnails of the images at urls, downloaded and decoded in the background
+
+use std::collections::HashMap;
+
+use eframe::egui::{ColorImage, TextureFilter, TextureHandle, Ui};
+
+use crate::{platform, task::Task};
+
+/// The size images are shrunk to fit in before they become textures
+const MAX_SIZE: u32 = 256;
+
+/// How long a thumbnail is kept after it was last shown, in seconds
+const KEEP_SECS: f64 = 60.0;
+
+enum Thumbnail {
+    Loading(Task<Result<ColorImage, String>>),
+    Loaded(TextureHandle),
+    Failed(String),
+}
+
+struct Entry {
+    thumbnail: Thumbnail,
+    /// When the thumbnail was last shown
+    shown: f64,
+}
+
+/// Thumbnails by url, which are each downloaded once while they are in use
+#[derive(Default)]
+pub struct Thumbnails {
+    entries: HashMap<String, Entry>,
+}
+
+impl Thumbnails {
+    /// Show the thumbnail of an image at a height, starting to download it
+    /// the first time
+    pub fn show(&mut self, ui: &mut Ui, url: &str, height: f32) {
+        let url = url.trim();
+        let entry = self.entries.entry(url.into()).or_insert_with(|| {
+            let url = url.to_string();
+            Entry {
+                thumbnail: Thumbnail::Loading(Task::spawn(ui.ctx(), move || download(&url))),
+                shown: 0.0,
+            }
+        });
+        entry.shown = ui.input().time;
+        let thumbnail = &mut entry.thumbnail;
+        if let Thumbnail::Loading(task) = thumbnail {
+            if let Some(result) = task.poll() {
+                *thumbnail = match result {
+                    Ok(image) => {
+                        Thumbnail::Loaded(ui.ctx().load_texture(url, image, TextureFilter::Linear))
+                    }
+                    Err(e) => Thumbnail::Failed(e),
+                };
+            }
+        }
+        match thumbnail {
+            Thumbnail::Loading(_) => {
+                ui.spinner();
+            }
+            Thumbnail::Loaded(texture) => {
+                let size = texture.size_vec2();
+                ui.image(&*texture, size * (height / size.y));
+            }
+            Thumbnail::Failed(e) => {
+                ui.weak("no preview").on_hover_text(e.as_str());
+            }
+        }
+    }
+    /// Drop the thumbnails that haven't been shown for a while, so that
+    /// urls that are no longer used don't keep their textures
+    pub fn evict(&mut self, now: f64) {
+        self.entries
+            .retain(|_, entry| now - entry.shown < KEEP_SECS);
+    }
+}
+
+fn download(url: &str) -> Result<ColorImage, String> {
+    let bytes = platform::get(url)?;
+    let image = image::load_from_memory(&bytes)
+        .map_err(|e| e.to_string())?
+        .thumbnail(MAX_SIZE, MAX_SIZE)
+        .to_rgba8();
+    let size = [image.width() as usize, image.height() as usize];
+    Ok(ColorImage::from_rgba_unmultiplied(size, image.as_raw()))
+}
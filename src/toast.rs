@@ -0,0 +1,158 @@
+//! Short messages shown over the UI for a few seconds, with a history of
+//! past messages
+
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use eframe::egui::*;
+
+/// How long a toast is shown, in seconds
+const INFO_SECONDS: f64 = 3.0;
+/// Errors stay up longer so that there is time to read them
+const ERROR_SECONDS: f64 = 8.0;
+
+/// The number of past messages to keep
+const MAX_HISTORY: usize = 200;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Info,
+    Error,
+}
+
+/// A message that was shown
+pub struct Message {
+    pub time: DateTime<Local>,
+    pub level: Level,
+    pub text: String,
+}
+
+struct Toast {
+    level: Level,
+    text: String,
+    /// When the toast was first drawn, in egui's input time
+    shown_at: Option<f64>,
+}
+
+#[derive(Default)]
+pub struct Toasts {
+    toasts: Vec<Toast>,
+    history: Vec<Message>,
+}
+
+impl Toasts {
+    pub fn info(&mut self, text: impl Into<String>) {
+        self.add(Level::Info, text.into());
+    }
+    pub fn error(&mut self, text: impl Into<String>) {
+        self.add(Level::Error, text.into());
+    }
+    /// Show a success message, or the error prefixed with what failed
+    pub fn result(&mut self, result: Result<String, String>, failed: &str) {
+        match result {
+            Ok(message) => self.info(message),
+            Err(e) => self.error(format!("{failed}: {e}")),
+        }
+    }
+    fn add(&mut self, level: Level, text: String) {
+        // Copying on every change repeats the same message a lot, so only
+        // the latest of a run is kept
+        match self.history.last_mut() {
+            Some(last) if last.level == level && last.text == text => last.time = Local::now(),
+            _ => {
+                if self.history.len() == MAX_HISTORY {
+                    self.history.remove(0);
+                }
+                self.history.push(Message {
+                    time: Local::now(),
+                    level,
+                    text: text.clone(),
+                });
+            }
+        }
+        // Repeating a message restarts it rather than stacking a copy
+        self.toasts.retain(|toast| toast.text != text);
+        self.toasts.push(Toast {
+            level,
+            text,
+            shown_at: None,
+        });
+    }
+    /// Past messages, oldest first
+    pub fn history(&self) -> &[Message] {
+        &self.history
+    }
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+    /// Show the current toasts in the bottom right corner, newest at the
+    /// bottom
+    pub fn show(&mut self, ctx: &Context) {
+        let now = ctx.input().time;
+        self.toasts.retain(|toast| !toast.expired(now));
+        if self.toasts.is_empty() {
+            return;
+        }
+        Area::new("toasts")
+            .anchor(Align2::RIGHT_BOTTOM, [-10.0, -10.0])
+            .order(Order::Foreground)
+            .show(ctx, |ui| {
+                self.toasts.retain_mut(|toast| {
+                    toast.shown_at.get_or_insert(now);
+                    let response = Frame::popup(ui.style())
+                        .show(ui, |ui| match toast.level {
+                            Level::Info => ui.label(&toast.text),
+                            Level::Error => ui.colored_label(Color32::RED, &toast.text),
+                        })
+                        .response
+                        .interact(Sense::click())
+                        .on_hover_text("click to dismiss");
+                    !response.clicked()
+                });
+            });
+        let remaining = self
+            .toasts
+            .iter()
+            .filter_map(|toast| Some(toast.level.seconds() - (now - toast.shown_at?)))
+            .fold(f64::INFINITY, f64::min);
+        ctx.request_repaint_after(Duration::from_secs_f64(remaining.clamp(0.0, ERROR_SECONDS)));
+    }
+}
+
+impl Toast {
+    fn expired(&self, now: f64) -> bool {
+        self.shown_at
+            .is_some_and(|shown_at| now - shown_at >= self.level.seconds())
+    }
+}
+
+impl Level {
+    fn seconds(&self) -> f64 {
+        match self {
+            Level::Info => INFO_SECONDS,
+            Level::Error => ERROR_SECONDS,
+        }
+    }
+}
+
+/// Show past messages, newest first
+pub fn history_ui(ui: &mut Ui, toasts: &mut Toasts) {
+    if toasts.history().is_empty() {
+        ui.weak("no messages yet");
+        return;
+    }
+    if ui.button("clear").clicked() {
+        toasts.clear_history();
+        return;
+    }
+    Grid::new("messages").striped(true).show(ui, |ui| {
+        for message in toasts.history().iter().rev() {
+            ui.weak(message.time.format("%H:%M:%S").to_string());
+            match message.level {
+                Level::Info => ui.label(&message.text),
+                Level::Error => ui.colored_label(Color32::RED, &message.text),
+            };
+            ui.end_row();
+        }
+    });
+}
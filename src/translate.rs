@@ -0,0 +1,129 @@
+//! Translating prompts to English, which Midjourney understands best
+
+use serde::{Deserialize, Serialize};
+
+use crate::platform;
+
+const DEEPL_URL: &str = "https://api.deepl.com/v2/translate";
+/// Free DeepL keys end in `:fx` and use a different server
+const DEEPL_FREE_URL: &str = "https://api-free.deepl.com/v2/translate";
+
+/// The translation service to use
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    /// A LibreTranslate server, which may be self-hosted
+    #[default]
+    LibreTranslate,
+    DeepL,
+}
+
+impl Backend {
+    pub const ALL: [Backend; 2] = [Backend::LibreTranslate, Backend::DeepL];
+    pub fn str(&self) -> &'static str {
+        match self {
+            Backend::LibreTranslate => "LibreTranslate",
+            Backend::DeepL => "DeepL",
+        }
+    }
+}
+
+/// Where to send text to be translated
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Settings {
+    #[serde(default)]
+    pub backend: Backend,
+    /// The LibreTranslate server
+    #[serde(default)]
+    pub url: String,
+    /// Optional for LibreTranslate servers that don't require one, and saved
+    /// in secrets.yaml
+    #[serde(default, skip_serializing)]
+    pub api_key: String,
+}
+
+impl Settings {
+    /// Whether enough is set to translate
+    pub fn is_configured(&self) -> bool {
+        match self.backend {
+            Backend::LibreTranslate => !self.url.trim().is_empty(),
+            Backend::DeepL => !self.api_key.trim().is_empty(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct LibreRequest<'a> {
+    q: &'a str,
+    source: &'a str,
+    target: &'a str,
+    format: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    api_key: Option<&'a str>,
+}
+
+#[derive(Deserialize)]
+struct LibreResponse {
+    #[serde(rename = "translatedText")]
+    translated_text: String,
+}
+
+#[derive(Serialize)]
+struct DeepLRequest<'a> {
+    text: [&'a str; 1],
+    target_lang: &'a str,
+}
+
+#[derive(Deserialize)]
+struct DeepLResponse {
+    translations: Vec<DeepLTranslation>,
+}
+
+#[derive(Deserialize)]
+struct DeepLTranslation {
+    text: String,
+}
+
+/// Translate text from any language to English
+pub fn to_english(settings: &Settings, text: &str) -> Result<String, String> {
+    let api_key = settings.api_key.trim();
+    match settings.backend {
+        Backend::LibreTranslate => {
+            let url = format!("{}/translate", settings.url.trim().trim_end_matches('/'));
+            let body = serde_json::to_string(&LibreRequest {
+                q: text,
+                source: "auto",
+                target: "en",
+                format: "text",
+                api_key: (!api_key.is_empty()).then_some(api_key),
+            })
+            .map_err(|e| e.to_string())?;
+            let response = platform::post_json(&url, None, &body)?;
+            let response: LibreResponse =
+                serde_json::from_str(&response).map_err(|e| e.to_string())?;
+            Ok(response.translated_text)
+        }
+        Backend::DeepL => {
+            let url = if api_key.ends_with(":fx") {
+                DEEPL_FREE_URL
+            } else {
+                DEEPL_URL
+            };
+            let body = serde_json::to_string(&DeepLRequest {
+                text: [text],
+                target_lang: "EN",
+            })
+            .map_err(|e| e.to_string())?;
+            let authorization = format!("DeepL-Auth-Key {api_key}");
+            let response = platform::post_json(url, Some(&authorization), &body)?;
+            let response: DeepLResponse =
+                serde_json::from_str(&response).map_err(|e| e.to_string())?;
+            response
+                .translations
+                .into_iter()
+                .next()
+                .map(|translation| translation.text)
+                .ok_or_else(|| "the response has no translation".into())
+        }
+    }
+}
@@ -0,0 +1,379 @@
+//! A terminal frontend for editing the same prompt as the GUI
+
+use std::io;
+
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute,
+    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use midjourney_prompt_core::{Algorithm, Quality, Speed, Theme};
+use ratatui::{
+    backend::{Backend, CrosstermBackend},
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    Frame, Terminal,
+};
+
+use crate::Prompt;
+
+const HELP: &str = "tab: switch focus  ↑↓: select  ←→: change  space: toggle  \
+    enter: edit  d: delete  r: random seed  c: copy  esc: quit";
+
+/// Run the terminal frontend until the user quits
+pub fn run(prompt: &mut Prompt) -> io::Result<()> {
+    terminal::enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+    let result = Tui::new(prompt).run(&mut terminal);
+    terminal::disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    result
+}
+
+#[derive(Clone, Copy)]
+enum Field {
+    Algorithm,
+    Aspect,
+    Quality,
+    Speed,
+    Stylize,
+    Chaos,
+    Weird,
+    Seed,
+    Video,
+    Tile,
+    Theme(usize),
+    AddTheme,
+    Negative(usize),
+    AddNegative,
+}
+
+#[derive(PartialEq)]
+enum Focus {
+    Text,
+    Params,
+}
+
+struct Tui<'a> {
+    prompt: &'a mut Prompt,
+    focus: Focus,
+    selected: usize,
+    /// Whether the selected theme or negative is having its text edited
+    editing: bool,
+    status: String,
+}
+
+impl<'a> Tui<'a> {
+    fn new(prompt: &'a mut Prompt) -> Self {
+        Tui {
+            prompt,
+            focus: Focus::Text,
+            selected: 0,
+            editing: false,
+            status: String::new(),
+        }
+    }
+    fn run<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> io::Result<()> {
+        loop {
+            terminal.draw(|f| self.draw(f))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !self.handle_key(key) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+    fn fields(&self) -> Vec<Field> {
+        let params = &self.prompt.params;
+        let mut fields = vec![
+            Field::Algorithm,
+            Field::Aspect,
+            Field::Quality,
+            Field::Speed,
+            Field::Stylize,
+            Field::Chaos,
+            Field::Weird,
+            Field::Seed,
+            Field::Video,
+        ];
+        if params.algorithm.supports_tile() {
+            fields.push(Field::Tile);
+        }
+        fields.extend((0..params.themes.len()).map(Field::Theme));
+        fields.push(Field::AddTheme);
+        fields.extend((0..params.negatives.len()).map(Field::Negative));
+        fields.push(Field::AddNegative);
+        fields
+    }
+    fn field_text(&self, field: Field, editing: bool) -> String {
+        let params = &self.prompt.params;
+        let on_off = |b: bool| if b { "on" } else { "off" };
+        let cursor = if editing { "█" } else { "" };
+        match field {
+            Field::Algorithm => format!("algorithm  {}", params.algorithm),
+            Field::Aspect => format!("aspect     {}", params.aspect.aspect_string()),
+            Field::Quality => format!("quality    {}", params.quality),
+            Field::Speed => format!(
+                "speed      {}",
+                params.speed.map_or("none", |speed| speed.str())
+            ),
+            Field::Stylize => format!("stylize    {}", params.stylize),
+            Field::Chaos => format!("chaos      {}", params.chaos),
+            Field::Weird => format!("weird      {}", params.weird),
+            Field::Seed if params.use_seed => format!("seed       {}", params.seed),
+            Field::Seed => "seed       off".into(),
+            Field::Video => format!("video      {}", on_off(params.video)),
+            Field::Tile => format!("tile       {}", on_off(params.tile)),
+            Field::Theme(i) => {
+                let theme = &params.themes[i];
+                let check = if theme.enabled { "x" } else { " " };
+                format!("theme      [{check}] {}{cursor}", theme.name)
+            }
+            Field::AddTheme => "           + add theme".into(),
+            Field::Negative(i) => {
+                let (negative, enabled) = &params.negatives[i];
+                let check = if *enabled { "x" } else { " " };
+                format!("exclude    [{check}] {negative}{cursor}")
+            }
+            Field::AddNegative => "           + add exclusion".into(),
+        }
+    }
+    fn draw<B: Backend>(&self, f: &mut Frame<'_, B>) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(
+                [
+                    Constraint::Length(5),
+                    Constraint::Min(5),
+                    Constraint::Length(6),
+                    Constraint::Length(1),
+                ]
+                .as_ref(),
+            )
+            .split(f.size());
+        let block = |title: &'static str, focused: bool| {
+            let style = if focused {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default()
+            };
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(style)
+        };
+        // Prompt text
+        let mut text = self.prompt.text.clone();
+        if self.focus == Focus::Text {
+            text.push('█');
+        }
+        let text = Paragraph::new(text)
+            .block(block("prompt", self.focus == Focus::Text))
+            .wrap(Wrap { trim: false });
+        f.render_widget(text, chunks[0]);
+        // Parameters
+        let items: Vec<ListItem> = self
+            .fields()
+            .into_iter()
+            .enumerate()
+            .map(|(i, field)| {
+                ListItem::new(self.field_text(field, self.editing && i == self.selected))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(block("parameters", self.focus == Focus::Params))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let mut state = ListState::default();
+        if self.focus == Focus::Params {
+            state.select(Some(self.selected));
+        }
+        f.render_stateful_widget(list, chunks[1], &mut state);
+        // Command
+        let command = Paragraph::new(self.prompt.command().to_string())
+            .block(block("command", false))
+            .wrap(Wrap { trim: false });
+        f.render_widget(command, chunks[2]);
+        // Status
+        let status = if self.status.is_empty() {
+            HELP
+        } else {
+            &self.status
+        };
+        f.render_widget(Paragraph::new(status), chunks[3]);
+    }
+    /// Handle a key press, returning whether to keep running
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return false;
+        }
+        self.status.clear();
+        if self.editing {
+            self.edit_key(key.code);
+            return true;
+        }
+        match (key.code, &self.focus) {
+            (KeyCode::Esc, _) => return false,
+            (KeyCode::Tab, Focus::Text) => self.focus = Focus::Params,
+            (KeyCode::Tab, Focus::Params) => self.focus = Focus::Text,
+            (KeyCode::Char(c), Focus::Text) => self.prompt.text.push(c),
+            (KeyCode::Backspace, Focus::Text) => {
+                self.prompt.text.pop();
+            }
+            (KeyCode::Up | KeyCode::Char('k'), Focus::Params) => {
+                self.selected = self.selected.saturating_sub(1);
+            }
+            (KeyCode::Down | KeyCode::Char('j'), Focus::Params) => {
+                self.selected = (self.selected + 1).min(self.fields().len() - 1);
+            }
+            (KeyCode::Left | KeyCode::Char('h'), Focus::Params) => self.adjust(-1),
+            (KeyCode::Right | KeyCode::Char('l'), Focus::Params) => self.adjust(1),
+            (KeyCode::Char(' '), Focus::Params) => self.toggle(),
+            (KeyCode::Enter, Focus::Params) => self.start_editing(),
+            (KeyCode::Char('d') | KeyCode::Delete, Focus::Params) => self.delete(),
+            (KeyCode::Char('r'), Focus::Params) => {
+                self.prompt.params.use_seed = true;
+                self.prompt.params.seed = rand::random();
+            }
+            (KeyCode::Char('c'), Focus::Params) => self.copy(),
+            _ => {}
+        }
+        true
+    }
+    fn selected_field(&self) -> Field {
+        let fields = self.fields();
+        fields[self.selected.min(fields.len() - 1)]
+    }
+    fn adjust(&mut self, delta: i32) {
+        let field = self.selected_field();
+        let params = &mut self.prompt.params;
+        match field {
+            Field::Algorithm => {
+                params.set_algorithm(cycle(&Algorithm::ALL, params.algorithm, delta))
+            }
+            Field::Aspect => {
+                params.aspect = cycle(params.algorithm.allowed_aspects(), params.aspect, delta);
+            }
+            Field::Quality => params.quality = cycle(&Quality::ALL, params.quality, delta),
+            Field::Speed => {
+                let speeds = [
+                    None,
+                    Some(Speed::Fast),
+                    Some(Speed::Relax),
+                    Some(Speed::Turbo),
+                ];
+                params.speed = cycle(&speeds, params.speed, delta);
+            }
+            Field::Stylize => {
+                let range = params.algorithm.stylize_range();
+                let step = ((range.end() - range.start()) / 100).max(1);
+                params.stylize = step_by(params.stylize, step, delta, *range.start(), *range.end());
+            }
+            Field::Chaos => params.chaos = step_by(params.chaos, 5, delta, 0, 100),
+            Field::Weird => params.weird = step_by(params.weird, 50, delta, 0, 3000),
+            Field::Seed => {
+                params.use_seed = true;
+                params.seed = step_by(params.seed, 1, delta, 0, u32::MAX);
+            }
+            _ => self.toggle(),
+        }
+    }
+    fn toggle(&mut self) {
+        let field = self.selected_field();
+        let params = &mut self.prompt.params;
+        match field {
+            Field::Seed => params.use_seed = !params.use_seed,
+            Field::Video => params.video = !params.video,
+            Field::Tile => params.tile = !params.tile,
+            Field::Theme(i) => params.themes[i].enabled = !params.themes[i].enabled,
+            Field::Negative(i) => params.negatives[i].1 = !params.negatives[i].1,
+            _ => {}
+        }
+    }
+    fn start_editing(&mut self) {
+        let field = self.selected_field();
+        let params = &mut self.prompt.params;
+        match field {
+            Field::Theme(_) | Field::Negative(_) => {}
+            Field::AddTheme => params.themes.push(Theme::new("", true)),
+            Field::AddNegative => params.negatives.push((String::new(), true)),
+            _ => return,
+        }
+        // Adding pushes a new entry right where the add field was
+        self.editing = true;
+    }
+    fn edit_key(&mut self, code: KeyCode) {
+        let item = match self.selected_field() {
+            Field::Theme(i) => &mut self.prompt.params.themes[i].name,
+            Field::Negative(i) => &mut self.prompt.params.negatives[i].0,
+            _ => {
+                self.editing = false;
+                return;
+            }
+        };
+        match code {
+            KeyCode::Char(c) => item.push(c),
+            KeyCode::Backspace => {
+                item.pop();
+            }
+            KeyCode::Enter | KeyCode::Esc | KeyCode::Tab => {
+                self.editing = false;
+                if item.trim().is_empty() {
+                    self.delete();
+                }
+            }
+            _ => {}
+        }
+    }
+    fn delete(&mut self) {
+        let field = self.selected_field();
+        let params = &mut self.prompt.params;
+        match field {
+            Field::Theme(i) => {
+                params.themes.remove(i);
+            }
+            Field::Negative(i) => {
+                params.negatives.remove(i);
+            }
+            _ => {}
+        }
+        self.selected = self.selected.min(self.fields().len() - 1);
+    }
+    fn copy(&mut self) {
+        if self.prompt.text.trim().is_empty() {
+            self.status = "the prompt is empty".into();
+            return;
+        }
+        if self.prompt.copy_blocked() {
+            self.status = "the command is too long for Midjourney".into();
+            return;
+        }
+        let command = self.prompt.copied_command();
+        self.status = match self.prompt.clipboard.set_contents(command) {
+            Ok(()) => {
+                self.prompt.remember_seed();
+                self.prompt.record_history();
+                format!("copied {}", self.prompt.copy_format)
+            }
+            Err(e) => format!("error copying command: {e}"),
+        };
+    }
+}
+
+/// Move to the next or previous value in a list, wrapping around
+fn cycle<T: Copy + PartialEq>(all: &[T], curr: T, delta: i32) -> T {
+    let i = all.iter().position(|&item| item == curr).unwrap_or(0) as i32;
+    let len = all.len() as i32;
+    all[(i + delta).rem_euclid(len) as usize]
+}
+
+fn step_by(value: u32, step: u32, delta: i32, min: u32, max: u32) -> u32 {
+    if delta < 0 {
+        value.saturating_sub(step).max(min)
+    } else {
+        value.saturating_add(step).min(max)
+    }
+}
@@ -0,0 +1,59 @@
+//! Undo and redo of whole-state snapshots
+
+/// The most steps that can be undone
+const MAX_STEPS: usize = 100;
+/// Changes closer together than this many seconds are merged into one step
+const MERGE_SECONDS: f64 = 1.0;
+
+/// Undo and redo stacks of snapshots of some state
+pub struct Undo<T> {
+    undo: Vec<T>,
+    redo: Vec<T>,
+    last_change: f64,
+}
+
+impl<T> Default for Undo<T> {
+    fn default() -> Self {
+        Undo {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            last_change: f64::NEG_INFINITY,
+        }
+    }
+}
+
+impl<T> Undo<T> {
+    /// Record that the state was changed from `before` at time `now`
+    pub fn record(&mut self, before: T, now: f64) {
+        if now - self.last_change > MERGE_SECONDS {
+            self.undo.push(before);
+            if self.undo.len() > MAX_STEPS {
+                self.undo.remove(0);
+            }
+        }
+        self.redo.clear();
+        self.last_change = now;
+    }
+    /// Revert `curr` to the previous snapshot
+    ///
+    /// Returns whether there was anything to undo
+    pub fn undo(&mut self, curr: &mut T) -> bool {
+        Self::step(&mut self.undo, &mut self.redo, curr, &mut self.last_change)
+    }
+    /// Reapply the most recently undone snapshot to `curr`
+    ///
+    /// Returns whether there was anything to redo
+    pub fn redo(&mut self, curr: &mut T) -> bool {
+        Self::step(&mut self.redo, &mut self.undo, curr, &mut self.last_change)
+    }
+    fn step(from: &mut Vec<T>, to: &mut Vec<T>, curr: &mut T, last_change: &mut f64) -> bool {
+        let snapshot = match from.pop() {
+            Some(snapshot) => snapshot,
+            None => return false,
+        };
+        to.push(std::mem::replace(curr, snapshot));
+        // The next change should not be merged into the step we just moved
+        *last_change = f64::NEG_INFINITY;
+        true
+    }
+}
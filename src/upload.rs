@@ -0,0 +1,206 @@
+//! Uploading dropped images to get urls that can be used as image prompts
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::platform;
+
+const IMGUR_URL: &str = "https://api.imgur.com/3/image";
+
+/// Where images are uploaded
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    #[default]
+    None,
+    Imgur,
+    S3,
+}
+
+impl Backend {
+    pub const ALL: [Backend; 3] = [Backend::None, Backend::Imgur, Backend::S3];
+    pub fn str(&self) -> &'static str {
+        match self {
+            Backend::None => "none",
+            Backend::Imgur => "Imgur",
+            Backend::S3 => "S3",
+        }
+    }
+}
+
+/// The upload service and its credentials
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Settings {
+    #[serde(default)]
+    pub backend: Backend,
+    /// Saved in secrets.yaml
+    #[serde(default, skip_serializing)]
+    pub imgur_client_id: String,
+    #[serde(default)]
+    pub bucket: String,
+    #[serde(default)]
+    pub region: String,
+    /// Saved in secrets.yaml
+    #[serde(default, skip_serializing)]
+    pub access_key: String,
+    /// Saved in secrets.yaml
+    #[serde(default, skip_serializing)]
+    pub secret_key: String,
+    /// Where uploaded objects can be viewed, if not at the bucket's own url,
+    /// like a CDN in front of the bucket
+    #[serde(default)]
+    pub public_url: String,
+}
+
+impl Settings {
+    /// Whether enough is set to upload
+    pub fn is_configured(&self) -> bool {
+        match self.backend {
+            Backend::None => false,
+            Backend::Imgur => !self.imgur_client_id.trim().is_empty(),
+            Backend::S3 => [
+                &self.bucket,
+                &self.region,
+                &self.access_key,
+                &self.secret_key,
+            ]
+            .iter()
+            .all(|s| !s.trim().is_empty()),
+        }
+    }
+}
+
+/// The type of an image from its first bytes
+pub fn content_type(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG") {
+        Some("image/png")
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"GIF8") {
+        Some("image/gif")
+    } else if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(&b"WEBP"[..]) {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+/// Upload an image, returning its public url
+pub fn upload(settings: &Settings, name: &str, bytes: &[u8]) -> Result<String, String> {
+    let content_type = content_type(bytes).ok_or("the file is not a supported image")?;
+    match settings.backend {
+        Backend::None => Err("no upload service is set up".into()),
+        Backend::Imgur => upload_imgur(settings, name, content_type, bytes),
+        Backend::S3 => upload_s3(settings, name, content_type, bytes),
+    }
+}
+
+#[derive(Deserialize)]
+struct ImgurResponse {
+    data: ImgurImage,
+}
+
+#[derive(Deserialize)]
+struct ImgurImage {
+    link: String,
+}
+
+fn upload_imgur(
+    settings: &Settings,
+    name: &str,
+    content_type: &str,
+    bytes: &[u8],
+) -> Result<String, String> {
+    let boundary = format!("mjp-{}", Utc::now().timestamp_millis());
+    let name = name.replace(['"', '\r', '\n'], "");
+    let mut body = format!(
+        "--{boundary}\r\n\
+         Content-Disposition: form-data; name=\"image\"; filename=\"{name}\"\r\n\
+         Content-Type: {content_type}\r\n\r\n"
+    )
+    .into_bytes();
+    body.extend_from_slice(bytes);
+    body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+    let authorization = format!("Client-ID {}", settings.imgur_client_id.trim());
+    let multipart = format!("multipart/form-data; boundary={boundary}");
+    let headers = [
+        ("Authorization", authorization.as_str()),
+        ("Content-Type", multipart.as_str()),
+    ];
+    let response = platform::send("POST", IMGUR_URL, &headers, &body)?;
+    let response: ImgurResponse = serde_json::from_str(&response).map_err(|e| e.to_string())?;
+    Ok(response.data.link)
+}
+
+/// Upload with a request signed with AWS Signature Version 4
+fn upload_s3(
+    settings: &Settings,
+    name: &str,
+    content_type: &str,
+    bytes: &[u8],
+) -> Result<String, String> {
+    let bucket = settings.bucket.trim();
+    let region = settings.region.trim();
+    let now = Utc::now();
+    let date = now.format("%Y%m%d").to_string();
+    let time = now.format("%Y%m%dT%H%M%SZ").to_string();
+    // Only characters that need no escaping are kept, so the key can be
+    // signed as it is
+    let name: String = name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => c,
+            _ => '-',
+        })
+        .collect();
+    let key = format!("mjp/{}-{name}", now.format("%Y%m%d%H%M%S"));
+    let host = format!("{bucket}.s3.{region}.amazonaws.com");
+    let payload_hash = hex(&Sha256::digest(bytes));
+    let signed_headers = "content-type;host;x-amz-content-sha256;x-amz-date";
+    let canonical_request = format!(
+        "PUT\n/{key}\n\n\
+         content-type:{content_type}\nhost:{host}\n\
+         x-amz-content-sha256:{payload_hash}\nx-amz-date:{time}\n\n\
+         {signed_headers}\n{payload_hash}"
+    );
+    let scope = format!("{date}/{region}/s3/aws4_request");
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{time}\n{scope}\n{}",
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+    let secret = format!("AWS4{}", settings.secret_key.trim());
+    let mut signing_key = secret.into_bytes();
+    for part in [date.as_str(), region, "s3", "aws4_request"] {
+        signing_key = sign(&signing_key, part.as_bytes());
+    }
+    let signature = hex(&sign(&signing_key, string_to_sign.as_bytes()));
+    let authorization = format!(
+        "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, \
+         Signature={signature}",
+        settings.access_key.trim()
+    );
+    let headers = [
+        ("Authorization", authorization.as_str()),
+        ("Content-Type", content_type),
+        ("x-amz-content-sha256", payload_hash.as_str()),
+        ("x-amz-date", time.as_str()),
+    ];
+    platform::send("PUT", &format!("https://{host}/{key}"), &headers, bytes)?;
+    let public_url = match settings.public_url.trim().trim_end_matches('/') {
+        "" => format!("https://{host}"),
+        url => url.into(),
+    };
+    Ok(format!("{public_url}/{key}"))
+}
+
+fn sign(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("any key length is valid");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}